@@ -972,6 +972,23 @@ int main(int argc, char *argv[]) {
         signal_handler_connect_ref(output_signals, "stop", output_stop_cb, NULL);
     }
 
+    // Open the encoders before starting the output, so a driver or hardware failure is
+    // reported apart from output errors (bad path, RTMP) and the caller can pick another encoder
+    if (!obs_output_initialize_encoders(output, 0)) {
+        const char* error = obs_output_get_last_error(output);
+        std::cerr << "Failed to initialise encoder: " << (encoder_id ? encoder_id : args.encoder.c_str())
+                  << ": " << (error ? error : "unknown error") << std::endl;
+        obs_output_release(output);
+        obs_encoder_release(v_encoder);
+        obs_encoder_release(a_encoder);
+        clear_output_sources();
+        obs_scene_release(scene);
+        if (service) {
+            obs_service_release(service);
+        }
+        return -1;
+    }
+
     // Start
     if (!obs_output_start(output)) {
         std::cerr << "Failed to start output: " << obs_output_get_last_error(output) << std::endl;
@@ -10,7 +10,8 @@ CREATE TABLE IF NOT EXISTS users (
     username VARCHAR(255) NOT NULL UNIQUE,
     password_hash VARCHAR(255) NOT NULL,
     role VARCHAR(50) NOT NULL DEFAULT 'user',
-    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    storage_quota_bytes BIGINT
 );
 
 CREATE TABLE IF NOT EXISTS system_config (
@@ -30,7 +31,12 @@ CREATE TABLE IF NOT EXISTS user_configs (
     rtmp_key VARCHAR(255),
     capture_mode VARCHAR(20),
     capture_method VARCHAR(20),
-    window_id VARCHAR(512)
+    window_id VARCHAR(512),
+    window_exe VARCHAR(255),
+    window_class VARCHAR(255),
+    window_title_pattern VARCHAR(512),
+    video_encoders TEXT[],
+    scale_mode VARCHAR(20)
 );
 
 CREATE TABLE IF NOT EXISTS announcements (
@@ -53,11 +59,147 @@ CREATE TABLE IF NOT EXISTS recordings (
     filename VARCHAR(255) NOT NULL,
     filepath VARCHAR(1024) NOT NULL,
     status VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    size_bytes BIGINT,
+    duration_secs DOUBLE PRECISION,
+    video_codec VARCHAR(64),
+    width INT,
+    height INT,
+    frame_rate DOUBLE PRECISION,
+    audio_tracks INT,
+    is_complete BOOLEAN,
+    probed_at TIMESTAMP WITH TIME ZONE,
+    pinned BOOLEAN NOT NULL DEFAULT FALSE,
+    capture_mode VARCHAR(20),
+    deleted_at TIMESTAMP WITH TIME ZONE,
+    trash_path VARCHAR(1024),
+    archive_state VARCHAR(20),
+    archive_backend VARCHAR(20),
+    archive_key VARCHAR(1024),
+    archived_at TIMESTAMP WITH TIME ZONE,
+    archive_error TEXT,
+    local_copy BOOLEAN NOT NULL DEFAULT TRUE,
+    thumbnail_path VARCHAR(1024),
+    source_recording_id UUID REFERENCES recordings(id) ON DELETE SET NULL,
+    clip_start_secs DOUBLE PRECISION,
+    clip_end_secs DOUBLE PRECISION,
+    sha256 VARCHAR(64),
+    sha256_at TIMESTAMP WITH TIME ZONE,
+    integrity_state VARCHAR(20),
+    integrity_checked_at TIMESTAMP WITH TIME ZONE,
+    status_changed_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Every status change of a recording; from_status is NULL for the row's first status
+CREATE TABLE IF NOT EXISTS recording_status_history (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    recording_id UUID NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+    from_status VARCHAR(50),
+    to_status VARCHAR(50) NOT NULL,
+    reason TEXT,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_recording_status_history_recording ON recording_status_history(recording_id, created_at);
+
+-- Points flagged during a recording, as seconds from the start of the file
+CREATE TABLE IF NOT EXISTS recording_markers (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    recording_id UUID NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    offset_secs DOUBLE PRECISION NOT NULL,
+    label VARCHAR(200),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_recording_markers_recording ON recording_markers(recording_id, offset_secs);
+
+CREATE TABLE IF NOT EXISTS recording_shares (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    recording_id UUID NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+    created_by UUID REFERENCES users(id) ON DELETE CASCADE,
+    token VARCHAR(64) NOT NULL UNIQUE,
+    password_hash VARCHAR(255),
+    expires_at TIMESTAMP WITH TIME ZONE,
+    max_downloads INT,
+    download_count INT NOT NULL DEFAULT 0,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    -- QR hand-off links are minted on demand; they are reused while valid and pruned once expired
+    handoff BOOLEAN NOT NULL DEFAULT FALSE,
     created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
 );
 
+CREATE INDEX IF NOT EXISTS idx_recording_shares_recording ON recording_shares(recording_id);
+
+CREATE TABLE IF NOT EXISTS download_tokens (
+    token VARCHAR(64) PRIMARY KEY,
+    recording_id UUID NOT NULL REFERENCES recordings(id) ON DELETE CASCADE,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    -- 'download' tokens are single-use and need the bearer; 'playback' tokens are short-lived
+    -- and reusable so a <video> element can issue range requests without headers
+    purpose VARCHAR(16) NOT NULL DEFAULT 'download',
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX IF NOT EXISTS idx_download_tokens_recording ON download_tokens(recording_id);
+CREATE INDEX IF NOT EXISTS idx_download_tokens_expires ON download_tokens(expires_at);
+
+-- Post-recording processing. Steps of one pipeline share pipeline_id and run in step_index order.
+CREATE TABLE IF NOT EXISTS jobs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    recording_id UUID REFERENCES recordings(id) ON DELETE CASCADE,
+    user_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    pipeline_id UUID NOT NULL,
+    step_index INT NOT NULL DEFAULT 0,
+    kind VARCHAR(32) NOT NULL,
+    params JSONB NOT NULL DEFAULT '{}',
+    status VARCHAR(20) NOT NULL DEFAULT 'queued',
+    progress REAL NOT NULL DEFAULT 0,
+    attempts INT NOT NULL DEFAULT 0,
+    max_attempts INT NOT NULL DEFAULT 3,
+    cancel_requested BOOLEAN NOT NULL DEFAULT FALSE,
+    log TEXT NOT NULL DEFAULT '',
+    error TEXT,
+    result JSONB,
+    run_after TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_jobs_queue ON jobs(status, run_after);
+CREATE INDEX IF NOT EXISTS idx_jobs_recording ON jobs(recording_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_jobs_pipeline ON jobs(pipeline_id, step_index);
+
+-- Resumable uploads in progress; bytes land in .uploads/ under the recording root
+CREATE TABLE IF NOT EXISTS uploads (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    filename VARCHAR(1024) NOT NULL,
+    upload_length BIGINT NOT NULL,
+    upload_offset BIGINT NOT NULL DEFAULT 0,
+    recording_id UUID REFERENCES recordings(id) ON DELETE SET NULL,
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    completed_at TIMESTAMP WITH TIME ZONE
+);
+
+CREATE INDEX IF NOT EXISTS idx_uploads_user ON uploads(user_id, created_at DESC);
+
 -- ===== Ensure all columns exist (for databases created before schema updates) =====
 
+-- users columns
+ALTER TABLE users ADD COLUMN IF NOT EXISTS storage_quota_bytes BIGINT;
+
+-- download_tokens columns
+ALTER TABLE download_tokens ADD COLUMN IF NOT EXISTS purpose VARCHAR(16) NOT NULL DEFAULT 'download';
+
+-- recording_shares columns
+ALTER TABLE recording_shares ADD COLUMN IF NOT EXISTS handoff BOOLEAN NOT NULL DEFAULT FALSE;
+
 -- user_configs columns
 ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS max_bitrate INT;
 ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS max_fps INT;
@@ -70,6 +212,52 @@ ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS rtmp_key VARCHAR(255);
 ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS capture_mode VARCHAR(20);
 ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS capture_method VARCHAR(20);
 ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS window_id VARCHAR(512);
+ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS window_exe VARCHAR(255);
+ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS window_class VARCHAR(255);
+ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS window_title_pattern VARCHAR(512);
+ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS video_encoders TEXT[];
+ALTER TABLE user_configs ADD COLUMN IF NOT EXISTS scale_mode VARCHAR(20);
+
+-- recordings columns
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS size_bytes BIGINT;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS duration_secs DOUBLE PRECISION;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS video_codec VARCHAR(64);
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS width INT;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS height INT;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS frame_rate DOUBLE PRECISION;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS audio_tracks INT;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS is_complete BOOLEAN;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS probed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS pinned BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS capture_mode VARCHAR(20);
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS trash_path VARCHAR(1024);
+-- archive_state: NULL (local only), uploading, archived, failed
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS archive_state VARCHAR(20);
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS archive_backend VARCHAR(20);
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS archive_key VARCHAR(1024);
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS archived_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS archive_error TEXT;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS local_copy BOOLEAN NOT NULL DEFAULT TRUE;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS thumbnail_path VARCHAR(1024);
+-- Clips point back at the recording they were cut from
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS source_recording_id UUID REFERENCES recordings(id) ON DELETE SET NULL;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS clip_start_secs DOUBLE PRECISION;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS clip_end_secs DOUBLE PRECISION;
+-- Hash taken when the recording was finalized; verification compares against it
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS sha256 VARCHAR(64);
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS sha256_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS integrity_state VARCHAR(20);
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS integrity_checked_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE recordings ADD COLUMN IF NOT EXISTS status_changed_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP;
+-- Statuses from before the state machine: stopped becomes saved or incomplete,
+-- and trashed rows are marked deleted
+UPDATE recordings SET status = CASE WHEN is_complete = FALSE THEN 'incomplete' ELSE 'saved' END
+    WHERE status = 'stopped';
+UPDATE recordings SET status = 'deleted' WHERE deleted_at IS NOT NULL AND status <> 'deleted';
+
+CREATE INDEX IF NOT EXISTS idx_recordings_created ON recordings(created_at DESC, id DESC);
+CREATE INDEX IF NOT EXISTS idx_recordings_user_created ON recordings(user_id, created_at DESC, id DESC);
 
 -- Widen monitor_id for Windows device interface paths (e.g. \\?\DISPLAY#...#{guid})
 -- This is safe to run repeatedly; ALTER COLUMN TYPE is a no-op if already the target type
@@ -0,0 +1,407 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+    routing::post,
+    Router,
+};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::files::{
+    get_recording_base, load_bulk_files, resolve_download_path, log_status, settle_recording, unused_name, validate_bulk_ids, BulkFailure,
+    RecordingFile, RECORDING_FILE_COLUMNS,
+};
+use crate::api::recorder::{check_storage_quota, get_user_from_header};
+use crate::api::share::ensure_recording_access;
+use crate::api::trash::delete_recording;
+use crate::core::markers::{DEFAULT_CLIP_AFTER_SECS, DEFAULT_CLIP_BEFORE_SECS};
+use crate::core::mp4_edit::{clip_mp4, concat_mp4};
+use crate::core::path_template::{replace_file_name, validate_component};
+use crate::core::recording_state::{is_in_progress, RecordingStatus};
+
+#[derive(Deserialize)]
+struct ClipPayload {
+    start_secs: Option<f64>,
+    end_secs: Option<f64>,
+    /// Cut around a marker instead: from `before_secs` ahead of it to `after_secs` past it.
+    marker_id: Option<Uuid>,
+    before_secs: Option<f64>,
+    after_secs: Option<f64>,
+    /// File name for the clip, placed next to the source. Defaults to `<stem>_clip_<start>-<end>.mp4`.
+    name: Option<String>,
+}
+
+#[derive(FromRow)]
+struct ClipSource {
+    user_id: Option<Uuid>,
+    filename: String,
+    status: String,
+    local_copy: bool,
+    capture_mode: Option<String>,
+    duration_secs: Option<f64>,
+}
+
+#[derive(Deserialize)]
+struct ConcatPayload {
+    /// Parts in playback order.
+    ids: Vec<Uuid>,
+    /// File name for the result, placed next to the first part. Defaults to `<stem>_merged.mp4`.
+    name: Option<String>,
+    /// Move the parts to the recycle bin once the merged file is registered.
+    #[serde(default)]
+    delete_parts: bool,
+}
+
+#[derive(Serialize)]
+struct ConcatResponse {
+    recording: RecordingFile,
+    deleted_parts: Vec<Uuid>,
+    failed: Vec<BulkFailure>,
+}
+
+/// Lossless MP4 editing routes, mounted under `/api/files`.
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/:id/clip", post(clip_file))
+        .route("/concat", post(concat_files))
+}
+
+/// (stem, lowercase extension) of an MP4/MOV file name; None for other containers.
+fn mp4_stem(relative: &str) -> Option<(String, String)> {
+    let file_name = relative.rsplit('/').next().unwrap_or(relative);
+    match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() && matches!(ext.to_ascii_lowercase().as_str(), "mp4" | "m4v" | "mov") => {
+            Some((stem.to_string(), ext.to_ascii_lowercase()))
+        }
+        _ => None,
+    }
+}
+
+/// The caller's file name for a derived recording, or `default` when none was given.
+fn output_name(requested: Option<&str>, default: &str, ext: &str) -> Result<String, &'static str> {
+    match requested.map(str::trim).filter(|n| !n.is_empty()) {
+        Some(name) => {
+            validate_component(name)?;
+            Ok(if name.contains('.') { name.to_string() } else { format!("{}.{}", name, ext) })
+        }
+        None => Ok(format!("{}.{}", default, ext)),
+    }
+}
+
+/// The clip range around a marker, clamped to the recording.
+async fn marker_range(
+    pool: &sqlx::PgPool,
+    recording_id: Uuid,
+    marker_id: Uuid,
+    payload: &ClipPayload,
+    duration_secs: Option<f64>,
+) -> Result<(f64, f64), (StatusCode, &'static str)> {
+    let before = payload.before_secs.unwrap_or(DEFAULT_CLIP_BEFORE_SECS);
+    let after = payload.after_secs.unwrap_or(DEFAULT_CLIP_AFTER_SECS);
+    if !before.is_finite() || !after.is_finite() || before < 0.0 || after < 0.0 || before + after <= 0.0 {
+        return Err((StatusCode::BAD_REQUEST, "before_secs and after_secs must be non-negative and not both zero"));
+    }
+    let marker: Option<(f64,)> = sqlx::query_as("SELECT offset_secs FROM recording_markers WHERE id = $1 AND recording_id = $2")
+        .bind(marker_id)
+        .bind(recording_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let offset = marker.ok_or((StatusCode::NOT_FOUND, "Marker not found"))?.0;
+    let end = offset + after;
+    Ok(((offset - before).max(0.0), duration_secs.map_or(end, |d| end.min(d))))
+}
+
+/// Cut a time range out of an MP4 recording into a new recording, without re-encoding.
+async fn clip_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<ClipPayload>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    if let Err(e) = ensure_recording_access(pool, &headers, id).await {
+        return e.into_response();
+    }
+    let source: Option<ClipSource> = sqlx::query_as(
+        "SELECT user_id, filename, status, local_copy, capture_mode, duration_secs FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    let source = match source {
+        Some(s) => s,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+    if is_in_progress(&source.status) {
+        return (StatusCode::CONFLICT, "Recording still in progress").into_response();
+    }
+    if !source.local_copy {
+        return (StatusCode::CONFLICT, "No local copy to clip").into_response();
+    }
+
+    let (start, end) = match payload.marker_id {
+        Some(marker_id) => match marker_range(pool, id, marker_id, &payload, source.duration_secs).await {
+            Ok(r) => r,
+            Err(e) => return e.into_response(),
+        },
+        None => match (payload.start_secs, payload.end_secs) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return (StatusCode::BAD_REQUEST, "start_secs and end_secs are required without marker_id").into_response(),
+        },
+    };
+    if !start.is_finite() || !end.is_finite() || start < 0.0 || end <= start {
+        return (StatusCode::BAD_REQUEST, "end_secs must be after start_secs").into_response();
+    }
+
+    let relative = source.filename.replace('\\', "/");
+    let (stem, ext) = match mp4_stem(&relative) {
+        Some(v) => v,
+        None => return (StatusCode::BAD_REQUEST, "Only MP4 recordings can be clipped").into_response(),
+    };
+    let default_name = format!("{}_clip_{}-{}", stem, start.floor() as u64, end.ceil() as u64);
+    let clip_name = match output_name(payload.name.as_deref(), &default_name, &ext) {
+        Ok(n) => n,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let base = match get_recording_base(pool).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
+    let input = match resolve_download_path(&base, &source.filename).await {
+        Ok(p) => p,
+        Err(r) => return r,
+    };
+
+    // The clip counts against the owner's quota; estimate it from the share of the source it covers
+    if let Some(owner) = source.user_id {
+        let source_size = tokio::fs::metadata(&input).await.map(|m| m.len() as i64).unwrap_or(0);
+        let estimate = match source.duration_secs.filter(|d| *d > 0.0) {
+            Some(duration) => (source_size as f64 * ((end.min(duration) - start) / duration).clamp(0.0, 1.0)) as i64,
+            None => source_size,
+        };
+        if let Err(e) = check_storage_quota(pool, owner, estimate).await {
+            return e.into_response();
+        }
+    }
+
+    let clip_relative = unused_name(&base, &replace_file_name(&relative, &clip_name)).await;
+    let target = base.join(&clip_relative);
+    // Dot-prefixed so reconcile ignores it until it is complete
+    let temp = target.with_file_name(format!(".clip-{}.{}", Uuid::new_v4().simple(), ext));
+
+    let (input_path, temp_path) = (input.clone(), temp.clone());
+    let range = match tokio::task::spawn_blocking(move || clip_mp4(&input_path, &temp_path, start, end)).await {
+        Ok(Ok(r)) => r,
+        Ok(Err(e)) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response();
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Clip Error: {}", e)).into_response();
+        }
+    };
+    if let Err(e) = tokio::fs::rename(&temp, &target).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Write Error: {}", e)).into_response();
+    }
+
+    let filepath = target.to_string_lossy().to_string();
+    let inserted: Result<(Uuid,), sqlx::Error> = sqlx::query_as(
+        "INSERT INTO recordings (user_id, filename, filepath, status, capture_mode, source_recording_id, clip_start_secs, clip_end_secs)
+         VALUES ($1, $2, $3, 'finalizing', $4, $5, $6, $7) RETURNING id"
+    )
+    .bind(source.user_id)
+    .bind(&clip_relative)
+    .bind(&filepath)
+    .bind(&source.capture_mode)
+    .bind(id)
+    .bind(range.start_secs)
+    .bind(range.end_secs)
+    .fetch_one(pool)
+    .await;
+    let clip_id = match inserted {
+        Ok(row) => row.0,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&target).await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Insert Error: {}", e)).into_response();
+        }
+    };
+    if let Err(e) = log_status(pool, clip_id, None, RecordingStatus::Finalizing, "clip exported").await {
+        tracing::warn!("Failed to log status of clip {}: {}", clip_id, e);
+    }
+    if let Err(e) = settle_recording(pool, clip_id, &filepath, false, "clip probed").await {
+        tracing::warn!("Failed to settle clip {}: {}", clip_id, e);
+    }
+
+    match sqlx::query_as::<_, RecordingFile>(&format!("SELECT {} FROM recordings WHERE id = $1", RECORDING_FILE_COLUMNS))
+        .bind(clip_id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(file) => (StatusCode::CREATED, Json(file)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+/// Join recordings end to end into a new recording, without re-encoding.
+async fn concat_files(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ConcatPayload>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    let ids = match validate_bulk_ids(&payload.ids) {
+        Ok(ids) => ids,
+        Err(e) => return e.into_response(),
+    };
+    if ids.len() < 2 || ids.len() != payload.ids.len() {
+        return (StatusCode::BAD_REQUEST, "Give at least two distinct recordings").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let (parts, failed) = match load_bulk_files(pool, &ids, user_id, &role).await {
+        Ok(v) => v,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+    if let Some(f) = failed.first() {
+        let status = if f.error == "Access denied" { StatusCode::FORBIDDEN } else { StatusCode::NOT_FOUND };
+        return (status, format!("{}: {}", f.id, f.error)).into_response();
+    }
+    let owner = parts[0].user_id;
+    if parts.iter().any(|p| p.user_id != owner) {
+        return (StatusCode::BAD_REQUEST, "All parts must belong to the same user").into_response();
+    }
+    for part in &parts {
+        if is_in_progress(&part.status) {
+            return (StatusCode::CONFLICT, format!("{} is still recording", part.filename)).into_response();
+        }
+        if !part.local_copy {
+            return (StatusCode::CONFLICT, format!("{} has no local copy", part.filename)).into_response();
+        }
+        if mp4_stem(&part.filename.replace('\\', "/")).is_none() {
+            return (StatusCode::BAD_REQUEST, format!("{} is not an MP4 recording", part.filename)).into_response();
+        }
+    }
+
+    let base = match get_recording_base(pool).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
+    let mut inputs = Vec::with_capacity(parts.len());
+    for part in &parts {
+        match resolve_download_path(&base, &part.filename).await {
+            Ok(p) => inputs.push(p),
+            Err(r) => return r,
+        }
+    }
+
+    // The merged file is about as large as its parts together
+    if let Some(owner) = owner {
+        let mut incoming = 0i64;
+        for input in &inputs {
+            incoming += tokio::fs::metadata(input).await.map(|m| m.len() as i64).unwrap_or(0);
+        }
+        if let Err(e) = check_storage_quota(pool, owner, incoming).await {
+            return e.into_response();
+        }
+    }
+
+    let relative = parts[0].filename.replace('\\', "/");
+    let (stem, ext) = mp4_stem(&relative).unwrap_or_default();
+    let merged_name = match output_name(payload.name.as_deref(), &format!("{}_merged", stem), &ext) {
+        Ok(n) => n,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let merged_relative = unused_name(&base, &replace_file_name(&relative, &merged_name)).await;
+    let target = base.join(&merged_relative);
+    // Dot-prefixed so reconcile ignores it until it is complete
+    let temp = target.with_file_name(format!(".concat-{}.{}", Uuid::new_v4().simple(), ext));
+
+    let temp_path = temp.clone();
+    let joined = tokio::task::spawn_blocking(move || {
+        let paths: Vec<&FsPath> = inputs.iter().map(PathBuf::as_path).collect();
+        concat_mp4(&paths, &temp_path)
+    })
+    .await;
+    match joined {
+        Ok(Ok(_)) => {}
+        Ok(Err(e)) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return (StatusCode::UNPROCESSABLE_ENTITY, e.to_string()).into_response();
+        }
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&temp).await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Concat Error: {}", e)).into_response();
+        }
+    }
+    if let Err(e) = tokio::fs::rename(&temp, &target).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Write Error: {}", e)).into_response();
+    }
+
+    let filepath = target.to_string_lossy().to_string();
+    let inserted: Result<(Uuid,), sqlx::Error> = sqlx::query_as(
+        "INSERT INTO recordings (user_id, filename, filepath, status, capture_mode) VALUES ($1, $2, $3, 'finalizing', $4) RETURNING id"
+    )
+    .bind(owner)
+    .bind(&merged_relative)
+    .bind(&filepath)
+    .bind(&parts[0].capture_mode)
+    .fetch_one(pool)
+    .await;
+    let merged_id = match inserted {
+        Ok(row) => row.0,
+        Err(e) => {
+            let _ = tokio::fs::remove_file(&target).await;
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Insert Error: {}", e)).into_response();
+        }
+    };
+    if let Err(e) = log_status(pool, merged_id, None, RecordingStatus::Finalizing, "segments concatenated").await {
+        tracing::warn!("Failed to log status of merged recording {}: {}", merged_id, e);
+    }
+    if let Err(e) = settle_recording(pool, merged_id, &filepath, false, "merged recording probed").await {
+        tracing::warn!("Failed to settle merged recording {}: {}", merged_id, e);
+    }
+
+    let mut deleted_parts = Vec::new();
+    let mut failed = Vec::new();
+    if payload.delete_parts {
+        for part in &parts {
+            match delete_recording(pool, part.id, &part.filepath).await {
+                Ok(()) => deleted_parts.push(part.id),
+                Err((_, e)) => failed.push(BulkFailure { id: part.id, error: e }),
+            }
+        }
+    }
+
+    match sqlx::query_as::<_, RecordingFile>(&format!("SELECT {} FROM recordings WHERE id = $1", RECORDING_FILE_COLUMNS))
+        .bind(merged_id)
+        .fetch_one(pool)
+        .await
+    {
+        Ok(recording) => (StatusCode::CREATED, Json(ConcatResponse { recording, deleted_parts, failed })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
@@ -8,22 +8,137 @@ use axum::{
 };
 use std::sync::Arc;
 use crate::AppState;
-use crate::DownloadToken;
 use crate::core::auth::decode_jwt;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use sqlx::FromRow;
+use sqlx::{FromRow, Postgres, QueryBuilder};
 use serde_json::Value;
 use std::path::{Path as FsPath, PathBuf};
-use axum::http::header::{CONTENT_DISPOSITION, CONTENT_TYPE};
+use axum::http::header::{
+    ACCEPT_RANGES, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_RANGE, LAST_MODIFIED, RANGE,
+};
 use chrono::Utc;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
+use crate::core::media_probe::{probe_file as probe_media, MediaInfo};
+use crate::core::download::{content_disposition, content_type, etag, etag_matches, parse_range, ByteRange};
+use crate::core::zip_stream::{unique_entry_names, ZipWriter};
+use crate::core::path_template::{replace_file_name, validate_relative, with_suffix};
+use crate::core::integrity::{digest_header, sha256_file, IntegrityState};
+use crate::core::markers::{chapters, render as render_chapters, ChapterFormat};
+use crate::core::recording_state::{finalized_status, is_in_progress, wait_until_stable, RecordingStatus};
+use crate::core::file_query::{like_pattern, Cursor, SortKey, DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE};
+use crate::api::share::{create_handoff, ensure_recording_access};
+use crate::api::{edit, retention, trash};
+use crate::api::trash::delete_recording;
+use crate::api::settings::load_storage_config;
+use crate::core::storage::{DownloadMode, StorageBackend, StorageKind};
+
+// Read buffer for streamed downloads
+const STREAM_CHUNK_SIZE: usize = 256 * 1024;
+
+// Thumbnails written by the processing queue, named by recording id
+pub(crate) const THUMBNAIL_DIR: &str = ".thumbnails";
+
+// Playback tokens are reusable for range requests, so they only live long enough to start playing
+const PLAYBACK_TOKEN_TTL_MINUTES: i64 = 10;
+
+// Upper bound on ids per bulk request
+const MAX_BULK_IDS: usize = 500;
 
 #[derive(Serialize, FromRow)]
 pub struct RecordingFile {
     pub id: Uuid,
+    pub user_id: Option<Uuid>,
     pub filename: String,
     pub status: String,
     pub created_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub size_bytes: Option<i64>,
+    pub duration_secs: Option<f64>,
+    pub video_codec: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub frame_rate: Option<f64>,
+    pub audio_tracks: Option<i32>,
+    /// None until probed; false when the container index is missing.
+    pub is_complete: Option<bool>,
+    /// Pinned recordings are exempt from retention cleanup.
+    pub pinned: bool,
+    pub capture_mode: Option<String>,
+    /// None while only on the local disk; otherwise uploading, archived or failed.
+    pub archive_state: Option<String>,
+    pub archive_error: Option<String>,
+    /// False once the local file was dropped after archival.
+    pub local_copy: bool,
+    pub has_thumbnail: bool,
+    /// Set on clips: the recording they were cut from and the range taken.
+    pub source_recording_id: Option<Uuid>,
+    pub clip_start_secs: Option<f64>,
+    pub clip_end_secs: Option<f64>,
+    /// Hex SHA-256 taken when the recording was finalized.
+    pub sha256: Option<String>,
+    /// Result of the last verification: ok, mismatch or missing.
+    pub integrity_state: Option<String>,
+    pub integrity_checked_at: Option<chrono::DateTime<Utc>>,
+    /// When `status` last changed; the full trail is at `/:id/history`.
+    pub status_changed_at: Option<chrono::DateTime<Utc>>,
+    pub marker_count: i64,
+}
+
+pub(crate) const RECORDING_FILE_COLUMNS: &str =
+    "id, user_id, filename, status, status_changed_at, created_at, size_bytes, duration_secs, video_codec, width, height, frame_rate, audio_tracks, is_complete, pinned, capture_mode, archive_state, archive_error, local_copy, thumbnail_path IS NOT NULL AS has_thumbnail, source_recording_id, clip_start_secs, clip_end_secs, sha256, integrity_state, integrity_checked_at, (SELECT COUNT(*) FROM recording_markers m WHERE m.recording_id = recordings.id) AS marker_count";
+
+/// A moment flagged while recording, `offset_secs` from the start of the file.
+#[derive(Serialize, FromRow)]
+pub(crate) struct RecordingMarker {
+    pub id: Uuid,
+    pub recording_id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub offset_secs: f64,
+    pub label: Option<String>,
+    pub created_at: chrono::DateTime<Utc>,
+}
+
+pub(crate) const MARKER_COLUMNS: &str = "id, recording_id, user_id, offset_secs, label, created_at";
+
+#[derive(Deserialize)]
+struct ListFilesQuery {
+    /// Admins only; everyone else is limited to their own recordings.
+    user_id: Option<Uuid>,
+    from: Option<chrono::DateTime<Utc>>,
+    to: Option<chrono::DateTime<Utc>>,
+    status: Option<String>,
+    /// Capture mode, `screen` or `window`.
+    mode: Option<String>,
+    /// Case-insensitive filename substring.
+    q: Option<String>,
+    /// Last verification result, e.g. `mismatch`.
+    integrity: Option<String>,
+    sort: Option<String>,
+    order: Option<String>,
+    limit: Option<i64>,
+    cursor: Option<String>,
+}
+
+struct FileFilters {
+    owner: Option<Uuid>,
+    from: Option<chrono::DateTime<Utc>>,
+    to: Option<chrono::DateTime<Utc>>,
+    status: Option<String>,
+    mode: Option<String>,
+    search: Option<String>,
+    integrity: Option<String>,
+}
+
+#[derive(Serialize)]
+struct FileListResponse {
+    items: Vec<RecordingFile>,
+    /// Pass back as `cursor` for the next page; null on the last page.
+    next_cursor: Option<String>,
+    /// Count and size of everything matching the filters, across all pages.
+    total_count: i64,
+    total_size_bytes: i64,
 }
 
 #[derive(Deserialize)]
@@ -31,6 +146,53 @@ pub struct RenamePayload {
     pub new_filename: String,
 }
 
+#[derive(Deserialize)]
+pub struct AssignPayload {
+    /// New owner, or null to leave the recording unowned.
+    pub user_id: Option<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkIdsPayload {
+    pub ids: Vec<Uuid>,
+}
+
+#[derive(Deserialize)]
+pub struct BulkAssignPayload {
+    pub ids: Vec<Uuid>,
+    /// New owner, or null to leave the recordings unowned.
+    pub user_id: Option<Uuid>,
+}
+
+/// Per-id outcome of a bulk operation; one bad id doesn't abort the rest.
+#[derive(Serialize, Default)]
+struct BulkResult {
+    succeeded: Vec<Uuid>,
+    failed: Vec<BulkFailure>,
+}
+
+#[derive(Serialize)]
+pub(crate) struct BulkFailure {
+    pub id: Uuid,
+    pub error: String,
+}
+
+#[derive(FromRow)]
+pub(crate) struct BulkFile {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub filepath: String,
+    pub filename: String,
+    pub status: String,
+    pub local_copy: bool,
+    pub capture_mode: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PinPayload {
+    pub pinned: bool,
+}
+
 #[derive(Serialize)]
 struct DownloadTokenResponse {
     token: String,
@@ -42,11 +204,32 @@ struct DownloadTokenQuery {
     token: String,
 }
 
+#[derive(Deserialize)]
+struct QrQuery {
+    /// `json` (default) returns both images plus the link; `svg` and `png` return the bare image.
+    format: Option<String>,
+}
+
+#[derive(FromRow)]
+struct StoredDownloadToken {
+    user_id: Uuid,
+    recording_id: Uuid,
+}
+
+#[derive(FromRow)]
+struct StoredPlaybackToken {
+    user_id: Uuid,
+    recording_id: Uuid,
+    role: String,
+}
+
 #[derive(FromRow)]
 struct FileOwnership {
     user_id: Option<Uuid>,
     filepath: String,
     filename: String,
+    status: String,
+    local_copy: bool,
 }
 
 pub fn router() -> Router<Arc<AppState>> {
@@ -55,7 +238,25 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/:id", delete(delete_file))
         .route("/:id/rename", post(rename_file))
         .route("/:id/download-token", post(create_download_token))
+        .route("/:id/playback-token", post(create_playback_token))
+        .route("/:id/probe", post(probe_file))
+        .route("/:id/assign", post(assign_file))
+        .route("/:id/pin", post(pin_file))
+        .route("/:id/qr", get(get_file_qr))
+        .route("/:id/archive", post(archive_file))
+        .route("/:id/thumbnail", get(get_thumbnail))
+        .route("/:id/history", get(get_file_history))
+        .route("/:id/markers", get(list_markers))
+        .route("/:id/markers/:marker_id", delete(delete_marker))
+        .route("/:id/chapters", get(export_chapters))
+        .route("/bulk/delete", post(bulk_delete_files))
+        .route("/bulk/assign", post(bulk_assign_files))
+        .route("/bulk/zip", post(bulk_zip_files))
         .route("/download", get(download_with_token))
+        .route("/play", get(play_with_token))
+        .merge(edit::router())
+        .merge(trash::router())
+        .merge(retention::router())
 }
 
 // Helper to extract user info from token
@@ -77,47 +278,141 @@ fn get_user_from_header(headers: &HeaderMap) -> Result<(Uuid, String, String), (
 async fn list_files(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
+    Query(params): Query<ListFilesQuery>,
 ) -> impl IntoResponse {
     let (user_id, _, role) = match get_user_from_header(&headers) {
         Ok(u) => u,
         Err(e) => return e.into_response(),
     };
 
+    // Non-admins only ever see their own recordings
+    let owner = if role == "admin" {
+        params.user_id
+    } else if params.user_id.is_some_and(|u| u != user_id) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    } else {
+        Some(user_id)
+    };
+
+    let sort = match params.sort.as_deref() {
+        None => SortKey::CreatedAt,
+        Some(s) => match SortKey::parse(s) {
+            Some(k) => k,
+            None => return (StatusCode::BAD_REQUEST, "sort must be created_at, filename, size or duration").into_response(),
+        },
+    };
+    let descending = match params.order.as_deref() {
+        None | Some("desc") => true,
+        Some("asc") => false,
+        Some(_) => return (StatusCode::BAD_REQUEST, "order must be asc or desc").into_response(),
+    };
+    let cursor = match params.cursor.as_deref().filter(|c| !c.is_empty()) {
+        None => None,
+        Some(c) => match Cursor::decode(c) {
+            Some(c) if c.matches(sort, descending) => Some(c),
+            Some(_) => return (StatusCode::BAD_REQUEST, "Cursor belongs to a different sort or order").into_response(),
+            None => return (StatusCode::BAD_REQUEST, "Invalid cursor").into_response(),
+        },
+    };
+    let limit = params.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+    let filters = FileFilters {
+        owner,
+        from: params.from,
+        to: params.to,
+        status: params.status.filter(|s| !s.is_empty()),
+        mode: params.mode.filter(|s| !s.is_empty()),
+        search: params.q.map(|q| q.trim().to_string()).filter(|q| !q.is_empty()),
+        integrity: params.integrity.filter(|s| !s.is_empty()),
+    };
+
     let db_guard = state.db.read().await;
     let pool = match db_guard.as_ref() {
         Some(p) => p,
         None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
     };
 
-    let files = if role == "admin" {
-         sqlx::query_as::<_, RecordingFile>(
-            "SELECT id, filename, status, created_at FROM recordings ORDER BY created_at DESC"
-        )
-        .fetch_all(pool)
-        .await
+    let mut totals_query = QueryBuilder::<Postgres>::new(
+        "SELECT COUNT(*), COALESCE(SUM(size_bytes), 0)::BIGINT FROM recordings WHERE TRUE"
+    );
+    push_file_filters(&mut totals_query, &filters);
+    let (total_count, total_size_bytes): (i64, i64) = match totals_query.build_query_as().fetch_one(pool).await {
+        Ok(t) => t,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+
+    let mut query = QueryBuilder::<Postgres>::new(format!("SELECT {} FROM recordings WHERE TRUE", RECORDING_FILE_COLUMNS));
+    push_file_filters(&mut query, &filters);
+    if let Some(c) = &cursor {
+        // Row comparison continues strictly after the last row of the previous page
+        query.push(format!(" AND ({}, id) {} (", sort.expr(), if descending { "<" } else { ">" }));
+        query.push_bind(c.value.clone());
+        query.push(format!("{}, ", sort.cast()));
+        query.push_bind(c.id);
+        query.push(")");
+    }
+    let direction = if descending { "DESC" } else { "ASC" };
+    query.push(format!(" ORDER BY {} {}, id {} LIMIT ", sort.expr(), direction, direction));
+    query.push_bind(limit + 1);
+
+    let mut items: Vec<RecordingFile> = match query.build_query_as().fetch_all(pool).await {
+        Ok(f) => f,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+
+    let next_cursor = if items.len() as i64 > limit {
+        items.truncate(limit as usize);
+        items.last().map(|last| Cursor { sort, descending, value: cursor_value(sort, last), id: last.id }.encode())
     } else {
-        sqlx::query_as::<_, RecordingFile>(
-            "SELECT id, filename, status, created_at FROM recordings WHERE user_id = $1 ORDER BY created_at DESC"
-        )
-        .bind(user_id)
-        .fetch_all(pool)
-        .await
+        None
     };
 
-    match files {
-        Ok(f) => Json(f).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    Json(FileListResponse { items, next_cursor, total_count, total_size_bytes }).into_response()
+}
+
+fn push_file_filters(query: &mut QueryBuilder<'_, Postgres>, filters: &FileFilters) {
+    query.push(" AND deleted_at IS NULL");
+    if let Some(owner) = filters.owner {
+        query.push(" AND user_id = ").push_bind(owner);
+    }
+    if let Some(from) = filters.from {
+        query.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(to) = filters.to {
+        query.push(" AND created_at < ").push_bind(to);
+    }
+    if let Some(status) = &filters.status {
+        query.push(" AND status = ").push_bind(status.clone());
+    }
+    if let Some(mode) = &filters.mode {
+        query.push(" AND capture_mode = ").push_bind(mode.clone());
+    }
+    if let Some(search) = &filters.search {
+        query.push(" AND filename ILIKE ").push_bind(like_pattern(search));
+    }
+    if let Some(integrity) = &filters.integrity {
+        query.push(" AND integrity_state = ").push_bind(integrity.clone());
+    }
+}
+
+/// The row's value for the sort key, matching the coalescing in `SortKey::expr`.
+fn cursor_value(sort: SortKey, file: &RecordingFile) -> String {
+    match sort {
+        SortKey::CreatedAt => file
+            .created_at
+            .unwrap_or(chrono::DateTime::<Utc>::UNIX_EPOCH)
+            .to_rfc3339_opts(chrono::SecondsFormat::AutoSi, true),
+        SortKey::Filename => file.filename.clone(),
+        SortKey::Size => file.size_bytes.unwrap_or(-1).to_string(),
+        SortKey::Duration => file.duration_secs.unwrap_or(-1.0).to_string(),
     }
 }
 
-async fn resolve_file_path(pool: &sqlx::PgPool, filepath: &str) -> String {
+pub(crate) async fn resolve_file_path(pool: &sqlx::PgPool, filepath: &str) -> String {
     let path = FsPath::new(filepath);
     if path.is_absolute() {
         return filepath.to_string();
     }
 
-    let filename = path.file_name().and_then(|f| f.to_str()).unwrap_or(filepath);
-
     let row: Option<(Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'global_recording_path'")
         .fetch_optional(pool)
         .await
@@ -125,13 +420,24 @@ async fn resolve_file_path(pool: &sqlx::PgPool, filepath: &str) -> String {
 
     let base = row.and_then(|v| v.0.as_str().map(String::from)).unwrap_or_default();
     if base.is_empty() {
-        filename.to_string()
-    } else {
-        FsPath::new(&base).join(filename).to_string_lossy().to_string()
+        return filepath.to_string();
     }
+    // Relative paths keep their folders (path templates nest recordings under the root).
+    // Older rows stored a path relative to wherever the CLI ran, so fall back to the bare
+    // file name at the root when the nested path isn't there.
+    let nested = FsPath::new(&base).join(path);
+    if !tokio::fs::try_exists(&nested).await.unwrap_or(false) {
+        if let Some(name) = path.file_name() {
+            let flat = FsPath::new(&base).join(name);
+            if tokio::fs::try_exists(&flat).await.unwrap_or(false) {
+                return flat.to_string_lossy().to_string();
+            }
+        }
+    }
+    nested.to_string_lossy().to_string()
 }
 
-async fn get_recording_base(pool: &sqlx::PgPool) -> Result<PathBuf, Response> {
+pub(crate) async fn get_recording_base(pool: &sqlx::PgPool) -> Result<PathBuf, Response> {
     let row: Option<(Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'global_recording_path'")
         .fetch_optional(pool)
         .await
@@ -159,10 +465,15 @@ async fn get_download_token_ttl_minutes(pool: &sqlx::PgPool) -> i64 {
     if minutes < 1 { 1 } else { minutes }
 }
 
-async fn resolve_download_path(base: &PathBuf, relative: &str) -> Result<PathBuf, Response> {
+pub(crate) async fn resolve_download_path(base: &PathBuf, relative: &str) -> Result<PathBuf, Response> {
     if relative.trim().is_empty() {
         return Err((StatusCode::BAD_REQUEST, "Invalid filename").into_response());
     }
+    // Names may span folders, but never leave the root
+    let rel = FsPath::new(relative);
+    if rel.components().any(|c| !matches!(c, std::path::Component::Normal(_) | std::path::Component::CurDir)) {
+        return Err((StatusCode::FORBIDDEN, "Access denied").into_response());
+    }
 
     let candidate = base.join(relative);
     let candidate_canon = match tokio::fs::canonicalize(&candidate).await {
@@ -182,205 +493,352 @@ async fn resolve_download_path(base: &PathBuf, relative: &str) -> Result<PathBuf
     Ok(candidate_canon)
 }
 
-async fn delete_file(
-    State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
-    Path(id): Path<Uuid>,
-) -> impl IntoResponse {
-    let (user_id, _, role) = match get_user_from_header(&headers) {
-        Ok(u) => u,
-        Err(e) => return e.into_response(),
-    };
+/// Where a recording's bytes are read from: the local disk, or the archive once the
+/// local copy has been dropped.
+pub(crate) enum RecordingSource {
+    Local(PathBuf),
+    Archived {
+        backend: Box<dyn StorageBackend>,
+        key: String,
+        mode: DownloadMode,
+        presign_secs: u64,
+        archived_at: Option<chrono::DateTime<Utc>>,
+    },
+}
 
-    let db_guard = state.db.read().await;
-    let pool = match db_guard.as_ref() {
-        Some(p) => p,
-        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
-    };
+#[derive(FromRow)]
+struct ArchiveLocation {
+    local_copy: bool,
+    archive_state: Option<String>,
+    archive_backend: Option<String>,
+    archive_key: Option<String>,
+    archived_at: Option<chrono::DateTime<Utc>>,
+}
 
-    let file: Option<FileOwnership> = sqlx::query_as(
-        "SELECT user_id, filepath, filename FROM recordings WHERE id = $1"
+pub(crate) async fn locate_recording(pool: &sqlx::PgPool, id: Uuid, filename: &str) -> Result<RecordingSource, Response> {
+    let row: Option<ArchiveLocation> = sqlx::query_as(
+        "SELECT local_copy, archive_state, archive_backend, archive_key, archived_at FROM recordings WHERE id = $1"
     )
     .bind(id)
     .fetch_optional(pool)
     .await
     .unwrap_or(None);
-
-    let file = match file {
-        Some(f) => f,
-        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    let row = match row {
+        Some(r) => r,
+        None => return Err((StatusCode::NOT_FOUND, "File not found").into_response()),
     };
 
-    if role != "admin" && file.user_id != Some(user_id) {
-        return (StatusCode::FORBIDDEN, "Access denied").into_response();
-    }
-
-    let resolved_path = resolve_file_path(pool, &file.filepath).await;
-    if let Err(e) = tokio::fs::remove_file(&resolved_path).await {
-        if e.kind() != std::io::ErrorKind::NotFound {
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Delete Error: {}", e)).into_response();
-        }
+    if row.local_copy {
+        let base = get_recording_base(pool).await?;
+        return resolve_download_path(&base, filename).await.map(RecordingSource::Local);
     }
 
-    if let Err(e) = sqlx::query("DELETE FROM recordings WHERE id = $1")
-        .bind(id)
-        .execute(pool)
-        .await 
-    {
-        return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Delete Error: {}", e)).into_response();
-    }
+    let key = match (row.archive_state.as_deref(), row.archive_key) {
+        (Some("archived"), Some(k)) => k,
+        _ => return Err((StatusCode::NOT_FOUND, "File not found").into_response()),
+    };
+    let config = load_storage_config(pool).await;
+    let backend = archive_backend(&config, row.archive_backend.as_deref())
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()).into_response())?;
+    Ok(RecordingSource::Archived {
+        backend,
+        key,
+        mode: config.download_mode,
+        presign_secs: config.presign_expires_minutes * 60,
+        archived_at: row.archived_at,
+    })
+}
 
-    (StatusCode::OK, "File deleted").into_response()
+/// The backend a recording was archived to, which may no longer be the active one.
+pub(crate) fn archive_backend(config: &crate::core::storage::StorageConfig, stored: Option<&str>) -> anyhow::Result<Box<dyn StorageBackend>> {
+    let kind: StorageKind = stored
+        .and_then(|k| serde_json::from_value(Value::String(k.to_string())).ok())
+        .unwrap_or(StorageKind::None);
+    config
+        .backend_for(kind)?
+        .ok_or_else(|| anyhow::anyhow!("Archive backend is not configured"))
 }
 
-async fn create_download_token(
-    State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
-    Path(id): Path<Uuid>,
-) -> impl IntoResponse {
-    let (user_id, _, role) = match get_user_from_header(&headers) {
-        Ok(u) => u,
-        Err(e) => return e.into_response(),
-    };
+#[derive(FromRow)]
+struct ArchiveCandidate {
+    filename: String,
+    filepath: String,
+    status: String,
+    local_copy: bool,
+    archive_backend: Option<String>,
+    archive_key: Option<String>,
+}
 
-    let db_guard = state.db.read().await;
-    let pool = match db_guard.as_ref() {
-        Some(p) => p,
-        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
-    };
+/// Upload a finished recording to the archive and record the outcome on its row.
+/// Drops the local file afterwards when the policy says so.
+pub(crate) async fn archive_recording(pool: &sqlx::PgPool, id: Uuid) -> anyhow::Result<()> {
+    let config = load_storage_config(pool).await;
+    let backend = config
+        .backend()?
+        .ok_or_else(|| anyhow::anyhow!("Archival is not configured"))?;
 
-    let file: Option<FileOwnership> = sqlx::query_as(
-        "SELECT user_id, filepath, filename FROM recordings WHERE id = $1"
+    let row: Option<ArchiveCandidate> = sqlx::query_as(
+        "SELECT filename, filepath, status, local_copy, archive_backend, archive_key FROM recordings WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(pool)
-    .await
-    .unwrap_or(None);
+    .await?;
+    let ArchiveCandidate { filename, filepath, status, local_copy, archive_backend: previous_backend, archive_key: previous_key } =
+        row.ok_or_else(|| anyhow::anyhow!("Recording not found"))?;
+    if is_in_progress(&status) {
+        anyhow::bail!("Recording still in progress");
+    }
+    if !local_copy {
+        anyhow::bail!("No local copy to archive");
+    }
 
-    let file = match file {
-        Some(f) => f,
-        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
-    };
+    // Claim the row so a manual archive and the post-stop one don't upload twice
+    let claimed = sqlx::query(
+        "UPDATE recordings SET archive_state = 'uploading', archive_error = NULL
+         WHERE id = $1 AND archive_state IS DISTINCT FROM 'uploading'"
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    if claimed.rows_affected() == 0 {
+        anyhow::bail!("Archive already in progress");
+    }
 
-    if role != "admin" && file.user_id != Some(user_id) {
-        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    let path = PathBuf::from(resolve_file_path(pool, &filepath).await);
+    let key = config.object_key(&filename);
+    let uploaded = async {
+        let local_size = tokio::fs::metadata(&path).await?.len();
+        backend.put_file(&key, &path).await?;
+        // Confirm the object landed whole before anything relies on it
+        match backend.size(&key).await? {
+            Some(n) if n == local_size => Ok(()),
+            Some(n) => Err(anyhow::anyhow!("Archived size {} does not match local size {}", n, local_size)),
+            None => Err(anyhow::anyhow!("Archived object not found after upload")),
+        }
+    }
+    .await;
+
+    if let Err(e) = uploaded {
+        sqlx::query("UPDATE recordings SET archive_state = 'failed', archive_error = $2 WHERE id = $1")
+            .bind(id)
+            .bind(e.to_string())
+            .execute(pool)
+            .await?;
+        return Err(e);
     }
 
-    let base = match get_recording_base(pool).await {
-        Ok(b) => b,
-        Err(r) => return r,
-    };
+    sqlx::query(
+        "UPDATE recordings SET archive_state = 'archived', archive_backend = $2, archive_key = $3,
+         archived_at = NOW(), archive_error = NULL WHERE id = $1"
+    )
+    .bind(id)
+    .bind(backend.kind().as_str())
+    .bind(&key)
+    .execute(pool)
+    .await?;
+
+    // Re-archiving after the backend or prefix changed would otherwise orphan the old object
+    if let Some(old_key) = previous_key {
+        if previous_backend.as_deref() != Some(backend.kind().as_str()) || old_key != key {
+            let removed = match archive_backend(&config, previous_backend.as_deref()) {
+                Ok(old) => old.delete(&old_key).await,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = removed {
+                tracing::warn!("Failed to remove previous archive copy {}: {}", old_key, e);
+            }
+        }
+    }
 
-    let resolved = match resolve_download_path(&base, &file.filename).await {
-        Ok(p) => p,
-        Err(r) => return r,
-    };
+    if config.delete_local_after_upload {
+        match tokio::fs::remove_file(&path).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                tracing::warn!("Archived {} but failed to remove local copy: {}", filename, e);
+            }
+            _ => {
+                sqlx::query("UPDATE recordings SET local_copy = FALSE WHERE id = $1")
+                    .bind(id)
+                    .execute(pool)
+                    .await?;
+            }
+        }
+    }
+    Ok(())
+}
 
-    let token = Uuid::new_v4().to_string();
-    let ttl_minutes = get_download_token_ttl_minutes(pool).await;
-    let expires_at = Utc::now().timestamp() + ttl_minutes * 60;
-    let mut tokens = state.download_tokens.write().await;
-    tokens.insert(token.clone(), DownloadToken {
-        user_id,
-        file_id: id,
-        expires_at,
-    });
+/// Read container metadata for a recording and store it on the row.
+pub(crate) async fn probe_recording(pool: &sqlx::PgPool, id: Uuid, filepath: &str) -> anyhow::Result<MediaInfo> {
+    let path = PathBuf::from(resolve_file_path(pool, filepath).await);
+    let info = tokio::task::spawn_blocking(move || probe_media(&path)).await??;
 
-    let _ = resolved;
-    Json(DownloadTokenResponse { token, expires_at }).into_response()
+    sqlx::query(
+        "UPDATE recordings SET size_bytes = $1, duration_secs = $2, video_codec = $3, width = $4, height = $5,
+         frame_rate = $6, audio_tracks = $7, is_complete = $8, probed_at = NOW() WHERE id = $9"
+    )
+    .bind(info.size_bytes)
+    .bind(info.duration_secs)
+    .bind(&info.video_codec)
+    .bind(info.width)
+    .bind(info.height)
+    .bind(info.frame_rate)
+    .bind(info.audio_tracks)
+    .bind(info.complete)
+    .bind(id)
+    .execute(pool)
+    .await?;
+
+    Ok(info)
 }
 
-async fn download_with_token(
-    State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
-    Query(params): Query<DownloadTokenQuery>,
-) -> impl IntoResponse {
-    let (user_id, _, role) = match get_user_from_header(&headers) {
-        Ok(u) => u,
-        Err(e) => return e.into_response(),
-    };
+/// Hash a finalized recording and store it as the reference for later verification.
+pub(crate) async fn record_checksum(pool: &sqlx::PgPool, id: Uuid, filepath: &str) -> anyhow::Result<String> {
+    let path = PathBuf::from(resolve_file_path(pool, filepath).await);
+    let digest = tokio::task::spawn_blocking(move || sha256_file(&path)).await??;
 
-    let token_value = params.token.trim().to_string();
-    if token_value.is_empty() {
-        return (StatusCode::BAD_REQUEST, "Missing token").into_response();
-    }
+    sqlx::query(
+        "UPDATE recordings SET sha256 = $2, sha256_at = NOW(), integrity_state = $3, integrity_checked_at = NOW() WHERE id = $1"
+    )
+    .bind(id)
+    .bind(&digest)
+    .bind(IntegrityState::Ok.as_str())
+    .execute(pool)
+    .await?;
 
-    let token_info = {
-        let mut tokens = state.download_tokens.write().await;
-        tokens.remove(&token_value)
-    };
+    Ok(digest)
+}
 
-    let token_info = match token_info {
-        Some(t) => t,
-        None => return (StatusCode::UNAUTHORIZED, "Invalid token").into_response(),
-    };
+/// Append to a recording's status history; `from` is None for the status it was created with.
+pub(crate) async fn log_status<'e, E: sqlx::PgExecutor<'e>>(
+    executor: E,
+    id: Uuid,
+    from: Option<RecordingStatus>,
+    to: RecordingStatus,
+    reason: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO recording_status_history (recording_id, from_status, to_status, reason) VALUES ($1, $2, $3, $4)")
+        .bind(id)
+        .bind(from.map(RecordingStatus::as_str))
+        .bind(to.as_str())
+        .bind(reason)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
 
-    if token_info.expires_at < Utc::now().timestamp() {
-        return (StatusCode::UNAUTHORIZED, "Token expired").into_response();
+/// Move a recording to `to` if the state machine allows it, logging the change.
+/// Returns the status it left; moving to the current status is a no-op.
+pub(crate) async fn transition_status(pool: &sqlx::PgPool, id: Uuid, to: RecordingStatus, reason: &str) -> anyhow::Result<RecordingStatus> {
+    let mut tx = pool.begin().await?;
+    let current: Option<(String,)> = sqlx::query_as("SELECT status FROM recordings WHERE id = $1 FOR UPDATE")
+        .bind(id)
+        .fetch_optional(&mut *tx)
+        .await?;
+    let current = current.ok_or_else(|| anyhow::anyhow!("Recording {} not found", id))?.0;
+    let from = RecordingStatus::parse(&current).ok_or_else(|| anyhow::anyhow!("Unknown status '{}'", current))?;
+    if from == to {
+        return Ok(from);
     }
-
-    if token_info.user_id != user_id {
-        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    if !from.can_transition_to(to) {
+        return Err(anyhow::anyhow!("Cannot move recording from {} to {}", from.as_str(), to.as_str()));
     }
 
-    let db_guard = state.db.read().await;
-    let pool = match db_guard.as_ref() {
-        Some(p) => p,
-        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
-    };
+    sqlx::query("UPDATE recordings SET status = $2, status_changed_at = NOW() WHERE id = $1")
+        .bind(id)
+        .bind(to.as_str())
+        .execute(&mut *tx)
+        .await?;
+    log_status(&mut *tx, id, Some(from), to, reason).await?;
+    tx.commit().await?;
+    Ok(from)
+}
 
-    let file: Option<FileOwnership> = sqlx::query_as(
-        "SELECT user_id, filepath, filename FROM recordings WHERE id = $1"
+/// The status a recording had before it last entered `status`, e.g. to undo a soft delete.
+pub(crate) async fn status_before(pool: &sqlx::PgPool, id: Uuid, status: RecordingStatus) -> Option<RecordingStatus> {
+    let row: Option<(Option<String>,)> = sqlx::query_as(
+        "SELECT from_status FROM recording_status_history WHERE recording_id = $1 AND to_status = $2
+         ORDER BY created_at DESC LIMIT 1"
     )
-    .bind(token_info.file_id)
+    .bind(id)
+    .bind(status.as_str())
     .fetch_optional(pool)
     .await
     .unwrap_or(None);
+    row.and_then(|r| r.0).and_then(|s| RecordingStatus::parse(&s))
+}
 
-    let file = match file {
-        Some(f) => f,
-        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+/// Probe and hash a finished file, then move the recording to saved or incomplete, or to
+/// uploaded for an `upload`.
+pub(crate) async fn settle_recording(pool: &sqlx::PgPool, id: Uuid, filepath: &str, upload: bool, reason: &str) -> anyhow::Result<RecordingStatus> {
+    let status = match probe_recording(pool, id, filepath).await {
+        Ok(info) => finalized_status(info.complete, upload),
+        Err(e) => {
+            tracing::warn!("Failed to probe recording {}: {}", id, e);
+            finalized_status(false, upload)
+        }
     };
-
-    if role != "admin" && file.user_id != Some(user_id) {
-        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    if let Err(e) = record_checksum(pool, id, filepath).await {
+        tracing::warn!("Failed to hash recording {}: {}", id, e);
     }
+    transition_status(pool, id, status, reason).await?;
+    Ok(status)
+}
 
-    let base = match get_recording_base(pool).await {
-        Ok(b) => b,
-        Err(r) => return r,
-    };
-
-    let resolved = match resolve_download_path(&base, &file.filename).await {
-        Ok(p) => p,
-        Err(r) => return r,
-    };
+/// After a re-probe, move a finished recording between saved and incomplete to match the file.
+pub(crate) async fn update_completeness(pool: &sqlx::PgPool, id: Uuid, complete: bool) {
+    let status: Option<(String,)> = sqlx::query_as("SELECT status FROM recordings WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    if !matches!(status.and_then(|s| RecordingStatus::parse(&s.0)), Some(RecordingStatus::Saved | RecordingStatus::Incomplete)) {
+        return;
+    }
+    if let Err(e) = transition_status(pool, id, finalized_status(complete, false), "re-probed").await {
+        tracing::warn!("Failed to update status of recording {}: {}", id, e);
+    }
+}
 
-    let data = match tokio::fs::read(&resolved).await {
-        Ok(d) => d,
-        Err(e) => {
-            if e.kind() == std::io::ErrorKind::NotFound {
-                return (StatusCode::NOT_FOUND, "File not found").into_response();
-            }
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Read Error: {}", e)).into_response();
+/// Finish a recording whose process has exited: wait for the file to stop growing, then
+/// settle it, or mark it failed if nothing usable was written.
+pub(crate) async fn finalize_recording(pool: &sqlx::PgPool, id: Uuid, filepath: &str) -> anyhow::Result<RecordingStatus> {
+    let path = PathBuf::from(resolve_file_path(pool, filepath).await);
+    match wait_until_stable(&path).await? {
+        None => {
+            transition_status(pool, id, RecordingStatus::Failed, "no output file was written").await?;
+            Ok(RecordingStatus::Failed)
         }
-    };
+        Some(0) => {
+            transition_status(pool, id, RecordingStatus::Failed, "output file is empty").await?;
+            Ok(RecordingStatus::Failed)
+        }
+        Some(_) => settle_recording(pool, id, filepath, false, "output file settled").await,
+    }
+}
 
-    let mut response = Response::new(Body::from(data));
-    *response.status_mut() = StatusCode::OK;
-    response.headers_mut().insert(CONTENT_TYPE, "application/octet-stream".parse().unwrap());
-    response.headers_mut().insert(
-        CONTENT_DISPOSITION,
-        format!("attachment; filename=\"{}\"", file.filename).parse().unwrap(),
-    );
-    response
+/// Compare a fresh hash with the stored one and record the verdict. A recording hashed for
+/// the first time takes this hash as its reference.
+pub(crate) async fn compare_checksum(pool: &sqlx::PgPool, id: Uuid, digest: &str) -> Result<IntegrityState, sqlx::Error> {
+    let row: (String,) = sqlx::query_as(
+        "UPDATE recordings SET
+         integrity_state = CASE WHEN sha256 IS NULL OR sha256 = $2 THEN $3 ELSE $4 END,
+         sha256_at = CASE WHEN sha256 IS NULL THEN NOW() ELSE sha256_at END,
+         sha256 = COALESCE(sha256, $2),
+         integrity_checked_at = NOW()
+         WHERE id = $1 RETURNING integrity_state"
+    )
+    .bind(id)
+    .bind(digest)
+    .bind(IntegrityState::Ok.as_str())
+    .bind(IntegrityState::Mismatch.as_str())
+    .fetch_one(pool)
+    .await?;
+    Ok(if row.0 == IntegrityState::Mismatch.as_str() { IntegrityState::Mismatch } else { IntegrityState::Ok })
 }
 
-async fn rename_file(
+async fn probe_file(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
     Path(id): Path<Uuid>,
-    Json(payload): Json<RenamePayload>,
 ) -> impl IntoResponse {
     let (user_id, _, role) = match get_user_from_header(&headers) {
         Ok(u) => u,
@@ -393,8 +851,787 @@ async fn rename_file(
         None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
     };
 
-    let file: Option<FileOwnership> = sqlx::query_as(
-        "SELECT user_id, filepath, filename FROM recordings WHERE id = $1"
+    let file: Option<(Option<Uuid>, String, String)> = sqlx::query_as(
+        "SELECT user_id, filepath, status FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let (owner, filepath, status) = match file {
+        Some(f) => f,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+
+    if role != "admin" && owner != Some(user_id) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+    if is_in_progress(&status) {
+        return (StatusCode::CONFLICT, "Recording still in progress").into_response();
+    }
+
+    match probe_recording(pool, id, &filepath).await {
+        Ok(info) => {
+            update_completeness(pool, id, info.complete).await;
+            Json(info).into_response()
+        }
+        Err(e) => {
+            if let Some(io) = e.downcast_ref::<std::io::Error>() {
+                if io.kind() == std::io::ErrorKind::NotFound {
+                    return (StatusCode::NOT_FOUND, "File not found").into_response();
+                }
+            }
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Probe failed: {}", e)).into_response()
+        }
+    }
+}
+
+#[derive(Serialize, FromRow)]
+struct StatusChange {
+    from_status: Option<String>,
+    to_status: String,
+    reason: Option<String>,
+    created_at: chrono::DateTime<Utc>,
+}
+
+/// Every status a recording has been through, oldest first.
+async fn get_file_history(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+    if let Err(e) = ensure_recording_access(pool, &headers, id).await {
+        return e.into_response();
+    }
+
+    match sqlx::query_as::<_, StatusChange>(
+        "SELECT from_status, to_status, reason, created_at FROM recording_status_history
+         WHERE recording_id = $1 ORDER BY created_at"
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(history) => Json(history).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+async fn list_markers(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+    if let Err(e) = ensure_recording_access(pool, &headers, id).await {
+        return e.into_response();
+    }
+
+    match load_markers(pool, id).await {
+        Ok(markers) => Json(markers).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+async fn load_markers(pool: &sqlx::PgPool, id: Uuid) -> Result<Vec<RecordingMarker>, sqlx::Error> {
+    sqlx::query_as::<_, RecordingMarker>(&format!(
+        "SELECT {} FROM recording_markers WHERE recording_id = $1 ORDER BY offset_secs, created_at",
+        MARKER_COLUMNS
+    ))
+    .bind(id)
+    .fetch_all(pool)
+    .await
+}
+
+async fn delete_marker(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path((id, marker_id)): Path<(Uuid, Uuid)>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+    if let Err(e) = ensure_recording_access(pool, &headers, id).await {
+        return e.into_response();
+    }
+
+    match sqlx::query("DELETE FROM recording_markers WHERE id = $1 AND recording_id = $2")
+        .bind(marker_id)
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "Marker not found").into_response(),
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct ChapterQuery {
+    /// `vtt` (default) or `ffmetadata`.
+    format: Option<String>,
+}
+
+/// The markers as a chapter sidecar file named after the recording.
+async fn export_chapters(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(params): Query<ChapterQuery>,
+) -> impl IntoResponse {
+    let format = match ChapterFormat::parse(params.format.as_deref().unwrap_or("vtt")) {
+        Some(f) => f,
+        None => return (StatusCode::BAD_REQUEST, "format must be vtt or ffmetadata").into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+    if let Err(e) = ensure_recording_access(pool, &headers, id).await {
+        return e.into_response();
+    }
+
+    let recording: Option<(String, Option<f64>)> = sqlx::query_as("SELECT filename, duration_secs FROM recordings WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let (filename, duration_secs) = match recording {
+        Some(r) => r,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+    let markers = match load_markers(pool, id).await {
+        Ok(m) => m,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+    if markers.is_empty() {
+        return (StatusCode::NOT_FOUND, "No markers for this recording").into_response();
+    }
+
+    let stem = FsPath::new(&filename.replace('\\', "/"))
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| id.to_string());
+    let pairs: Vec<(f64, Option<String>)> = markers.into_iter().map(|m| (m.offset_secs, m.label)).collect();
+    let body = render_chapters(format, &chapters(&pairs, duration_secs), &stem);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, format.content_type())
+        .header(CONTENT_DISPOSITION, content_disposition("attachment", &format!("{}.{}", stem, format.extension())))
+        .body(Body::from(body))
+        .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response())
+}
+
+/// Bytes held by a user's recordings, counting unprobed files as empty.
+pub(crate) async fn storage_usage(pool: &sqlx::PgPool, user_id: Uuid) -> i64 {
+    let row: Option<(Option<i64>,)> = sqlx::query_as(
+        "SELECT SUM(COALESCE(size_bytes, 0))::BIGINT FROM recordings WHERE user_id = $1 AND status <> 'missing' AND deleted_at IS NULL"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    row.and_then(|r| r.0).unwrap_or(0)
+}
+
+/// Hand out a short-lived share link for a finished recording as a QR code.
+async fn get_file_qr(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Query(params): Query<QrQuery>,
+) -> impl IntoResponse {
+    let format = params.format.as_deref().unwrap_or("json");
+    if !matches!(format, "json" | "svg" | "png") {
+        return (StatusCode::BAD_REQUEST, "format must be json, svg or png").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let user_id = match ensure_recording_access(pool, &headers, id).await {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let status: Option<(String,)> = sqlx::query_as("SELECT status FROM recordings WHERE id = $1 AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    match status.and_then(|s| RecordingStatus::parse(&s.0)) {
+        Some(s) if s.in_progress() => return (StatusCode::CONFLICT, "Recording still in progress").into_response(),
+        Some(RecordingStatus::Missing) => return (StatusCode::NOT_FOUND, "File not found on disk").into_response(),
+        _ => {}
+    }
+
+    let handoff = match create_handoff(pool, &headers, user_id, id).await {
+        Ok(h) => h,
+        Err(e) => return e.into_response(),
+    };
+
+    match format {
+        "svg" => ([(CONTENT_TYPE, "image/svg+xml")], handoff.qr_svg).into_response(),
+        "png" => ([(CONTENT_TYPE, "image/png")], handoff.png).into_response(),
+        _ => Json(handoff).into_response(),
+    }
+}
+
+async fn get_thumbnail(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    if let Err(e) = ensure_recording_access(pool, &headers, id).await {
+        return e.into_response();
+    }
+
+    let row: Option<(Option<String>,)> = sqlx::query_as("SELECT thumbnail_path FROM recordings WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let name = match row.and_then(|r| r.0) {
+        Some(n) => n,
+        None => return (StatusCode::NOT_FOUND, "No thumbnail").into_response(),
+    };
+    let base = match get_recording_base(pool).await {
+        Ok(b) => b,
+        Err(resp) => return resp,
+    };
+
+    let mime = content_type(&name);
+    match tokio::fs::read(base.join(THUMBNAIL_DIR).join(&name)).await {
+        Ok(bytes) => (
+            [(CONTENT_TYPE, mime.as_str()), (CACHE_CONTROL, "private, max-age=300")],
+            bytes,
+        )
+            .into_response(),
+        Err(_) => (StatusCode::NOT_FOUND, "No thumbnail").into_response(),
+    }
+}
+
+async fn pin_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<PinPayload>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let owner: Option<(Option<Uuid>,)> = sqlx::query_as("SELECT user_id FROM recordings WHERE id = $1 AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+
+    let owner = match owner {
+        Some(o) => o.0,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+
+    if role != "admin" && owner != Some(user_id) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+
+    match sqlx::query("UPDATE recordings SET pinned = $1 WHERE id = $2")
+        .bind(payload.pinned)
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        Ok(_) => (StatusCode::OK, if payload.pinned { "File pinned" } else { "File unpinned" }).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+    }
+}
+
+/// `relative`, or the first `_2`, `_3`, ... variant of it that doesn't exist under `base`.
+pub(crate) async fn unused_name(base: &FsPath, relative: &str) -> String {
+    let mut candidate = relative.to_string();
+    let mut n = 2;
+    while tokio::fs::metadata(base.join(&candidate)).await.is_ok() {
+        candidate = with_suffix(relative, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Upload a recording to the archive in the background; progress shows up as `archive_state`.
+async fn archive_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let row: Option<(Option<Uuid>, String, bool, Option<String>)> = sqlx::query_as(
+        "SELECT user_id, status, local_copy, archive_state FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let (owner, status, local_copy, archive_state) = match row {
+        Some(r) => r,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+    if role != "admin" && owner != Some(user_id) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+    if is_in_progress(&status) {
+        return (StatusCode::CONFLICT, "Recording still in progress").into_response();
+    }
+    if !local_copy {
+        return (StatusCode::CONFLICT, "No local copy to archive").into_response();
+    }
+    if archive_state.as_deref() == Some("uploading") {
+        return (StatusCode::CONFLICT, "Archive already in progress").into_response();
+    }
+    if let Err(e) = load_storage_config(pool).await.backend().and_then(|b| b.ok_or_else(|| anyhow::anyhow!("Archival is not configured"))) {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = archive_recording(&pool, id).await {
+            tracing::warn!("Failed to archive recording {}: {}", id, e);
+        }
+    });
+    (StatusCode::ACCEPTED, "Archive started").into_response()
+}
+
+async fn assign_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<AssignPayload>,
+) -> impl IntoResponse {
+    match get_user_from_header(&headers) {
+        Ok((_, _, role)) if role == "admin" => {}
+        Ok(_) => return (StatusCode::FORBIDDEN, "Admin only").into_response(),
+        Err(e) => return e.into_response(),
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    if let Some(owner) = payload.user_id {
+        let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM users WHERE id = $1")
+            .bind(owner)
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+        if exists.is_none() {
+            return (StatusCode::BAD_REQUEST, "User not found").into_response();
+        }
+    }
+
+    match sqlx::query("UPDATE recordings SET user_id = $1 WHERE id = $2 AND deleted_at IS NULL")
+        .bind(payload.user_id)
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "File not found").into_response(),
+        Ok(_) => {
+            revoke_download_tokens(pool, id).await;
+            (StatusCode::OK, "File assigned").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+    }
+}
+
+async fn delete_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let file: Option<FileOwnership> = sqlx::query_as(
+        "SELECT user_id, filepath, filename, status, local_copy FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let file = match file {
+        Some(f) => f,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+
+    if role != "admin" && file.user_id != Some(user_id) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+    if is_in_progress(&file.status) {
+        return (StatusCode::CONFLICT, "Recording still in progress").into_response();
+    }
+
+    match delete_recording(pool, id, &file.filepath).await {
+        Ok(()) => (StatusCode::OK, "File moved to trash").into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Invalidate outstanding download tokens for a recording, e.g. after it is renamed or
+/// changes owner. Deleting the recording removes its tokens through the foreign key.
+pub(crate) async fn revoke_download_tokens(pool: &sqlx::PgPool, recording_id: Uuid) {
+    if let Err(e) = sqlx::query("UPDATE download_tokens SET revoked_at = NOW() WHERE recording_id = $1 AND revoked_at IS NULL")
+        .bind(recording_id)
+        .execute(pool)
+        .await
+    {
+        tracing::warn!("Failed to revoke download tokens for {}: {}", recording_id, e);
+    }
+}
+
+/// Drop expired and revoked download tokens once a minute.
+pub(crate) async fn token_sweep_loop(state: Arc<AppState>, mut shutdown_rx: tokio::sync::watch::Receiver<bool>) {
+    let tick = std::time::Duration::from_secs(60);
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(tick) => {}
+            _ = shutdown_rx.changed() => break,
+        }
+
+        let pool = match state.db.read().await.clone() {
+            Some(p) => p,
+            None => continue,
+        };
+        match sqlx::query("DELETE FROM download_tokens WHERE expires_at <= NOW() OR revoked_at IS NOT NULL")
+            .execute(&pool)
+            .await
+        {
+            Ok(r) if r.rows_affected() > 0 => tracing::debug!("Swept {} download tokens", r.rows_affected()),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Download token sweep failed: {}", e),
+        }
+    }
+}
+
+pub(crate) fn validate_bulk_ids(ids: &[Uuid]) -> Result<Vec<Uuid>, (StatusCode, &'static str)> {
+    if ids.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "No ids given"));
+    }
+    if ids.len() > MAX_BULK_IDS {
+        return Err((StatusCode::BAD_REQUEST, "Too many ids in one request"));
+    }
+    let mut seen = std::collections::HashSet::new();
+    Ok(ids.iter().copied().filter(|id| seen.insert(*id)).collect())
+}
+
+/// Load the requested recordings in request order, splitting out the ones that don't
+/// exist or that the caller may not touch.
+pub(crate) async fn load_bulk_files(
+    pool: &sqlx::PgPool,
+    ids: &[Uuid],
+    user_id: Uuid,
+    role: &str,
+) -> Result<(Vec<BulkFile>, Vec<BulkFailure>), sqlx::Error> {
+    let rows: Vec<BulkFile> = sqlx::query_as(
+        "SELECT id, user_id, filepath, filename, status, local_copy, capture_mode FROM recordings WHERE id = ANY($1) AND deleted_at IS NULL"
+    )
+    .bind(ids)
+    .fetch_all(pool)
+    .await?;
+    let mut by_id: std::collections::HashMap<Uuid, BulkFile> = rows.into_iter().map(|r| (r.id, r)).collect();
+
+    let mut allowed = Vec::new();
+    let mut failed = Vec::new();
+    for id in ids {
+        match by_id.remove(id) {
+            None => failed.push(BulkFailure { id: *id, error: "File not found".to_string() }),
+            Some(f) if role != "admin" && f.user_id != Some(user_id) => {
+                failed.push(BulkFailure { id: *id, error: "Access denied".to_string() })
+            }
+            Some(f) => allowed.push(f),
+        }
+    }
+    Ok((allowed, failed))
+}
+
+async fn bulk_delete_files(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<BulkIdsPayload>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    let ids = match validate_bulk_ids(&payload.ids) {
+        Ok(ids) => ids,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let (files, failed) = match load_bulk_files(pool, &ids, user_id, &role).await {
+        Ok(v) => v,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+
+    let mut result = BulkResult { failed, ..Default::default() };
+    for file in files {
+        if is_in_progress(&file.status) {
+            result.failed.push(BulkFailure { id: file.id, error: "Recording still in progress".to_string() });
+            continue;
+        }
+        match delete_recording(pool, file.id, &file.filepath).await {
+            Ok(()) => result.succeeded.push(file.id),
+            Err((_, e)) => result.failed.push(BulkFailure { id: file.id, error: e }),
+        }
+    }
+
+    Json(result).into_response()
+}
+
+async fn bulk_assign_files(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<BulkAssignPayload>,
+) -> impl IntoResponse {
+    let (user_id, role) = match get_user_from_header(&headers) {
+        Ok((id, _, role)) if role == "admin" => (id, role),
+        Ok(_) => return (StatusCode::FORBIDDEN, "Admin only").into_response(),
+        Err(e) => return e.into_response(),
+    };
+    let ids = match validate_bulk_ids(&payload.ids) {
+        Ok(ids) => ids,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    if let Some(owner) = payload.user_id {
+        let exists: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM users WHERE id = $1")
+            .bind(owner)
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+        if exists.is_none() {
+            return (StatusCode::BAD_REQUEST, "User not found").into_response();
+        }
+    }
+
+    let (files, failed) = match load_bulk_files(pool, &ids, user_id, &role).await {
+        Ok(v) => v,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+    let found: Vec<Uuid> = files.iter().map(|f| f.id).collect();
+
+    if let Err(e) = sqlx::query("UPDATE recordings SET user_id = $1 WHERE id = ANY($2)")
+        .bind(payload.user_id)
+        .bind(&found)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response();
+    }
+    for id in &found {
+        revoke_download_tokens(pool, *id).await;
+    }
+
+    Json(BulkResult { succeeded: found, failed }).into_response()
+}
+
+/// Stream the selected recordings as one ZIP. Entries are written as the client reads,
+/// so memory use stays at one chunk regardless of archive size.
+async fn bulk_zip_files(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<BulkIdsPayload>,
+) -> Response {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    let ids = match validate_bulk_ids(&payload.ids) {
+        Ok(ids) => ids,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let (files, failed) = match load_bulk_files(pool, &ids, user_id, &role).await {
+        Ok(v) => v,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+    // Once streaming starts we can't report errors, so refuse up front
+    if let Some(f) = failed.first() {
+        return (StatusCode::FORBIDDEN, format!("{}: {}", f.id, f.error)).into_response();
+    }
+    if let Some(f) = files.iter().find(|f| is_in_progress(&f.status)) {
+        return (StatusCode::CONFLICT, format!("{}: Recording still in progress", f.id)).into_response();
+    }
+
+    let mut sources = Vec::with_capacity(files.len());
+    for file in &files {
+        match locate_recording(pool, file.id, &file.filename).await {
+            Ok(s) => sources.push(s),
+            Err(r) => return r,
+        }
+    }
+    drop(db_guard);
+
+    let names = unique_entry_names(&files.iter().map(|f| f.filename.clone()).collect::<Vec<_>>());
+    let (reader, writer) = tokio::io::duplex(STREAM_CHUNK_SIZE);
+    tokio::spawn(async move {
+        if let Err(e) = write_zip(writer, names.into_iter().zip(sources).collect()).await {
+            // The client sees a truncated archive; nothing else we can do mid-stream
+            tracing::warn!("ZIP export aborted: {}", e);
+        }
+    });
+
+    let filename = format!("recordings-{}.zip", Utc::now().format("%Y%m%d-%H%M%S"));
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(CONTENT_TYPE, "application/zip")
+        .header(CONTENT_DISPOSITION, content_disposition("attachment", &filename))
+        .body(Body::from_stream(ReaderStream::with_capacity(reader, STREAM_CHUNK_SIZE)))
+        .unwrap_or_else(|_| (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response())
+}
+
+async fn write_zip(mut out: tokio::io::DuplexStream, entries: Vec<(String, RecordingSource)>) -> std::io::Result<()> {
+    let mut zip = ZipWriter::new();
+    let mut buf = vec![0u8; STREAM_CHUNK_SIZE];
+    for (name, source) in entries {
+        let (mut file, modified): (std::pin::Pin<Box<dyn tokio::io::AsyncRead + Send>>, _) = match source {
+            RecordingSource::Local(path) => {
+                let file = tokio::fs::File::open(&path).await?;
+                let modified = file
+                    .metadata()
+                    .await?
+                    .modified()
+                    .map(chrono::DateTime::<Utc>::from)
+                    .unwrap_or_else(|_| Utc::now());
+                (Box::pin(file), modified)
+            }
+            RecordingSource::Archived { backend, key, archived_at, .. } => {
+                let reader = backend.open(&key, None).await.map_err(std::io::Error::other)?;
+                (reader, archived_at.unwrap_or_else(Utc::now))
+            }
+        };
+
+        out.write_all(&zip.start_entry(&name, modified)).await?;
+        let mut hasher = crc32fast::Hasher::new();
+        let mut size: u64 = 0;
+        loop {
+            let n = file.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            out.write_all(&buf[..n]).await?;
+            size += n as u64;
+        }
+        out.write_all(&zip.finish_entry(hasher.finalize(), size)).await?;
+    }
+    out.write_all(&zip.finish()).await?;
+    out.shutdown().await
+}
+
+async fn create_download_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    issue_token(&state, &headers, id, "download").await
+}
+
+async fn create_playback_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    issue_token(&state, &headers, id, "playback").await
+}
+
+/// Store a token for one recording. `download` tokens use the configured TTL and
+/// `playback` tokens `PLAYBACK_TOKEN_TTL_MINUTES`.
+async fn issue_token(state: &AppState, headers: &HeaderMap, id: Uuid, purpose: &str) -> Response {
+    let (user_id, _, role) = match get_user_from_header(headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let file: Option<FileOwnership> = sqlx::query_as(
+        "SELECT user_id, filepath, filename, status, local_copy FROM recordings WHERE id = $1 AND deleted_at IS NULL"
     )
     .bind(id)
     .fetch_optional(pool)
@@ -410,49 +1647,403 @@ async fn rename_file(
         return (StatusCode::FORBIDDEN, "Access denied").into_response();
     }
 
-    let new_name = payload.new_filename.trim();
-    if new_name.is_empty() {
-        return (StatusCode::BAD_REQUEST, "Invalid filename").into_response();
+    // Fail now rather than hand out a token for a file that can't be served
+    if let Err(r) = locate_recording(pool, id, &file.filename).await {
+        return r;
     }
-    if std::path::Path::new(new_name).components().count() != 1 {
-        return (StatusCode::BAD_REQUEST, "Invalid filename").into_response();
+
+    let token = Uuid::new_v4().simple().to_string();
+    let ttl_minutes = if purpose == "playback" {
+        PLAYBACK_TOKEN_TTL_MINUTES
+    } else {
+        get_download_token_ttl_minutes(pool).await
+    };
+    let expires_at = Utc::now() + chrono::Duration::minutes(ttl_minutes);
+    if let Err(e) = sqlx::query(
+        "INSERT INTO download_tokens (token, recording_id, user_id, expires_at, purpose) VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(&token)
+    .bind(id)
+    .bind(user_id)
+    .bind(expires_at)
+    .bind(purpose)
+    .execute(pool)
+    .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to store token: {}", e)).into_response();
     }
 
-    let base = match get_recording_base(pool).await {
-        Ok(b) => b,
+    let expires_at = expires_at.timestamp();
+    Json(DownloadTokenResponse { token, expires_at }).into_response()
+}
+
+async fn download_with_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<DownloadTokenQuery>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let token_value = params.token.trim().to_string();
+    if token_value.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Missing token").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    // Download tokens are single-use: redeeming one revokes it
+    let token_info: Option<StoredDownloadToken> = sqlx::query_as(
+        "UPDATE download_tokens SET revoked_at = NOW()
+         WHERE token = $1 AND purpose = 'download' AND revoked_at IS NULL AND expires_at > NOW()
+         RETURNING user_id, recording_id"
+    )
+    .bind(&token_value)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let token_info = match token_info {
+        Some(t) => t,
+        None => return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response(),
+    };
+
+    if token_info.user_id != user_id && role != "admin" {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+
+    let (file, source, digest) = match token_recording(pool, token_info.recording_id, token_info.user_id, role == "admin").await {
+        Ok(r) => r,
         Err(r) => return r,
     };
+    drop(db_guard);
 
-    let old_path = match resolve_download_path(&base, &file.filename).await {
-        Ok(p) => p,
+    serve_source(&headers, source, &file.filename, "attachment", digest.as_deref()).await
+}
+
+/// Stream a recording to a `<video>` element. Media elements can't send headers, so the
+/// playback token alone authorises the request; it stays valid until it expires so the
+/// player can issue follow-up range requests.
+async fn play_with_token(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<DownloadTokenQuery>,
+) -> impl IntoResponse {
+    let token_value = params.token.trim().to_string();
+    if token_value.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Missing token").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let token_info: Option<StoredPlaybackToken> = sqlx::query_as(
+        "SELECT t.user_id, t.recording_id, u.role FROM download_tokens t JOIN users u ON u.id = t.user_id
+         WHERE t.token = $1 AND t.purpose = 'playback' AND t.revoked_at IS NULL AND t.expires_at > NOW()"
+    )
+    .bind(&token_value)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let token_info = match token_info {
+        Some(t) => t,
+        None => return (StatusCode::UNAUTHORIZED, "Invalid or expired token").into_response(),
+    };
+
+    let (file, source, digest) = match token_recording(pool, token_info.recording_id, token_info.user_id, token_info.role == "admin").await {
+        Ok(r) => r,
+        Err(r) => return r,
+    };
+    drop(db_guard);
+
+    serve_source(&headers, source, &file.filename, "inline", digest.as_deref()).await
+}
+
+/// Load the recording a token points at, re-checking that the token's user may still read it.
+async fn token_recording(
+    pool: &sqlx::PgPool,
+    recording_id: Uuid,
+    token_user: Uuid,
+    admin: bool,
+) -> Result<(FileOwnership, RecordingSource, Option<String>), Response> {
+    let file: Option<FileOwnership> = sqlx::query_as(
+        "SELECT user_id, filepath, filename, status, local_copy FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(recording_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let file = match file {
+        Some(f) => f,
+        None => return Err((StatusCode::NOT_FOUND, "File not found").into_response()),
+    };
+
+    // The recording may have been reassigned since the token was issued
+    if file.user_id != Some(token_user) && !admin {
+        return Err((StatusCode::FORBIDDEN, "Access denied").into_response());
+    }
+
+    let source = locate_recording(pool, recording_id, &file.filename).await?;
+    let digest = recording_digest(pool, recording_id).await;
+    Ok((file, source, digest))
+}
+
+/// `Digest` header value for a recording's stored hash, if it has one.
+pub(crate) async fn recording_digest(pool: &sqlx::PgPool, id: Uuid) -> Option<String> {
+    let row: Option<(Option<String>,)> = sqlx::query_as("SELECT sha256 FROM recordings WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|r| r.0).and_then(|h| digest_header(&h))
+}
+
+/// Serve a recording from wherever it lives: disk, a presigned redirect, or proxied from the archive.
+pub(crate) async fn serve_source(
+    headers: &HeaderMap,
+    source: RecordingSource,
+    filename: &str,
+    disposition: &str,
+    digest: Option<&str>,
+) -> Response {
+    let (backend, key, mode, presign_secs, archived_at) = match source {
+        RecordingSource::Local(path) => return serve_file(headers, &path, filename, disposition, digest).await,
+        RecordingSource::Archived { backend, key, mode, presign_secs, archived_at } => (backend, key, mode, presign_secs, archived_at),
+    };
+
+    if mode == DownloadMode::Presign {
+        let content_disposition = content_disposition(disposition, filename);
+        if let Some(url) = backend.presign_get(&key, presign_secs, &content_disposition) {
+            return Response::builder()
+                .status(StatusCode::FOUND)
+                .header(axum::http::header::LOCATION, url)
+                .body(Body::empty())
+                .unwrap_or_else(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Response Error: {}", e)).into_response());
+        }
+    }
+
+    let len = match backend.size(&key).await {
+        Ok(Some(n)) => n,
+        Ok(None) => return (StatusCode::NOT_FOUND, "File not found in archive").into_response(),
+        Err(e) => return (StatusCode::BAD_GATEWAY, format!("Archive Read Error: {}", e)).into_response(),
+    };
+    let modified = archived_at.map(std::time::SystemTime::from);
+    serve_body(headers, len, modified, filename, disposition, digest, BodySource::Archived(backend.as_ref(), &key)).await
+}
+
+/// Stream a file from disk, honouring conditional and single `Range` requests.
+pub(crate) async fn serve_file(headers: &HeaderMap, path: &FsPath, filename: &str, disposition: &str, digest: Option<&str>) -> Response {
+    let handle = match tokio::fs::File::open(path).await {
+        Ok(f) => f,
+        Err(e) => {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return (StatusCode::NOT_FOUND, "File not found").into_response();
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Read Error: {}", e)).into_response();
+        }
+    };
+    let meta = match handle.metadata().await {
+        Ok(m) => m,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Read Error: {}", e)).into_response(),
+    };
+
+    serve_body(headers, meta.len(), meta.modified().ok(), filename, disposition, digest, BodySource::File(handle)).await
+}
+
+enum BodySource<'a> {
+    File(tokio::fs::File),
+    Archived(&'a dyn StorageBackend, &'a str),
+}
+
+async fn serve_body(
+    headers: &HeaderMap,
+    len: u64,
+    modified: Option<std::time::SystemTime>,
+    filename: &str,
+    disposition: &str,
+    digest: Option<&str>,
+    body: BodySource<'_>,
+) -> Response {
+    let tag = etag(len, modified);
+    let last_modified = modified.map(httpdate::fmt_http_date);
+
+    let header_str = |name| headers.get(name).and_then(|v: &axum::http::HeaderValue| v.to_str().ok());
+
+    let not_modified = match header_str(IF_NONE_MATCH) {
+        Some(inm) => etag_matches(inm, &tag),
+        // HttpDate truncates to whole seconds, matching what we sent in Last-Modified
+        None => match (header_str(IF_MODIFIED_SINCE).and_then(|v| v.parse::<httpdate::HttpDate>().ok()), modified) {
+            (Some(since), Some(m)) => httpdate::HttpDate::from(m) <= since,
+            _ => false,
+        },
+    };
+
+    // If-Range: only honour Range when the client's copy is still current
+    let range_header = match header_str(IF_RANGE) {
+        Some(v) if v != tag && Some(v) != last_modified.as_deref() => None,
+        _ => header_str(RANGE),
+    };
+
+    let mut builder = Response::builder()
+        .header(ACCEPT_RANGES, "bytes")
+        .header(ETAG, &tag);
+    if let Some(lm) = last_modified.as_ref() {
+        builder = builder.header(LAST_MODIFIED, lm);
+    }
+    // Instance digest of the whole file, so it's the same on range responses
+    if let Some(d) = digest {
+        builder = builder.header("Digest", d);
+    }
+
+    if not_modified {
+        return builder.status(StatusCode::NOT_MODIFIED).body(Body::empty()).unwrap();
+    }
+
+    let (status, start, count) = match parse_range(range_header, len) {
+        ByteRange::Full => (StatusCode::OK, 0, len),
+        ByteRange::Partial { start, end } => {
+            builder = builder.header(CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len));
+            (StatusCode::PARTIAL_CONTENT, start, end - start + 1)
+        }
+        ByteRange::Unsatisfiable => {
+            return builder
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(CONTENT_RANGE, format!("bytes */{}", len))
+                .body(Body::empty())
+                .unwrap();
+        }
+    };
+
+    let stream = match body {
+        BodySource::File(mut handle) => {
+            if start > 0 {
+                if let Err(e) = handle.seek(std::io::SeekFrom::Start(start)).await {
+                    return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Read Error: {}", e)).into_response();
+                }
+            }
+            Body::from_stream(ReaderStream::with_capacity(handle.take(count), STREAM_CHUNK_SIZE))
+        }
+        BodySource::Archived(backend, key) => {
+            let range = if status == StatusCode::PARTIAL_CONTENT { Some((start, start + count - 1)) } else { None };
+            match backend.open(key, range).await {
+                Ok(reader) => Body::from_stream(ReaderStream::with_capacity(reader, STREAM_CHUNK_SIZE)),
+                Err(e) => return (StatusCode::BAD_GATEWAY, format!("Archive Read Error: {}", e)).into_response(),
+            }
+        }
+    };
+
+    builder
+        .status(status)
+        .header(CONTENT_TYPE, content_type(filename))
+        .header(CONTENT_LENGTH, count)
+        .header(CONTENT_DISPOSITION, content_disposition(disposition, filename))
+        .body(stream)
+        .unwrap_or_else(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Response Error: {}", e)).into_response())
+}
+
+async fn rename_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    Json(payload): Json<RenamePayload>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let file: Option<FileOwnership> = sqlx::query_as(
+        "SELECT user_id, filepath, filename, status, local_copy FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    let file = match file {
+        Some(f) => f,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+
+    if role != "admin" && file.user_id != Some(user_id) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+    if is_in_progress(&file.status) {
+        return (StatusCode::CONFLICT, "Recording still in progress").into_response();
+    }
+
+    // A bare name stays in the recording's current folder; a path is taken from the root
+    let requested = payload.new_filename.trim().replace('\\', "/");
+    let new_name = if requested.contains('/') {
+        requested
+    } else {
+        replace_file_name(&file.filename.replace('\\', "/"), &requested)
+    };
+    if let Err(e) = validate_relative(&new_name) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let base = match get_recording_base(pool).await {
+        Ok(b) => b,
         Err(r) => return r,
     };
 
-    let new_path = base.join(new_name);
+    let new_path = base.join(&new_name);
     if let Ok(_) = tokio::fs::metadata(&new_path).await {
         return (StatusCode::CONFLICT, "File already exists").into_response();
     }
 
-    if let Err(e) = tokio::fs::rename(&old_path, &new_path).await {
-        if e.kind() == std::io::ErrorKind::NotFound {
-            return (StatusCode::NOT_FOUND, "File not found").into_response();
+    // Archive-only recordings keep their object key; only the display name changes
+    if file.local_copy {
+        let old_path = match resolve_download_path(&base, &file.filename).await {
+            Ok(p) => p,
+            Err(r) => return r,
+        };
+        if let Some(parent) = new_path.parent() {
+            if let Err(e) = tokio::fs::create_dir_all(parent).await {
+                return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Rename Error: {}", e)).into_response();
+            }
+        }
+
+        if let Err(e) = tokio::fs::rename(&old_path, &new_path).await {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                return (StatusCode::NOT_FOUND, "File not found").into_response();
+            }
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Rename Error: {}", e)).into_response();
         }
-        return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Rename Error: {}", e)).into_response();
     }
 
-    let new_filepath = format!("/recordings/{}", new_name);
+    let new_filepath = new_path.to_string_lossy().to_string();
 
     // Update DB
     if let Err(e) = sqlx::query(
         "UPDATE recordings SET filename = $1, filepath = $2 WHERE id = $3"
     )
-    .bind(new_name)
+    .bind(&new_name)
     .bind(new_filepath)
     .bind(id)
     .execute(pool)
     .await {
         return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response();
     }
+    revoke_download_tokens(pool, id).await;
 
     (StatusCode::OK, "File renamed").into_response()
 }
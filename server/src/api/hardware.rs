@@ -1,98 +1,216 @@
 use axum::{
-    extract::State,
+    extract::{State, Path},
     http::{StatusCode, HeaderMap},
-    response::{IntoResponse, Json},
+    response::{IntoResponse, Json, Response},
     routing::{get, post},
     Router,
 };
 use std::sync::Arc;
+use std::time::Duration;
 use crate::AppState;
 use crate::core::hardware::probe_hardware;
 use crate::core::auth::decode_jwt;
+use crate::core::scan_job::{ScanJob, ScanJobState};
+use crate::core::encoders::merge_catalogue;
+use crate::api::settings::{load_encoder_catalogue, save_encoder_catalogue};
 use sqlx::FromRow;
 use serde::Serialize;
+use uuid::Uuid;
 
 #[derive(Serialize, FromRow)]
 struct SystemConfigRow {
     value: serde_json::Value,
 }
 
+pub(crate) const DEFAULT_SCAN_TIMEOUT_SECS: u64 = 60;
+
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/scan", post(scan_hardware))
+        .route("/scan/jobs", get(list_scan_jobs).post(start_scan_job))
+        .route("/scan/jobs/:id", get(get_scan_job).delete(cancel_scan_job))
         .route("/info", get(get_hardware_info))
 }
 
-async fn scan_hardware(
-    State(state): State<Arc<AppState>>,
-    headers: HeaderMap,
-) -> impl IntoResponse {
+fn require_admin(headers: &HeaderMap) -> Result<Uuid, (StatusCode, &'static str)> {
     // Auth check: Admin only
     let token = headers.get("Authorization")
         .and_then(|h| h.to_str().ok())
         .and_then(|h| h.strip_prefix("Bearer "))
-        .ok_or((StatusCode::UNAUTHORIZED, "Missing token"));
+        .ok_or((StatusCode::UNAUTHORIZED, "Missing token"))?;
 
-    let token = match token {
-        Ok(t) => t,
-        Err(e) => return e.into_response(),
-    };
-
-    let claims = match decode_jwt(token) {
-        Ok(c) => c,
-        Err(_) => return (StatusCode::UNAUTHORIZED, "Invalid token").into_response(),
-    };
+    let claims = decode_jwt(token)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token"))?;
 
     if claims.role != "admin" {
-        return (StatusCode::FORBIDDEN, "Admin only").into_response();
+        return Err((StatusCode::FORBIDDEN, "Admin only"));
     }
 
-    let db_guard = state.db.read().await;
-    let pool = match db_guard.as_ref() {
-        Some(p) => p,
-        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    Uuid::parse_str(&claims.sub).map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID"))
+}
+
+pub(crate) async fn get_scan_timeout(pool: &sqlx::PgPool) -> Duration {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'hardware_scan_timeout_secs'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let secs = row.and_then(|v| v.0.as_u64()).unwrap_or(DEFAULT_SCAN_TIMEOUT_SECS);
+    Duration::from_secs(secs.max(1))
+}
+
+/// Spawn a tracked scan job that probes the hardware and stores the result as `hardware_info`.
+async fn spawn_scan(
+    state: &Arc<AppState>,
+    user_id: Uuid,
+) -> Result<(ScanJob, tokio::task::JoinHandle<ScanJob>), Response> {
+    // Held until the job is registered; a recording that is starting holds it through startup
+    let _claim = match state.encoder_lock.try_lock() {
+        Ok(guard) => guard,
+        Err(_) => return Err((StatusCode::CONFLICT, "A recording is starting").into_response()),
+    };
+    if state.recorder_manager.has_any_recording().await {
+        return Err((StatusCode::CONFLICT, "A recording is holding the encoder").into_response());
+    }
+
+    let pool = {
+        let db_guard = state.db.read().await;
+        match db_guard.as_ref() {
+            Some(p) => p.clone(),
+            None => return Err((StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response()),
+        }
     };
 
     // Fetch CLI path
     let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'cli_capture_path'")
-        .fetch_optional(pool)
+        .fetch_optional(&pool)
         .await
         .unwrap_or(None);
-    
+
     let cli_path = match row {
         Some((val,)) => val.as_str().unwrap_or("").to_string(),
         None => "".to_string(),
     };
 
-    // Run probe
-    let info = match probe_hardware(cli_path).await {
-        Ok(i) => i,
-        Err(e) => {
-            let msg = e.to_string();
+    let timeout = get_scan_timeout(&pool).await;
+
+    let work = async move {
+        let info = probe_hardware(cli_path).await?;
+        let json_value = serde_json::to_value(&info)?;
+
+        sqlx::query(
+            "INSERT INTO system_config (key, value) VALUES ('hardware_info', $1)
+             ON CONFLICT (key) DO UPDATE SET value = $1"
+        )
+        .bind(&json_value)
+        .execute(&pool)
+        .await
+        .map_err(|e| anyhow::anyhow!("DB Write Failed: {}", e))?;
+
+        let existing = load_encoder_catalogue(&pool).await;
+        save_encoder_catalogue(&pool, &merge_catalogue(&existing, &info.encoders))
+            .await
+            .map_err(|e| anyhow::anyhow!("DB Write Failed: {}", e))?;
+
+        Ok(json_value)
+    };
+
+    state.scan_jobs.start("scan", user_id, timeout, work).await
+        .map_err(|e| (StatusCode::CONFLICT, e.to_string()).into_response())
+}
+
+async fn scan_hardware(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let user_id = match require_admin(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let (_, handle) = match spawn_scan(&state, user_id).await {
+        Ok(v) => v,
+        Err(r) => return r,
+    };
+
+    let job = match handle.await {
+        Ok(j) => j,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("Probe failed: {}", e)).into_response(),
+    };
+
+    match job.state {
+        ScanJobState::Completed => Json(job.result).into_response(),
+        ScanJobState::TimedOut => (StatusCode::GATEWAY_TIMEOUT, job.error.unwrap_or_default()).into_response(),
+        ScanJobState::Cancelled => (StatusCode::CONFLICT, job.error.unwrap_or_default()).into_response(),
+        _ => {
+            let msg = job.error.unwrap_or_default();
             if is_cli_config_error(&msg) {
                 return (StatusCode::BAD_REQUEST, msg).into_response();
             }
-            return (StatusCode::INTERNAL_SERVER_ERROR, format!("Probe failed: {}", msg)).into_response();
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Probe failed: {}", msg)).into_response()
         }
+    }
+}
+
+async fn start_scan_job(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let user_id = match require_admin(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
     };
 
-    // Save to DB
-    let json_value = serde_json::to_value(&info).unwrap();
+    match spawn_scan(&state, user_id).await {
+        Ok((job, _)) => (StatusCode::ACCEPTED, Json(job)).into_response(),
+        Err(r) => r,
+    }
+}
 
-    let result = sqlx::query(
-        "INSERT INTO system_config (key, value) VALUES ('hardware_info', $1) 
-         ON CONFLICT (key) DO UPDATE SET value = $1"
-    )
-    .bind(json_value)
-    .execute(pool)
-    .await;
+async fn list_scan_jobs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = require_admin(&headers) {
+        return e.into_response();
+    }
+
+    Json(state.scan_jobs.list().await).into_response()
+}
+
+async fn get_scan_job(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(e) = require_admin(&headers) {
+        return e.into_response();
+    }
 
-    match result {
-        Ok(_) => Json(info).into_response(),
-        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Write Failed: {}", e)).into_response(),
+    match state.scan_jobs.get(id).await {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, "Scan job not found").into_response(),
     }
 }
 
+async fn cancel_scan_job(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    if let Err(e) = require_admin(&headers) {
+        return e.into_response();
+    }
+
+    if state.scan_jobs.get(id).await.is_none() {
+        return (StatusCode::NOT_FOUND, "Scan job not found").into_response();
+    }
+    if !state.scan_jobs.cancel(id).await {
+        return (StatusCode::CONFLICT, "Scan job is not running").into_response();
+    }
+
+    (StatusCode::OK, "Scan cancelled").into_response()
+}
+
 async fn get_hardware_info(
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
@@ -0,0 +1,991 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+    routing::{get, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use sqlx::{FromRow, Postgres, QueryBuilder};
+use std::path::Path as FsPath;
+use std::process::Stdio;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::files::{
+    archive_recording, compare_checksum, get_recording_base, locate_recording, probe_recording, record_checksum,
+    revoke_download_tokens, update_completeness, RecordingSource, THUMBNAIL_DIR,
+};
+use crate::api::recorder::get_user_from_header;
+use crate::api::settings::{load_pipeline_config, load_storage_config};
+use crate::core::jobs::{
+    append_log, expand_args, parse_progress, retry_delay, JobKind, JobStatus, PipelineStep, MAX_WORKERS,
+};
+use crate::core::integrity::{IntegrityState, HASH_CHUNK_SIZE};
+use crate::core::path_template::{replace_file_name, with_suffix};
+use crate::core::recording_state::{is_in_progress, IN_PROGRESS_SQL};
+use crate::core::storage::StorageKind;
+
+// How often a running job writes progress/log and checks for cancellation
+const FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
+const IDLE_POLL: std::time::Duration = std::time::Duration::from_secs(5);
+const DEFAULT_LIST_LIMIT: i64 = 100;
+
+#[derive(Serialize, FromRow)]
+struct JobSummary {
+    id: Uuid,
+    recording_id: Option<Uuid>,
+    user_id: Option<Uuid>,
+    pipeline_id: Uuid,
+    step_index: i32,
+    kind: String,
+    status: String,
+    progress: f32,
+    attempts: i32,
+    max_attempts: i32,
+    error: Option<String>,
+    result: Option<serde_json::Value>,
+    run_after: DateTime<Utc>,
+    created_at: DateTime<Utc>,
+    started_at: Option<DateTime<Utc>>,
+    finished_at: Option<DateTime<Utc>>,
+}
+
+const JOB_SUMMARY_COLUMNS: &str = "id, recording_id, user_id, pipeline_id, step_index, kind, status, progress, attempts, max_attempts, error, result, run_after, created_at, started_at, finished_at";
+
+#[derive(Serialize)]
+struct JobDetail {
+    #[serde(flatten)]
+    job: JobSummary,
+    params: serde_json::Value,
+    log: String,
+}
+
+#[derive(Deserialize)]
+struct ListJobsQuery {
+    recording_id: Option<Uuid>,
+    status: Option<String>,
+    kind: Option<String>,
+    limit: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct EnqueuePayload {
+    recording_id: Uuid,
+}
+
+#[derive(FromRow)]
+struct ClaimedJob {
+    id: Uuid,
+    recording_id: Option<Uuid>,
+    pipeline_id: Uuid,
+    step_index: i32,
+    params: serde_json::Value,
+    attempts: i32,
+    max_attempts: i32,
+}
+
+#[derive(FromRow)]
+struct VerifyCandidate {
+    id: Uuid,
+    filename: String,
+    hashed: bool,
+}
+
+#[derive(FromRow)]
+struct JobRecording {
+    user_id: Option<Uuid>,
+    filename: String,
+    status: String,
+    local_copy: bool,
+    duration_secs: Option<f64>,
+    archive_state: Option<String>,
+}
+
+/// Live state of a running job, flushed to its row every `FLUSH_INTERVAL`.
+struct JobRun<'a> {
+    pool: &'a sqlx::PgPool,
+    id: Uuid,
+    log: String,
+    progress: f32,
+    last_flush: std::time::Instant,
+}
+
+enum JobFailure {
+    Cancelled,
+    Error(String),
+}
+
+impl From<String> for JobFailure {
+    fn from(e: String) -> Self {
+        JobFailure::Error(e)
+    }
+}
+
+impl<'a> JobRun<'a> {
+    fn new(pool: &'a sqlx::PgPool, id: Uuid) -> Self {
+        Self { pool, id, log: String::new(), progress: 0.0, last_flush: std::time::Instant::now() }
+    }
+
+    fn log(&mut self, line: &str) {
+        append_log(&mut self.log, line);
+    }
+
+    /// Write progress and log; returns true when someone asked for the job to stop.
+    async fn flush(&mut self) -> bool {
+        self.last_flush = std::time::Instant::now();
+        let row: Option<(bool,)> = sqlx::query_as(
+            "UPDATE jobs SET progress = $2, log = $3 WHERE id = $1 RETURNING cancel_requested"
+        )
+        .bind(self.id)
+        .bind(self.progress)
+        .bind(&self.log)
+        .fetch_optional(self.pool)
+        .await
+        .unwrap_or(None);
+        row.map(|r| r.0).unwrap_or(false)
+    }
+
+    async fn flush_if_due(&mut self) -> Result<(), JobFailure> {
+        if self.last_flush.elapsed() >= FLUSH_INTERVAL && self.flush().await {
+            return Err(JobFailure::Cancelled);
+        }
+        Ok(())
+    }
+}
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", get(list_jobs).post(enqueue_jobs))
+        .route("/verify", post(verify_recordings))
+        .route("/:id", get(get_job))
+        .route("/:id/retry", post(retry_job))
+        .route("/:id/cancel", post(cancel_job))
+}
+
+/// Queue `steps` as one pipeline for a recording.
+pub(crate) async fn enqueue_pipeline(
+    pool: &sqlx::PgPool,
+    recording_id: Uuid,
+    user_id: Option<Uuid>,
+    steps: &[PipelineStep],
+    max_attempts: i32,
+) -> Result<Option<Uuid>, sqlx::Error> {
+    if steps.is_empty() {
+        return Ok(None);
+    }
+    let pipeline_id = Uuid::new_v4();
+    let mut tx = pool.begin().await?;
+    for (index, step) in steps.iter().enumerate() {
+        sqlx::query(
+            "INSERT INTO jobs (recording_id, user_id, pipeline_id, step_index, kind, params, max_attempts)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)"
+        )
+        .bind(recording_id)
+        .bind(user_id)
+        .bind(pipeline_id)
+        .bind(index as i32)
+        .bind(step.kind.as_str())
+        .bind(serde_json::to_value(step).unwrap_or_default())
+        .bind(max_attempts.max(1))
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(Some(pipeline_id))
+}
+
+/// The configured post-stop pipeline, plus an upload when the storage policy archives on
+/// stop and the pipeline doesn't already include one.
+pub(crate) async fn enqueue_post_stop(pool: &sqlx::PgPool, recording_id: Uuid, user_id: Option<Uuid>) -> Result<Option<Uuid>, sqlx::Error> {
+    let config = load_pipeline_config(pool).await;
+    let storage = load_storage_config(pool).await;
+    let mut steps = config.steps;
+    if storage.upload_after_stop
+        && storage.backend != StorageKind::None
+        && !steps.iter().any(|s| s.kind == JobKind::Upload)
+    {
+        steps.push(upload_step());
+    }
+    enqueue_pipeline(pool, recording_id, user_id, &steps, config.max_attempts).await
+}
+
+fn upload_step() -> PipelineStep {
+    PipelineStep {
+        kind: JobKind::Upload,
+        command: String::new(),
+        args: Vec::new(),
+        output_ext: None,
+        timeout_secs: 24 * 3600,
+    }
+}
+
+/// Worker task `index`; only the first `workers` (from the pipeline config) take jobs.
+pub(crate) async fn job_worker_loop(state: Arc<AppState>, mut shutdown_rx: tokio::sync::watch::Receiver<bool>, index: usize) {
+    let mut recovered = false;
+    let spawned_at = Utc::now();
+    loop {
+        let pool = match state.db.read().await.clone() {
+            Some(p) => p,
+            None => {
+                tokio::select! {
+                    _ = tokio::time::sleep(IDLE_POLL) => continue,
+                    _ = shutdown_rx.changed() => break,
+                }
+            }
+        };
+
+        // Jobs left running by a previous process will never finish; put them back
+        if index == 0 && !recovered {
+            recovered = true;
+            match sqlx::query("UPDATE jobs SET status = 'queued', run_after = NOW() WHERE status = 'running' AND started_at < $1")
+                .bind(spawned_at)
+                .execute(&pool)
+                .await
+            {
+                Ok(r) if r.rows_affected() > 0 => tracing::info!("Requeued {} interrupted jobs", r.rows_affected()),
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to requeue interrupted jobs: {}", e),
+            }
+        }
+
+        let workers = load_pipeline_config(&pool).await.workers.clamp(1, MAX_WORKERS);
+        let claimed = if index < workers { claim_job(&pool).await } else { Ok(None) };
+        match claimed {
+            Ok(Some(job)) => {
+                let id = job.id;
+                tracing::info!("Worker {} running job {}", index, id);
+                if let Err(e) = execute_job(&pool, job).await {
+                    tracing::warn!("Failed to record result of job {}: {}", id, e);
+                }
+            }
+            Ok(None) => {
+                tokio::select! {
+                    _ = tokio::time::sleep(IDLE_POLL) => {}
+                    _ = shutdown_rx.changed() => break,
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Failed to claim job: {}", e);
+                tokio::select! {
+                    _ = tokio::time::sleep(IDLE_POLL) => {}
+                    _ = shutdown_rx.changed() => break,
+                }
+            }
+        }
+    }
+}
+
+/// Take the oldest runnable job whose earlier pipeline steps have all succeeded.
+async fn claim_job(pool: &sqlx::PgPool) -> Result<Option<ClaimedJob>, sqlx::Error> {
+    sqlx::query_as(
+        "UPDATE jobs SET status = 'running', attempts = attempts + 1, started_at = NOW(), finished_at = NULL,
+         error = NULL, progress = 0, log = ''
+         WHERE id = (
+             SELECT j.id FROM jobs j
+             WHERE j.status = 'queued' AND j.run_after <= NOW() AND NOT j.cancel_requested
+             AND NOT EXISTS (
+                 SELECT 1 FROM jobs p WHERE p.pipeline_id = j.pipeline_id AND p.step_index < j.step_index AND p.status <> 'succeeded'
+             )
+             ORDER BY j.created_at, j.step_index
+             FOR UPDATE SKIP LOCKED
+             LIMIT 1
+         )
+         RETURNING id, recording_id, pipeline_id, step_index, params, attempts, max_attempts"
+    )
+    .fetch_optional(pool)
+    .await
+}
+
+async fn execute_job(pool: &sqlx::PgPool, job: ClaimedJob) -> Result<(), sqlx::Error> {
+    let mut run = JobRun::new(pool, job.id);
+    let outcome = match serde_json::from_value::<PipelineStep>(job.params.clone()) {
+        Ok(step) => run_step(&mut run, job.recording_id, &step).await,
+        Err(e) => Err(JobFailure::Error(format!("Invalid job parameters: {}", e))),
+    };
+
+    match outcome {
+        Ok(result) => {
+            sqlx::query(
+                "UPDATE jobs SET status = 'succeeded', progress = 1, log = $2, result = $3, finished_at = NOW() WHERE id = $1"
+            )
+            .bind(job.id)
+            .bind(&run.log)
+            .bind(result)
+            .execute(pool)
+            .await?;
+        }
+        Err(JobFailure::Cancelled) => {
+            run.log("Cancelled");
+            finish_failed(pool, &job, JobStatus::Cancelled, "Cancelled", &run.log).await?;
+        }
+        Err(JobFailure::Error(e)) => {
+            run.log(&e);
+            if job.attempts < job.max_attempts {
+                sqlx::query(
+                    "UPDATE jobs SET status = 'queued', error = $2, log = $3, run_after = $4 WHERE id = $1"
+                )
+                .bind(job.id)
+                .bind(&e)
+                .bind(&run.log)
+                .bind(Utc::now() + retry_delay(job.attempts))
+                .execute(pool)
+                .await?;
+            } else {
+                finish_failed(pool, &job, JobStatus::Failed, &e, &run.log).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// End a job for good and cancel the pipeline steps queued behind it.
+async fn finish_failed(pool: &sqlx::PgPool, job: &ClaimedJob, status: JobStatus, error: &str, log: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE jobs SET status = $2, error = $3, log = $4, finished_at = NOW() WHERE id = $1")
+        .bind(job.id)
+        .bind(status.as_str())
+        .bind(error)
+        .bind(log)
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "UPDATE jobs SET status = 'cancelled', error = 'An earlier step did not complete', finished_at = NOW()
+         WHERE pipeline_id = $1 AND step_index > $2 AND status = 'queued'"
+    )
+    .bind(job.pipeline_id)
+    .bind(job.step_index)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+async fn run_step(run: &mut JobRun<'_>, recording_id: Option<Uuid>, step: &PipelineStep) -> Result<Option<serde_json::Value>, JobFailure> {
+    if step.kind == JobKind::Verify {
+        return verify_all(run).await;
+    }
+    let pool = run.pool;
+    let recording_id = recording_id.ok_or_else(|| "Job has no recording".to_string())?;
+    let rec: Option<JobRecording> = sqlx::query_as(
+        "SELECT user_id, filename, status, local_copy, duration_secs, archive_state FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(recording_id)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| format!("DB Error: {}", e))?;
+    let rec = rec.ok_or_else(|| "Recording no longer exists".to_string())?;
+    if is_in_progress(&rec.status) {
+        return Err("Recording still in progress".to_string().into());
+    }
+    if !rec.local_copy {
+        return Err("No local copy to process".to_string().into());
+    }
+
+    let base = get_recording_base(pool)
+        .await
+        .map_err(|_| "Recording directory unavailable".to_string())?;
+    let input = base.join(&rec.filename);
+    run.log(&format!("{} {}", step.kind.as_str(), rec.filename));
+
+    match step.kind {
+        JobKind::Checksum => {
+            let digest = hash_file(run, &input).await?;
+            run.log(&format!("sha256 {}", digest));
+            let state = compare_checksum(pool, recording_id, &digest)
+                .await
+                .map_err(|e| format!("DB Update Error: {}", e))?;
+            if state == IntegrityState::Mismatch {
+                return Err("File no longer matches the hash taken when it was finalized".to_string().into());
+            }
+            Ok(Some(serde_json::json!({ "sha256": digest })))
+        }
+        JobKind::Upload => {
+            archive_recording(pool, recording_id).await.map_err(|e| e.to_string())?;
+            Ok(None)
+        }
+        JobKind::Thumbnail => {
+            let ext = step.output_ext.as_deref().unwrap_or("jpg");
+            let dir = base.join(THUMBNAIL_DIR);
+            tokio::fs::create_dir_all(&dir)
+                .await
+                .map_err(|e| format!("Failed to create thumbnail folder: {}", e))?;
+            let name = format!("{}.{}", recording_id.simple(), ext);
+            run_command(run, step, &input, &dir.join(&name), rec.duration_secs).await?;
+            sqlx::query("UPDATE recordings SET thumbnail_path = $2 WHERE id = $1")
+                .bind(recording_id)
+                .bind(&name)
+                .execute(pool)
+                .await
+                .map_err(|e| format!("DB Update Error: {}", e))?;
+            Ok(Some(serde_json::json!({ "thumbnail": name })))
+        }
+        JobKind::Remux | JobKind::Transcode => {
+            let filename = replace_output(run, step, recording_id, &base, &rec).await?;
+            Ok(Some(serde_json::json!({ "filename": filename })))
+        }
+        JobKind::Verify => unreachable!("verify jobs are handled before loading a recording"),
+    }
+}
+
+/// Re-hash every finished recording, from disk or the archive, and flag any that no longer
+/// match. Recordings without a stored hash get this one as their reference.
+async fn verify_all(run: &mut JobRun<'_>) -> Result<Option<serde_json::Value>, JobFailure> {
+    let pool = run.pool;
+    // Failed recordings usually never had a file, so there is nothing to verify
+    let candidates: Vec<VerifyCandidate> = sqlx::query_as(&format!(
+        "SELECT id, filename, sha256 IS NOT NULL AS hashed FROM recordings
+         WHERE deleted_at IS NULL AND status NOT IN {} AND status <> 'failed' ORDER BY created_at",
+        IN_PROGRESS_SQL
+    ))
+    .fetch_all(pool)
+    .await
+    .map_err(|e| format!("DB Error: {}", e))?;
+    run.log(&format!("Verifying {} recordings", candidates.len()));
+
+    let total = candidates.len().max(1) as f32;
+    let (mut ok, mut baselined, mut errors) = (0, 0, 0);
+    let mut mismatched = Vec::new();
+    let mut missing = Vec::new();
+    for (index, rec) in candidates.iter().enumerate() {
+        let span = (index as f32 / total, (index + 1) as f32 / total);
+        run.progress = span.0;
+        match hash_recording(run, rec, span).await {
+            Ok(Some(digest)) => match compare_checksum(pool, rec.id, &digest).await {
+                Ok(IntegrityState::Mismatch) => {
+                    run.log(&format!("MISMATCH {} ({})", rec.filename, digest));
+                    mismatched.push(rec.id);
+                }
+                Ok(_) if rec.hashed => ok += 1,
+                Ok(_) => baselined += 1,
+                Err(e) => {
+                    run.log(&format!("{}: DB Update Error: {}", rec.filename, e));
+                    errors += 1;
+                }
+            },
+            Ok(None) => {
+                run.log(&format!("MISSING {}", rec.filename));
+                let _ = sqlx::query("UPDATE recordings SET integrity_state = $2, integrity_checked_at = NOW() WHERE id = $1")
+                    .bind(rec.id)
+                    .bind(IntegrityState::Missing.as_str())
+                    .execute(pool)
+                    .await;
+                missing.push(rec.id);
+            }
+            Err(JobFailure::Cancelled) => return Err(JobFailure::Cancelled),
+            Err(JobFailure::Error(e)) => {
+                run.log(&format!("{}: {}", rec.filename, e));
+                errors += 1;
+            }
+        }
+        run.flush_if_due().await?;
+    }
+
+    run.log(&format!(
+        "{} ok, {} newly hashed, {} mismatched, {} missing, {} errors",
+        ok, baselined, mismatched.len(), missing.len(), errors
+    ));
+    Ok(Some(serde_json::json!({
+        "checked": candidates.len(),
+        "ok": ok,
+        "baselined": baselined,
+        "mismatched": mismatched,
+        "missing": missing,
+        "errors": errors,
+    })))
+}
+
+/// Hash a recording from wherever it lives; None when neither copy exists any more.
+async fn hash_recording(run: &mut JobRun<'_>, rec: &VerifyCandidate, span: (f32, f32)) -> Result<Option<String>, JobFailure> {
+    let source = match locate_recording(run.pool, rec.id, &rec.filename).await {
+        Ok(s) => s,
+        Err(r) if r.status() == StatusCode::NOT_FOUND => return Ok(None),
+        Err(r) => return Err(format!("Recording unavailable ({})", r.status()).into()),
+    };
+    match source {
+        RecordingSource::Local(path) => {
+            let file = match tokio::fs::File::open(&path).await {
+                Ok(f) => f,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+                Err(e) => return Err(format!("Failed to open: {}", e).into()),
+            };
+            let total = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+            hash_reader(run, file, total, span).await.map(Some)
+        }
+        RecordingSource::Archived { backend, key, .. } => {
+            let total = match backend.size(&key).await {
+                Ok(Some(n)) => n,
+                Ok(None) => return Ok(None),
+                Err(e) => return Err(format!("Archive Read Error: {}", e).into()),
+            };
+            let reader = backend
+                .open(&key, None)
+                .await
+                .map_err(|e| format!("Archive Read Error: {}", e))?;
+            hash_reader(run, reader, total, span).await.map(Some)
+        }
+    }
+}
+
+/// Run a remux/transcode command into a hidden temp file, then swap it in for the original.
+async fn replace_output(
+    run: &mut JobRun<'_>,
+    step: &PipelineStep,
+    recording_id: Uuid,
+    base: &FsPath,
+    rec: &JobRecording,
+) -> Result<String, JobFailure> {
+    let pool = run.pool;
+    let input = base.join(&rec.filename);
+    let relative = rec.filename.replace('\\', "/");
+    let current_name = relative.rsplit('/').next().unwrap_or(&relative).to_string();
+    let (stem, input_ext) = match current_name.rsplit_once('.') {
+        Some((s, e)) if !s.is_empty() => (s.to_string(), e.to_string()),
+        _ => (current_name.clone(), String::new()),
+    };
+    let ext = step.output_ext.clone().unwrap_or(input_ext);
+    let dir = input.parent().map(FsPath::to_path_buf).unwrap_or_else(|| base.to_path_buf());
+    let temp = dir.join(format!(".{}.{}.{}", stem, run.id.simple(), ext));
+
+    if let Err(e) = run_command(run, step, &input, &temp, rec.duration_secs).await {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err(e);
+    }
+    let produced = tokio::fs::metadata(&temp).await.map(|m| m.len()).unwrap_or(0);
+    if produced == 0 {
+        let _ = tokio::fs::remove_file(&temp).await;
+        return Err("Command produced no output".to_string().into());
+    }
+
+    let mut new_relative = replace_file_name(&relative, &format!("{}.{}", stem, ext));
+    if new_relative != relative {
+        let mut n = 2;
+        while tokio::fs::metadata(base.join(&new_relative)).await.is_ok() {
+            new_relative = with_suffix(&replace_file_name(&relative, &format!("{}.{}", stem, ext)), n);
+            n += 1;
+        }
+    }
+    let target = base.join(&new_relative);
+    tokio::fs::rename(&temp, &target)
+        .await
+        .map_err(|e| format!("Failed to replace recording: {}", e))?;
+    if target != input {
+        if let Err(e) = tokio::fs::remove_file(&input).await {
+            run.log(&format!("Failed to remove original: {}", e));
+        }
+    }
+
+    // The archived copy is of the old file, so stop serving it. The backend and key stay so
+    // the next upload replaces that object instead of orphaning it.
+    sqlx::query(
+        "UPDATE recordings SET filename = $2, filepath = $3, archive_state = NULL, archive_error = NULL, archived_at = NULL
+         WHERE id = $1"
+    )
+    .bind(recording_id)
+    .bind(&new_relative)
+    .bind(target.to_string_lossy().to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| format!("DB Update Error: {}", e))?;
+    revoke_download_tokens(pool, recording_id).await;
+    if rec.archive_state.as_deref() == Some("archived") {
+        let max_attempts = load_pipeline_config(pool).await.max_attempts;
+        match enqueue_pipeline(pool, recording_id, rec.user_id, &[upload_step()], max_attempts).await {
+            Ok(_) => run.log("Queued an upload to replace the archived copy"),
+            Err(e) => run.log(&format!("Failed to queue re-upload: {}", e)),
+        }
+    }
+    match probe_recording(pool, recording_id, &target.to_string_lossy()).await {
+        Ok(info) => update_completeness(pool, recording_id, info.complete).await,
+        Err(e) => run.log(&format!("Probe failed: {}", e)),
+    }
+    // The new file becomes the reference for later verification
+    match record_checksum(pool, recording_id, &target.to_string_lossy()).await {
+        Ok(digest) => run.log(&format!("sha256 {}", digest)),
+        Err(e) => run.log(&format!("Hashing failed: {}", e)),
+    }
+    run.log(&format!("Replaced with {} ({} bytes)", new_relative, produced));
+    Ok(new_relative)
+}
+
+async fn hash_file(run: &mut JobRun<'_>, path: &FsPath) -> Result<String, JobFailure> {
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let total = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+    hash_reader(run, file, total, (0.0, 1.0)).await
+}
+
+/// SHA-256 of `reader`, moving the job's progress across `span` as its `total` bytes are read.
+async fn hash_reader<R: AsyncRead + Unpin>(run: &mut JobRun<'_>, mut reader: R, total: u64, span: (f32, f32)) -> Result<String, JobFailure> {
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; HASH_CHUNK_SIZE];
+    let mut done: u64 = 0;
+    loop {
+        let n = reader.read(&mut buf).await.map_err(|e| format!("Read Error: {}", e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        done += n as u64;
+        if total > 0 {
+            run.progress = span.0 + (span.1 - span.0) * (done as f32 / total as f32).min(1.0);
+        }
+        run.flush_if_due().await?;
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Spawn the step's program and stream its output into the job log until it exits,
+/// times out or the job is cancelled.
+async fn run_command(
+    run: &mut JobRun<'_>,
+    step: &PipelineStep,
+    input: &FsPath,
+    output: &FsPath,
+    duration_secs: Option<f64>,
+) -> Result<(), JobFailure> {
+    let input_str = input.to_string_lossy().to_string();
+    let output_str = output.to_string_lossy().to_string();
+    let dir = input.parent().map(|p| p.to_string_lossy().to_string()).unwrap_or_default();
+    let stem = input.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let duration = duration_secs.map(|d| d.to_string()).unwrap_or_default();
+    let args = expand_args(
+        &step.args,
+        &[("input", &input_str), ("output", &output_str), ("dir", &dir), ("stem", &stem), ("duration", &duration)],
+    );
+    run.log(&format!("$ {} {}", step.command, args.join(" ")));
+
+    let mut command = tokio::process::Command::new(step.command.trim());
+    command
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    #[cfg(windows)]
+    {
+        // CREATE_NO_WINDOW: don't flash a console on the capture PC
+        command.creation_flags(0x0800_0000);
+    }
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", step.command, e))?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<String>();
+    if let Some(stdout) = child.stdout.take() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stdout).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    if let Some(stderr) = child.stderr.take() {
+        let tx = tx.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                let _ = tx.send(line);
+            }
+        });
+    }
+    drop(tx);
+
+    let deadline = tokio::time::Instant::now() + std::time::Duration::from_secs(step.timeout_secs);
+    let mut ticker = tokio::time::interval(FLUSH_INTERVAL);
+    let status = loop {
+        tokio::select! {
+            Some(line) = rx.recv() => {
+                match parse_progress(&line, duration_secs) {
+                    Some(p) => run.progress = p,
+                    None => run.log(&line),
+                }
+            }
+            status = child.wait() => break status.map_err(|e| format!("Failed to wait for {}: {}", step.command, e))?,
+            _ = ticker.tick() => {
+                if run.flush().await {
+                    let _ = child.kill().await;
+                    return Err(JobFailure::Cancelled);
+                }
+            }
+            _ = tokio::time::sleep_until(deadline) => {
+                let _ = child.kill().await;
+                return Err(format!("Timed out after {}s", step.timeout_secs).into());
+            }
+        }
+    };
+    // Drain what the readers had buffered before the exit was observed
+    while let Ok(line) = rx.try_recv() {
+        if parse_progress(&line, duration_secs).is_none() {
+            run.log(&line);
+        }
+    }
+
+    if !status.success() {
+        return Err(format!("{} exited with {}", step.command, status).into());
+    }
+    Ok(())
+}
+
+async fn list_jobs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ListJobsQuery>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let mut query: QueryBuilder<Postgres> = QueryBuilder::new(format!("SELECT {} FROM jobs WHERE TRUE", JOB_SUMMARY_COLUMNS));
+    // Users see jobs on recordings they own; admins see everything
+    if role != "admin" {
+        query
+            .push(" AND recording_id IN (SELECT id FROM recordings WHERE user_id = ")
+            .push_bind(user_id)
+            .push(")");
+    }
+    if let Some(id) = params.recording_id {
+        query.push(" AND recording_id = ").push_bind(id);
+    }
+    if let Some(status) = params.status.filter(|s| !s.is_empty()) {
+        query.push(" AND status = ").push_bind(status);
+    }
+    if let Some(kind) = params.kind.filter(|s| !s.is_empty()) {
+        query.push(" AND kind = ").push_bind(kind);
+    }
+    let limit = params.limit.unwrap_or(DEFAULT_LIST_LIMIT).clamp(1, 500);
+    query.push(" ORDER BY created_at DESC, step_index LIMIT ").push_bind(limit);
+
+    match query.build_query_as::<JobSummary>().fetch_all(pool).await {
+        Ok(jobs) => Json(jobs).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+/// Load a job the caller may see, with its full log and parameters.
+async fn load_job(pool: &sqlx::PgPool, headers: &HeaderMap, id: Uuid) -> Result<JobDetail, (StatusCode, &'static str)> {
+    let (user_id, _, role) = get_user_from_header(headers)?;
+    let job: Option<JobSummary> = sqlx::query_as(&format!("SELECT {} FROM jobs WHERE id = $1", JOB_SUMMARY_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let job = job.ok_or((StatusCode::NOT_FOUND, "Job not found"))?;
+
+    if role != "admin" {
+        let owner: Option<(Option<Uuid>,)> = sqlx::query_as("SELECT user_id FROM recordings WHERE id = $1")
+            .bind(job.recording_id)
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+        if owner.and_then(|o| o.0) != Some(user_id) {
+            return Err((StatusCode::FORBIDDEN, "Access denied"));
+        }
+    }
+
+    let extra: Option<(serde_json::Value, String)> = sqlx::query_as("SELECT params, log FROM jobs WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let (params, log) = extra.unwrap_or_default();
+    Ok(JobDetail { job, params, log })
+}
+
+async fn get_job(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    match load_job(pool, &headers, id).await {
+        Ok(job) => Json(job).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Run a failed or cancelled job again, along with the steps that were cancelled behind it.
+async fn retry_job(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let job = match load_job(pool, &headers, id).await {
+        Ok(j) => j.job,
+        Err(e) => return e.into_response(),
+    };
+    if job.status != JobStatus::Failed.as_str() && job.status != JobStatus::Cancelled.as_str() {
+        return (StatusCode::CONFLICT, "Only failed or cancelled jobs can be retried").into_response();
+    }
+
+    match sqlx::query(
+        "UPDATE jobs SET status = 'queued', attempts = 0, error = NULL, cancel_requested = FALSE,
+         run_after = NOW(), finished_at = NULL
+         WHERE pipeline_id = $1 AND step_index >= $2 AND status IN ('failed', 'cancelled')"
+    )
+    .bind(job.pipeline_id)
+    .bind(job.step_index)
+    .execute(pool)
+    .await
+    {
+        Ok(_) => (StatusCode::OK, "Job queued").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+    }
+}
+
+/// Cancel a queued job outright; a running one is stopped at its next progress flush.
+async fn cancel_job(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let job = match load_job(pool, &headers, id).await {
+        Ok(j) => j.job,
+        Err(e) => return e.into_response(),
+    };
+
+    let result = if job.status == JobStatus::Queued.as_str() {
+        sqlx::query(
+            "UPDATE jobs SET status = 'cancelled', error = 'Cancelled', finished_at = NOW()
+             WHERE pipeline_id = $1 AND step_index >= $2 AND status = 'queued'"
+        )
+        .bind(job.pipeline_id)
+        .bind(job.step_index)
+        .execute(pool)
+        .await
+    } else if job.status == JobStatus::Running.as_str() {
+        sqlx::query("UPDATE jobs SET cancel_requested = TRUE WHERE id = $1")
+            .bind(id)
+            .execute(pool)
+            .await
+    } else {
+        return (StatusCode::CONFLICT, "Job already finished").into_response();
+    };
+
+    match result {
+        Ok(_) => (StatusCode::OK, "Cancel requested").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+    }
+}
+
+/// Queue the post-stop pipeline for an existing recording.
+async fn enqueue_jobs(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<EnqueuePayload>,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let row: Option<(Option<Uuid>, String)> = sqlx::query_as(
+        "SELECT user_id, status FROM recordings WHERE id = $1 AND deleted_at IS NULL"
+    )
+    .bind(payload.recording_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    let (owner, status) = match row {
+        Some(r) => r,
+        None => return (StatusCode::NOT_FOUND, "File not found").into_response(),
+    };
+    if role != "admin" && owner != Some(user_id) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+    if is_in_progress(&status) {
+        return (StatusCode::CONFLICT, "Recording still in progress").into_response();
+    }
+
+    match enqueue_post_stop(pool, payload.recording_id, owner).await {
+        Ok(Some(pipeline_id)) => Json(serde_json::json!({ "pipeline_id": pipeline_id })).into_response(),
+        Ok(None) => (StatusCode::BAD_REQUEST, "No processing steps configured").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+/// Queue a job that re-hashes every recording and flags the ones that changed. Admin only.
+async fn verify_recordings(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let active: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM jobs WHERE kind = $1 AND status IN ('queued', 'running') LIMIT 1")
+        .bind(JobKind::Verify.as_str())
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    if active.is_some() {
+        return (StatusCode::CONFLICT, "A verification is already queued or running").into_response();
+    }
+
+    let step = PipelineStep {
+        kind: JobKind::Verify,
+        command: String::new(),
+        args: Vec::new(),
+        output_ext: None,
+        timeout_secs: 24 * 3600,
+    };
+    let inserted: Result<(Uuid,), _> = sqlx::query_as(
+        "INSERT INTO jobs (recording_id, user_id, pipeline_id, step_index, kind, params, max_attempts)
+         VALUES (NULL, $1, $2, 0, $3, $4, 1) RETURNING id"
+    )
+    .bind(user_id)
+    .bind(Uuid::new_v4())
+    .bind(step.kind.as_str())
+    .bind(serde_json::to_value(&step).unwrap_or_default())
+    .fetch_one(pool)
+    .await;
+
+    match inserted {
+        Ok((id,)) => Json(serde_json::json!({ "job_id": id })).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Insert Error: {}", e)).into_response(),
+    }
+}
@@ -8,6 +8,12 @@ pub mod settings;
 pub mod user_config;
 pub mod users;
 pub mod service;
+pub mod share;
+pub mod jobs;
+pub mod uploads;
+pub mod trash;
+pub mod retention;
+pub mod edit;
 
 
 
@@ -5,10 +5,25 @@ use axum::{
     routing::{get, post},
     Router,
 };
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use crate::AppState;
 use crate::core::auth::decode_jwt;
-use crate::core::recorder::{StopRequest, RequestStatus};
+use crate::core::recorder::{EncoderInitError, StopRequest, RequestStatus};
+use crate::core::hardware::{resolve_window, WindowMatchError, WindowMatchRule};
+use crate::core::encoders::encoder_chain;
+use crate::core::hardware::HardwareInfo;
+use crate::core::resolution::{fit_pixel_budget, monitor_dims, parse_dims, pixel_count, resolve, shape, ScaleMode};
+use crate::api::settings::{load_encoder_catalogue, load_handoff_config, load_path_template};
+use crate::core::path_template::{render, replace_file_name, uses_seq, validate_component, with_suffix, TemplateVars};
+use crate::api::share::create_handoff;
+use crate::api::hardware::get_scan_timeout;
+use crate::api::files::{finalize_recording, log_status, storage_usage, transition_status, RecordingMarker, MARKER_COLUMNS};
+use crate::core::markers::validate_label;
+use crate::core::recording_state::{RecordingStatus, IN_PROGRESS_SQL};
+use crate::api::jobs::enqueue_post_stop;
+use crate::core::preflight::run_preflight;
+use crate::core::scan_job::ScanJobState;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use sqlx::FromRow;
@@ -19,6 +34,16 @@ pub struct StartRecordingPayload {
     pub mode: Option<String>, // "record" or "stream"
 }
 
+#[derive(Deserialize)]
+pub struct PreflightPayload {
+    pub mode: Option<String>, // "record" or "stream"
+}
+
+#[derive(Deserialize)]
+pub struct MarkerPayload {
+    pub label: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct RequestStopPayload {
     pub target_user_id: Uuid,
@@ -41,96 +66,93 @@ pub struct ActiveUser {
     pub username: String,
 }
 
-async fn get_sys_val(pool: &sqlx::PgPool, key: &str) -> Option<serde_json::Value> {
-    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = $1")
-        .bind(key)
-        .fetch_optional(pool)
-        .await
-        .unwrap_or(None);
-    row.map(|r| r.0)
+/// Where an effective setting came from when user config and system limits were merged.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SettingSource {
+    User,
+    SystemDefault,
+    ClampedByLimit,
+    ResolvedAtStart,
+    /// Only known once `/start` resolves it; a dry run leaves it open.
+    Unresolved,
 }
 
-fn resolution_rank_from_label(value: &str) -> i32 {
-    let v = value.trim().to_lowercase();
-    if v == "4k" || v == "2160p" {
-        return 3;
-    }
-    if v == "1080p" {
-        return 2;
-    }
-    if v == "720p" {
-        return 1;
+#[derive(Serialize, Clone, Debug)]
+pub(crate) struct EffectiveValue {
+    pub value: serde_json::Value,
+    pub source: SettingSource,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<String>,
+}
+
+impl EffectiveValue {
+    fn new<T: Serialize>(value: T, source: SettingSource) -> Self {
+        Self {
+            value: serde_json::to_value(value).unwrap_or_default(),
+            source,
+            requested: None,
+            limit: None,
+        }
     }
-    if v == "480p" {
-        return 0;
+
+    fn user<T: Serialize>(value: T) -> Self {
+        Self::new(value, SettingSource::User)
     }
-    -1
-}
 
-fn resolution_rank_from_dims(w: i32, h: i32) -> i32 {
-    let max_side = w.max(h);
-    if max_side >= 3000 {
-        3
-    } else if max_side >= 1900 {
-        2
-    } else if max_side >= 1200 {
-        1
-    } else {
-        0
+    fn system<T: Serialize>(value: T) -> Self {
+        Self::new(value, SettingSource::SystemDefault)
     }
-}
 
-fn dims_for_rank(rank: i32, portrait: bool) -> (i32, i32) {
-    let (w, h) = match rank {
-        3 => (3840, 2160),
-        2 => (1920, 1080),
-        1 => (1280, 720),
-        _ => (854, 480),
-    };
-    if portrait { (h, w) } else { (w, h) }
-}
+    fn from_user_or(value: &str, default: &str) -> Self {
+        if value.is_empty() { Self::system(default) } else { Self::user(value) }
+    }
 
-fn parse_resolution_dims(value: &str) -> Option<(i32, i32)> {
-    let v = value.trim();
-    if let Some((w, h)) = v.split_once('x') {
-        let wv = w.parse::<i32>().ok()?;
-        let hv = h.parse::<i32>().ok()?;
-        if wv > 0 && hv > 0 {
-            return Some((wv, hv));
+    /// Record that a limit replaced the value. A no-op if the limit did not change it.
+    fn clamped<T: Serialize>(self, value: T, limit: String) -> Self {
+        let value = serde_json::to_value(value).unwrap_or_default();
+        if value == self.value {
+            return self;
+        }
+        Self {
+            requested: Some(self.requested.unwrap_or(self.value)),
+            value,
+            source: SettingSource::ClampedByLimit,
+            limit: Some(limit),
         }
     }
-    let rank = resolution_rank_from_label(v);
-    if rank >= 0 {
-        return Some(dims_for_rank(rank, false));
-    }
-    None
 }
 
-fn clamp_resolution(requested: &str, max_value: &str) -> (i32, i32) {
-    let max_rank = {
-        let from_label = resolution_rank_from_label(max_value);
-        if from_label >= 0 {
-            from_label
-        } else if let Some((w, h)) = parse_resolution_dims(max_value) {
-            resolution_rank_from_dims(w, h)
-        } else {
-            2
-        }
-    };
+pub(crate) struct StartAttempt {
+    pub encoder: String,
+    pub args: Vec<String>,
+    pub settings: BTreeMap<&'static str, EffectiveValue>,
+}
 
-    let (req_w, req_h, portrait, req_rank) = if let Some((w, h)) = parse_resolution_dims(requested) {
-        (w, h, h > w, resolution_rank_from_dims(w, h))
-    } else {
-        (0, 0, false, max_rank)
-    };
+/// Arguments for one start, with one attempt per encoder in fallback order.
+pub(crate) struct StartParams {
+    pub attempts: Vec<StartAttempt>,
+    /// Settings shared by every attempt (capture target, audio, output).
+    pub settings: BTreeMap<&'static str, EffectiveValue>,
+    /// Preferred encoders that were dropped because the catalogue no longer allows them.
+    pub skipped_encoders: Vec<String>,
+    pub filename: Option<String>,
+    /// Where cli-capture writes the recording, stored as the row's filepath.
+    pub output_path: Option<String>,
+    /// `screen` or `window`, stored on the recording row for filtering.
+    pub capture_mode: String,
+    pub cli_path: String,
+}
 
-    if req_rank > max_rank {
-        return dims_for_rank(max_rank, portrait);
-    }
-    if req_w > 0 && req_h > 0 {
-        return (req_w, req_h);
-    }
-    dims_for_rank(max_rank, false)
+async fn get_sys_val(pool: &sqlx::PgPool, key: &str) -> Option<serde_json::Value> {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = $1")
+        .bind(key)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.map(|r| r.0)
 }
 
 pub(crate) fn validate_device_id(id: &str) -> Result<(), &'static str> {
@@ -184,25 +206,25 @@ pub(crate) fn validate_window_id(id: &str) -> Result<(), &'static str> {
     Ok(())
 }
 
+pub(crate) fn validate_window_rule_value(value: &str) -> Result<(), &'static str> {
+    if value.chars().any(|c| c.is_control()) {
+        return Err("Invalid characters in window match rule");
+    }
+    Ok(())
+}
+
 pub(crate) fn validate_filename(name: &str) -> Result<(), &'static str> {
     if name.is_empty() {
         return Err("Filename cannot be empty");
     }
-    let forbidden_chars = ['/', '\\', ':', '*', '?', '"', '<', '>', '|', '\0', '\n', '\r', ';', '&', '$', '`'];
-    if name.chars().any(|c| forbidden_chars.contains(&c)) {
-        return Err("Invalid characters in filename");
-    }
-    if name.contains("..") {
-        return Err("Path traversal not allowed in filename");
-    }
-    Ok(())
+    validate_component(name)
 }
 
 pub(crate) fn validate_resolution_value(value: &str, allow_empty: bool) -> Result<(), &'static str> {
     if value.trim().is_empty() {
         return if allow_empty { Ok(()) } else { Err("Resolution is required") };
     }
-    if parse_resolution_dims(value).is_some() {
+    if parse_dims(value).is_some() {
         Ok(())
     } else {
         Err("Invalid resolution format")
@@ -264,35 +286,28 @@ pub(crate) fn validate_resolution_limit(requested: &str, max_value: &str) -> Res
     if requested.trim().is_empty() {
         return Ok(());
     }
-    let requested_rank = if let Some((w, h)) = parse_resolution_dims(requested) {
-        resolution_rank_from_dims(w, h)
-    } else {
-        return Err("Invalid resolution format");
-    };
-    let max_rank = {
-        let from_label = resolution_rank_from_label(max_value);
-        if from_label >= 0 {
-            from_label
-        } else if let Some((w, h)) = parse_resolution_dims(max_value) {
-            resolution_rank_from_dims(w, h)
-        } else {
-            return Err("Invalid max_res format");
-        }
-    };
-    if requested_rank > max_rank {
+    let requested = parse_dims(requested).ok_or("Invalid resolution format")?;
+    let max = parse_dims(max_value).ok_or("Invalid max_res format")?;
+    // The limit is a pixel budget, so any shape fits as long as the area does
+    if pixel_count(requested) > pixel_count(max) {
         Err("Resolution exceeds system limit")
     } else {
         Ok(())
     }
 }
 
+pub(crate) fn validate_scale_mode(value: &str) -> Result<(), &'static str> {
+    ScaleMode::parse(value).map(|_| ()).ok_or("Invalid scale_mode")
+}
+
 async fn build_start_params(
     pool: &sqlx::PgPool,
     user_id: Uuid,
     username: &str,
     mode: String,
     filename_override: Option<String>,
-) -> Result<(Vec<String>, Option<String>, String), Response> {
+    dry_run: bool,
+) -> Result<StartParams, Response> {
     let sys_max_bitrate = get_sys_val(pool, "max_bitrate").await.and_then(|v| v.as_i64()).unwrap_or(4000) as i32;
     let sys_max_fps = get_sys_val(pool, "max_fps").await.and_then(|v| v.as_i64()).unwrap_or(30) as i32;
     let sys_max_res = get_sys_val(pool, "max_res").await.and_then(|v| v.as_str().map(String::from)).unwrap_or("1920x1080".to_string());
@@ -303,15 +318,18 @@ async fn build_start_params(
     validate_resolution_value(&sys_max_res, false).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
     validate_encoder_id(&sys_encoder).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
 
-    let user_config = sqlx::query_as::<_, crate::api::user_config::UserConfig>("SELECT max_bitrate, max_fps, resolution, monitor_id, desktop_audio, mic_audio, rtmp_url, rtmp_key, capture_mode, capture_method, window_id FROM user_configs WHERE user_id = $1")
+    let user_config = sqlx::query_as::<_, crate::api::user_config::UserConfig>("SELECT max_bitrate, max_fps, resolution, monitor_id, desktop_audio, mic_audio, rtmp_url, rtmp_key, capture_mode, capture_method, window_id, window_exe, window_class, window_title_pattern, video_encoders, scale_mode FROM user_configs WHERE user_id = $1")
         .bind(user_id)
         .fetch_optional(pool)
         .await
         .unwrap_or(None);
     
-    let bitrate = user_config.as_ref().and_then(|c| c.max_bitrate).unwrap_or(sys_max_bitrate);
-    let fps = user_config.as_ref().and_then(|c| c.max_fps).unwrap_or(sys_max_fps).min(sys_max_fps);
-    let resolution = user_config.as_ref().and_then(|c| c.resolution.clone()).unwrap_or(sys_max_res.clone());
+    let user_bitrate = user_config.as_ref().and_then(|c| c.max_bitrate);
+    let user_fps = user_config.as_ref().and_then(|c| c.max_fps);
+    let user_resolution = user_config.as_ref().and_then(|c| c.resolution.clone()).filter(|r| !r.trim().is_empty());
+    let bitrate = user_bitrate.unwrap_or(sys_max_bitrate);
+    let fps = user_fps.unwrap_or(sys_max_fps).min(sys_max_fps);
+    let resolution = user_resolution.clone().unwrap_or(sys_max_res.clone());
     validate_max_bitrate(bitrate).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
     validate_max_fps(fps).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
     validate_resolution_value(&resolution, false).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
@@ -322,7 +340,16 @@ async fn build_start_params(
     let monitor_id = user_config.as_ref().and_then(|c| c.monitor_id.clone()).unwrap_or_default();
     let capture_mode = user_config.as_ref().and_then(|c| c.capture_mode.clone()).unwrap_or_default();
     let capture_method = user_config.as_ref().and_then(|c| c.capture_method.clone()).unwrap_or_default();
+    let scale_mode_value = user_config.as_ref().and_then(|c| c.scale_mode.clone()).unwrap_or_default();
     let window_id = user_config.as_ref().and_then(|c| c.window_id.clone()).unwrap_or_default();
+    let window_rule = user_config.as_ref()
+        .and_then(|c| c.window_exe.clone())
+        .filter(|exe| !exe.trim().is_empty())
+        .map(|exe| WindowMatchRule {
+            exe,
+            class: user_config.as_ref().and_then(|c| c.window_class.clone()),
+            title_pattern: user_config.as_ref().and_then(|c| c.window_title_pattern.clone()),
+        });
 
     // Validate all user inputs to prevent command injection
     validate_device_id(&monitor_id).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
@@ -333,25 +360,39 @@ async fn build_start_params(
     validate_capture_method(&capture_method).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
     validate_rtmp_url(&rtmp_url).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
     validate_rtmp_key(&rtmp_key).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+    let scale_mode = ScaleMode::parse(&scale_mode_value).ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid scale_mode").into_response())?;
 
-    let mut args = Vec::new();
-    args.push("--bitrate".to_string());
-    args.push(bitrate.to_string());
-    
-    args.push("--fps".to_string());
-    args.push(fps.to_string());
+    // Shape follows the monitor being captured; window captures have no fixed source size
+    let source_dims = if capture_mode == "window" {
+        None
+    } else {
+        get_sys_val(pool, "hardware_info").await
+            .and_then(|v| serde_json::from_value::<HardwareInfo>(v).ok())
+            .and_then(|info| monitor_dims(&info.screens, &monitor_id))
+    };
 
-    let (w, h) = clamp_resolution(&resolution, &sys_max_res);
-    args.push("--width".to_string());
-    args.push(w.to_string());
-    args.push("--height".to_string());
-    args.push(h.to_string());
+    let preferred_encoders = user_config.as_ref().and_then(|c| c.video_encoders.clone()).unwrap_or_default();
+    let catalogue = load_encoder_catalogue(pool).await;
+    let chain = encoder_chain(&catalogue, &preferred_encoders, &sys_encoder);
+    let skipped_encoders: Vec<String> = preferred_encoders.iter().filter(|id| !chain.contains(id)).cloned().collect();
 
-    args.push("--encoder".to_string());
-    args.push(sys_encoder);
-    
+    let mut settings = BTreeMap::new();
+    settings.insert("mode", EffectiveValue::user(&mode));
+    settings.insert("capture_mode", EffectiveValue::from_user_or(&capture_mode, "screen"));
+    settings.insert("scale_mode", if scale_mode_value.is_empty() {
+        EffectiveValue::system(scale_mode)
+    } else {
+        EffectiveValue::user(scale_mode)
+    });
+    if let Some((w, h)) = source_dims {
+        settings.insert("source_resolution", EffectiveValue::new(format!("{}x{}", w, h), SettingSource::ResolvedAtStart));
+    }
+
+    // Output, audio and capture arguments are shared by every encoder attempt
+    let mut args = Vec::new();
     if mode == "stream" {
         if !rtmp_url.is_empty() {
+            settings.insert("rtmp_url", EffectiveValue::user(&rtmp_url));
             args.push("--rtmp".to_string());
             args.push(rtmp_url);
         } else {
@@ -366,56 +407,108 @@ async fn build_start_params(
     }
 
     if !desktop_audio.is_empty() {
+        settings.insert("desktop_audio", EffectiveValue::user(&desktop_audio));
         args.push("--desktop-audio".to_string());
         args.push(desktop_audio);
     }
     if !mic_audio.is_empty() {
+        settings.insert("mic_audio", EffectiveValue::user(&mic_audio));
         args.push("--mic-audio".to_string());
         args.push(mic_audio);
     }
     if capture_mode == "window" {
+        let window_id = match window_rule {
+            // Resolving means running `--scan-windows`, which a dry run must not do
+            Some(rule) if dry_run => {
+                settings.insert("window_rule", EffectiveValue::user(&rule));
+                settings.insert("window", EffectiveValue::new(serde_json::Value::Null, SettingSource::Unresolved));
+                "<resolved at start>".to_string()
+            }
+            Some(rule) => {
+                settings.insert("window_rule", EffectiveValue::user(&rule));
+                let cli_path = get_sys_val(pool, "cli_capture_path").await
+                    .and_then(|v| v.as_str().map(String::from))
+                    .unwrap_or_default();
+                match resolve_window(&cli_path, &rule).await {
+                    Ok(window) => {
+                        settings.insert("window", EffectiveValue {
+                            source: SettingSource::ResolvedAtStart,
+                            ..EffectiveValue::user(&window.id)
+                        });
+                        window.id
+                    }
+                    Err(e) => {
+                        let msg = e.to_string();
+                        let status = match e.downcast_ref::<WindowMatchError>() {
+                            Some(WindowMatchError::Ambiguous { .. }) => StatusCode::CONFLICT,
+                            Some(WindowMatchError::NoMatch { .. }) => StatusCode::NOT_FOUND,
+                            None if is_cli_config_error(&msg) => StatusCode::BAD_REQUEST,
+                            None => {
+                                return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Window scan failed: {}", msg)).into_response())
+                            }
+                        };
+                        return Err((status, msg).into_response());
+                    }
+                }
+            }
+            None => {
+                settings.insert("window", EffectiveValue::user(&window_id));
+                window_id
+            }
+        };
         if window_id.is_empty() {
             return Err((StatusCode::BAD_REQUEST, "window_id is required for window capture").into_response());
         }
+        validate_window_id(&window_id).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
         args.push("--window".to_string());
         args.push(window_id);
     } else {
         if !monitor_id.is_empty() {
+            settings.insert("monitor", EffectiveValue::user(&monitor_id));
             args.push("--monitor".to_string());
             args.push(monitor_id);
         }
         if !capture_method.is_empty() {
+            settings.insert("capture_method", EffectiveValue::user(&capture_method));
             args.push("--method".to_string());
             args.push(capture_method);
         }
     }
 
     let mut filename = None;
+    let mut output_path = None;
     if mode == "record" {
-        let name = filename_override.unwrap_or_else(|| format!("{}_{}.mp4", username, chrono::Utc::now().timestamp()));
-        
-        // Validate filename
-        validate_filename(&name).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+        let name_source = if filename_override.is_some() { SettingSource::User } else { SettingSource::SystemDefault };
+        if let Some(name) = filename_override.as_deref() {
+            validate_filename(name).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+        }
 
         let global_path_row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'global_recording_path'")
             .fetch_optional(pool)
             .await
             .unwrap_or(None);
-        
-        let full_path = if let Some((val,)) = global_path_row {
-            let base = val.as_str().unwrap_or("");
-            if !base.is_empty() {
-                std::path::Path::new(base).join(&name).to_string_lossy().to_string()
-            } else {
-                name.clone()
-            }
-        } else {
-            name.clone()
+        let base = global_path_row
+            .and_then(|(val,)| val.as_str().map(String::from))
+            .filter(|b| !b.is_empty())
+            .map(std::path::PathBuf::from);
+
+        let template = load_path_template(pool).await;
+        let user_id_str = user_id.to_string();
+        let vars = TemplateVars { username, user_id: &user_id_str, now: chrono::Local::now(), ext: "mp4" };
+        let name = allocate_recording_name(pool, base.as_deref(), &template, &vars, filename_override.as_deref())
+            .await
+            .map_err(|e| e.into_response())?;
+
+        let full_path = match &base {
+            Some(b) => b.join(&name).to_string_lossy().to_string(),
+            None => name.clone(),
         };
 
+        settings.insert("output", EffectiveValue { source: name_source, ..EffectiveValue::user(&full_path) });
         args.push("--output".to_string());
-        args.push(full_path);
+        args.push(full_path.clone());
         filename = Some(name);
+        output_path = Some(full_path);
     }
 
     let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'cli_capture_path'")
@@ -428,13 +521,215 @@ async fn build_start_params(
         None => "".to_string(),
     };
 
-    Ok((args, filename, cli_path))
+    let bitrate_base = if user_bitrate.is_some() { EffectiveValue::user(bitrate) } else { EffectiveValue::system(bitrate) };
+    let mut fps_base = if user_fps.is_some() { EffectiveValue::user(user_fps.unwrap_or(fps)) } else { EffectiveValue::system(fps) };
+    fps_base = fps_base.clamped(fps, format!("system max_fps {}", sys_max_fps));
+    let sys_limit = parse_dims(&sys_max_res).ok_or_else(|| (StatusCode::BAD_REQUEST, "Invalid max_res format").into_response())?;
+    let requested_dims = user_resolution.as_deref().and_then(parse_dims);
+    let shaped = shape(requested_dims, source_dims, scale_mode).unwrap_or(sys_limit);
+    let (sys_w, sys_h) = resolve(requested_dims, source_dims, sys_limit, scale_mode);
+    let resolution_base = match (user_resolution.as_ref(), source_dims) {
+        (Some(r), _) => EffectiveValue::user(r),
+        (None, Some((w, h))) => EffectiveValue::new(format!("{}x{}", w, h), SettingSource::ResolvedAtStart),
+        (None, None) => EffectiveValue::system(&sys_max_res),
+    }
+    .clamped(format!("{}x{}", shaped.0, shaped.1), format!("scale_mode {:?}", scale_mode).to_lowercase())
+    .clamped(format!("{}x{}", sys_w, sys_h), format!("system max_res {} pixel budget", sys_max_res));
+
+    let mut attempts = Vec::new();
+    for encoder in chain {
+        validate_encoder_id(&encoder).map_err(|e| (StatusCode::BAD_REQUEST, e).into_response())?;
+        let limits = catalogue.iter().find(|e| e.id == encoder);
+        let bitrate = limits.and_then(|l| l.max_bitrate).map_or(bitrate, |max| bitrate.min(max));
+        let fps = limits.and_then(|l| l.max_fps).map_or(fps, |max| fps.min(max));
+        let (mut w, mut h) = (sys_w, sys_h);
+        if let Some(max) = limits.and_then(|l| l.max_res.as_deref()).and_then(parse_dims) {
+            (w, h) = fit_pixel_budget((w, h), pixel_count(max));
+        }
+
+        let mut attempt_settings = BTreeMap::new();
+        attempt_settings.insert("encoder", if preferred_encoders.contains(&encoder) {
+            EffectiveValue::user(&encoder)
+        } else {
+            EffectiveValue::system(&encoder)
+        });
+        attempt_settings.insert("bitrate", bitrate_base.clone().clamped(bitrate, format!("{} max_bitrate", encoder)));
+        attempt_settings.insert("fps", fps_base.clone().clamped(fps, format!("{} max_fps", encoder)));
+        attempt_settings.insert("resolution", resolution_base.clone().clamped(format!("{}x{}", w, h), format!("{} max_res", encoder)));
+
+        let mut encoder_args = vec![
+            "--bitrate".to_string(),
+            bitrate.to_string(),
+            "--fps".to_string(),
+            fps.to_string(),
+            "--width".to_string(),
+            w.to_string(),
+            "--height".to_string(),
+            h.to_string(),
+            "--encoder".to_string(),
+            encoder.clone(),
+        ];
+        encoder_args.extend(args.iter().cloned());
+        attempts.push(StartAttempt { encoder, args: encoder_args, settings: attempt_settings });
+    }
+
+    let capture_mode = if capture_mode.is_empty() { "screen".to_string() } else { capture_mode };
+    Ok(StartParams { attempts, settings, skipped_encoders, filename, output_path, capture_mode, cli_path })
+}
+
+// Give up looking for a free name after this many suffixes
+const MAX_NAME_ATTEMPTS: u32 = 1000;
+
+/// Expand the path template into a relative name that neither exists on disk nor
+/// belongs to another recording. Templates with `{seq}` count up; others get `_2`, `_3`...
+async fn allocate_recording_name(
+    pool: &sqlx::PgPool,
+    base: Option<&std::path::Path>,
+    template: &str,
+    vars: &TemplateVars<'_>,
+    override_name: Option<&str>,
+) -> Result<String, (StatusCode, String)> {
+    let counting = uses_seq(template) && override_name.is_none();
+    for n in 1..=MAX_NAME_ATTEMPTS {
+        let rendered = render(template, vars, if counting { n } else { 1 })
+            .map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+        let candidate = match override_name {
+            Some(name) => replace_file_name(&rendered, name),
+            None => rendered,
+        };
+        let candidate = if counting || n == 1 { candidate } else { with_suffix(&candidate, n) };
+
+        let on_disk = match base {
+            Some(b) => b.join(&candidate),
+            None => std::path::PathBuf::from(&candidate),
+        };
+        if tokio::fs::metadata(&on_disk).await.is_ok() {
+            continue;
+        }
+        let taken: Option<(Uuid,)> = sqlx::query_as("SELECT id FROM recordings WHERE filename = $1 AND deleted_at IS NULL")
+            .bind(&candidate)
+            .fetch_optional(pool)
+            .await
+            .unwrap_or(None);
+        if taken.is_none() {
+            return Ok(candidate);
+        }
+    }
+    Err((StatusCode::CONFLICT, "Could not find a free recording name".to_string()))
+}
+
+/// Start cli-capture with each encoder in turn until one initialises.
+/// Returns the id of the encoder that is now running.
+async fn start_with_fallback(state: &Arc<AppState>, user_id: Uuid, params: StartParams, mode: &str) -> anyhow::Result<String> {
+    // Templates may put recordings in per-user or per-date folders
+    if let Some(parent) = params.output_path.as_deref().and_then(|p| std::path::Path::new(p).parent()) {
+        if !parent.as_os_str().is_empty() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| anyhow::anyhow!("Failed to create recording directory: {}", e))?;
+        }
+    }
+
+    let total = params.attempts.len();
+    let mut last_err = anyhow::anyhow!("No encoder available");
+    for (i, StartAttempt { encoder, args, .. }) in params.attempts.into_iter().enumerate() {
+        match state.recorder_manager.start_recording(user_id, params.cli_path.clone(), args, mode.to_string()).await {
+            Ok(_) => return Ok(encoder),
+            Err(e) if e.is::<EncoderInitError>() && i + 1 < total => {
+                tracing::warn!("Encoder {} failed to initialise, trying next: {}", encoder, e);
+                last_err = e;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+    Err(last_err)
+}
+
+/// `start_with_fallback` plus the recording row: inserted as pending, moved to recording once
+/// cli-capture is up, or to failed if it died during startup. Streaming has no row.
+async fn start_tracked(state: &Arc<AppState>, pool: &sqlx::PgPool, user_id: Uuid, params: StartParams, mode: &str) -> anyhow::Result<String> {
+    let pending = match params.filename.as_deref() {
+        Some(name) => {
+            let inserted: Result<(Uuid,), sqlx::Error> = sqlx::query_as(
+                "INSERT INTO recordings (user_id, filename, filepath, status, capture_mode) VALUES ($1, $2, $3, 'pending', $4) RETURNING id"
+            )
+            .bind(user_id)
+            .bind(name)
+            .bind(params.output_path.as_deref().unwrap_or(name))
+            .bind(&params.capture_mode)
+            .fetch_one(pool)
+            .await;
+            match inserted {
+                Ok((id,)) => {
+                    if let Err(e) = log_status(pool, id, None, RecordingStatus::Pending, "start requested").await {
+                        tracing::warn!("Failed to log status of recording {}: {}", id, e);
+                    }
+                    Some(id)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to insert recording {}: {}", name, e);
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    let result = start_with_fallback(state, user_id, params, mode).await;
+    if let Some(id) = pending {
+        let error = match &result {
+            Ok(encoder) => transition_status(pool, id, RecordingStatus::Recording, &format!("capture started with {}", encoder)).await.err(),
+            // Nothing was launched, so there is no attempt worth keeping
+            Err(e) if is_cli_config_error(&e.to_string()) || e.to_string().contains("Another recording in progress") => {
+                sqlx::query("DELETE FROM recordings WHERE id = $1").bind(id).execute(pool).await.err().map(Into::into)
+            }
+            Err(e) => transition_status(pool, id, RecordingStatus::Failed, &e.to_string()).await.err(),
+        };
+        if let Some(e) = error {
+            tracing::warn!("Failed to update status of recording {}: {}", id, e);
+        }
+    }
+    result
+}
+
+/// Copy of a cli-capture argument list that is safe to show back to the user.
+pub(crate) fn redact_args(args: &[String]) -> Vec<String> {
+    let mut redacted = Vec::with_capacity(args.len());
+    let mut hide_next = false;
+    for arg in args {
+        if hide_next {
+            redacted.push("***".to_string());
+            hide_next = false;
+            continue;
+        }
+        hide_next = arg == "--key";
+        redacted.push(arg.clone());
+    }
+    redacted
+}
+
+/// Flag/value pairs of a redacted argument list as a JSON object, e.g. `{"bitrate": "4000"}`.
+fn args_to_settings(args: &[String]) -> serde_json::Map<String, serde_json::Value> {
+    let mut settings = serde_json::Map::new();
+    let mut iter = redact_args(args).into_iter().peekable();
+    while let Some(arg) = iter.next() {
+        let Some(flag) = arg.strip_prefix("--") else { continue };
+        let value = match iter.peek() {
+            Some(next) if !next.starts_with("--") => serde_json::Value::String(iter.next().unwrap_or_default()),
+            _ => serde_json::Value::Bool(true),
+        };
+        settings.insert(flag.replace('-', "_"), value);
+    }
+    settings
 }
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/start", post(start_recording))
         .route("/stop", post(stop_recording))
+        .route("/marker", post(add_marker))
+        .route("/preflight", post(preflight))
+        .route("/effective-config", get(effective_config))
         .route("/status", get(get_status))
         .route("/active", get(get_active_users))
         .route("/request-stop", post(request_stop))
@@ -459,6 +754,28 @@ pub fn get_user_from_header(headers: &HeaderMap) -> Result<(Uuid, String, String
     Ok((user_id, claims.username, claims.role))
 }
 
+/// Refuse to start a recording once the user's stored recordings reach their quota, or to
+/// accept `incoming` more bytes that would take them past it.
+pub(crate) async fn check_storage_quota(pool: &sqlx::PgPool, user_id: Uuid, incoming: i64) -> Result<(), (StatusCode, String)> {
+    let quota: Option<(Option<i64>,)> = sqlx::query_as("SELECT storage_quota_bytes FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let quota = match quota.and_then(|q| q.0) {
+        Some(q) => q,
+        None => return Ok(()),
+    };
+    let used = storage_usage(pool, user_id).await;
+    if used >= quota || used + incoming > quota {
+        return Err((
+            StatusCode::INSUFFICIENT_STORAGE,
+            format!("Storage quota exceeded: {} of {} bytes used", used, quota),
+        ));
+    }
+    Ok(())
+}
+
 async fn start_recording(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -469,6 +786,9 @@ async fn start_recording(
         Err(e) => return e.into_response(),
     };
 
+    // Held until the process is running (or has failed) so no scan can start in between
+    let _claim = state.encoder_lock.lock().await;
+
     // Check if recording
     if state.recorder_manager.is_recording(user_id).await {
          return (StatusCode::BAD_REQUEST, "Process already in progress").into_response();
@@ -476,6 +796,9 @@ async fn start_recording(
     if state.recorder_manager.has_any_recording().await {
         return (StatusCode::CONFLICT, "Another user is recording").into_response();
     }
+    if state.scan_jobs.is_running().await {
+        return (StatusCode::CONFLICT, "Hardware scan in progress").into_response();
+    }
 
     let db_guard = state.db.read().await;
     let pool = match db_guard.as_ref() {
@@ -484,26 +807,18 @@ async fn start_recording(
     };
 
     let mode = payload.mode.unwrap_or_else(|| "record".to_string());
-    let (args, filename, cli_path) = match build_start_params(pool, user_id, &username, mode.clone(), payload.filename.clone()).await {
+    if mode == "record" {
+        if let Err(e) = check_storage_quota(pool, user_id, 0).await {
+            return e.into_response();
+        }
+    }
+    let params = match build_start_params(pool, user_id, &username, mode.clone(), payload.filename.clone(), false).await {
         Ok(v) => v,
         Err(r) => return r,
     };
 
-    match state.recorder_manager.start_recording(user_id, cli_path, args, mode.clone()).await {
-        Ok(_) => {
-            if let Some(name) = filename {
-                 let _ = sqlx::query(
-                    "INSERT INTO recordings (user_id, filename, filepath, status) VALUES ($1, $2, $3, 'recording')"
-                )
-                .bind(user_id)
-                .bind(&name)
-                .bind(format!("/recordings/{}", name))
-                .execute(pool)
-                .await;
-            }
-            
-            (StatusCode::OK, format!("{} started", if mode == "record" { "Recording" } else { "Streaming" })).into_response()
-        },
+    match start_tracked(&state, pool, user_id, params, &mode).await {
+        Ok(_) => (StatusCode::OK, format!("{} started", if mode == "record" { "Recording" } else { "Streaming" })).into_response(),
         Err(e) => {
             let msg = e.to_string();
             if msg.contains("Another recording in progress") {
@@ -517,6 +832,131 @@ async fn start_recording(
     }
 }
 
+async fn preflight(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<PreflightPayload>,
+) -> impl IntoResponse {
+    let (user_id, username, _) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    // --test still loads OBS and creates the capture sources, so it cannot share the machine with a session
+    let claim = match state.encoder_lock.try_lock() {
+        Ok(guard) => guard,
+        Err(_) => return (StatusCode::CONFLICT, "A recording is starting").into_response(),
+    };
+    if state.recorder_manager.has_any_recording().await {
+        return (StatusCode::CONFLICT, "A recording is holding the encoder").into_response();
+    }
+
+    let (params, timeout) = {
+        let db_guard = state.db.read().await;
+        let pool = match db_guard.as_ref() {
+            Some(p) => p,
+            None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+        };
+
+        let mode = payload.mode.unwrap_or_else(|| "record".to_string());
+        let params = match build_start_params(pool, user_id, &username, mode, None, false).await {
+            Ok(v) => v,
+            Err(r) => return r,
+        };
+        (params, get_scan_timeout(pool).await)
+    };
+
+    let args = match params.attempts.into_iter().next() {
+        Some(attempt) => attempt.args,
+        None => return (StatusCode::BAD_REQUEST, "No encoder available").into_response(),
+    };
+    let settings = args_to_settings(&args);
+    let cli_path = params.cli_path;
+
+    let work = async move {
+        let report = run_preflight(&cli_path, &args).await?;
+        Ok(serde_json::to_value(report)?)
+    };
+
+    let handle = match state.scan_jobs.start("preflight", user_id, timeout, work).await {
+        Ok((_, handle)) => handle,
+        Err(e) => return (StatusCode::CONFLICT, e.to_string()).into_response(),
+    };
+    drop(claim);
+    let job = match handle.await {
+        Ok(j) => j,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("Preflight failed: {}", e)).into_response(),
+    };
+
+    match job.state {
+        ScanJobState::Completed => {
+            let mut report = job.result.unwrap_or_default();
+            if let Some(obj) = report.as_object_mut() {
+                obj.insert("settings".to_string(), serde_json::Value::Object(settings));
+            }
+            Json(report).into_response()
+        }
+        ScanJobState::TimedOut => (StatusCode::GATEWAY_TIMEOUT, job.error.unwrap_or_default()).into_response(),
+        ScanJobState::Cancelled => (StatusCode::CONFLICT, job.error.unwrap_or_default()).into_response(),
+        _ => {
+            let msg = job.error.unwrap_or_default();
+            if is_cli_config_error(&msg) {
+                return (StatusCode::BAD_REQUEST, msg).into_response();
+            }
+            (StatusCode::INTERNAL_SERVER_ERROR, format!("Preflight failed: {}", msg)).into_response()
+        }
+    }
+}
+
+/// Dry run of the start path: resolve the settings as `/start` would and explain where each
+/// value came from, without launching cli-capture. A window match rule is reported but left
+/// unresolved, since resolving it needs a window scan.
+async fn effective_config(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(payload): Query<PreflightPayload>,
+) -> impl IntoResponse {
+    let (user_id, username, _) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let mode = payload.mode.unwrap_or_else(|| "record".to_string());
+    let params = match build_start_params(pool, user_id, &username, mode.clone(), None, true).await {
+        Ok(v) => v,
+        Err(r) => return r,
+    };
+
+    let encoders: Vec<serde_json::Value> = params
+        .attempts
+        .iter()
+        .map(|a| {
+            serde_json::json!({
+                "encoder": a.encoder,
+                "settings": a.settings,
+                "args": redact_args(&a.args),
+            })
+        })
+        .collect();
+    let args = params.attempts.first().map(|a| redact_args(&a.args)).unwrap_or_default();
+
+    Json(serde_json::json!({
+        "mode": mode,
+        "cli_path": params.cli_path,
+        "settings": params.settings,
+        "encoders": encoders,
+        "skipped_encoders": params.skipped_encoders,
+        "args": args,
+    }))
+    .into_response()
+}
+
 fn is_cli_config_error(msg: &str) -> bool {
     msg.contains("CLI path")
         || msg.contains("CLI is not executable")
@@ -532,29 +972,211 @@ async fn stop_recording(
         Err(e) => return e.into_response(),
     };
 
-    perform_stop(&state, user_id).await
+    perform_stop(&state, user_id, Some(&headers)).await
 }
 
-async fn perform_stop(state: &Arc<AppState>, user_id: Uuid) -> Response {
+/// Stop the user's process and start finalizing their recordings. With `handoff_headers`
+/// the response carries a QR hand-off for each finished recording.
+async fn perform_stop(state: &Arc<AppState>, user_id: Uuid, handoff_headers: Option<&HeaderMap>) -> Response {
     match state.recorder_manager.stop_recording(user_id).await {
         Ok(_) => {
-             // Update DB status
             let db_guard = state.db.read().await;
+            let mut handoff = Vec::new();
             if let Some(pool) = db_guard.as_ref() {
-                let _ = sqlx::query(
-                    "UPDATE recordings SET status = 'stopped' WHERE user_id = $1 AND status = 'recording'"
-                )
-                .bind(user_id)
-                .execute(pool)
-                .await;
+                let stopped = begin_finalizing(pool, user_id, "stopped").await;
+
+                if let Some(headers) = handoff_headers {
+                    if load_handoff_config(pool).await.enabled {
+                        for (id, _) in &stopped {
+                            match create_handoff(pool, headers, user_id, *id).await {
+                                Ok(h) => handoff.push(h),
+                                Err((_, e)) => tracing::warn!("Failed to create hand-off for {}: {}", id, e),
+                            }
+                        }
+                    }
+                }
+
+                // Finalize in the background so stop returns as soon as the process is gone
+                tokio::spawn(finish_recordings(pool.clone(), user_id, stopped));
             }
 
-            (StatusCode::OK, "Process stopped").into_response()
+            Json(serde_json::json!({ "message": "Process stopped", "handoff": handoff })).into_response()
         },
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to stop: {}", e)).into_response(),
     }
 }
 
+/// Move the user's live recordings to finalizing, returning their ids and paths.
+async fn begin_finalizing(pool: &sqlx::PgPool, user_id: Uuid, reason: &str) -> Vec<(Uuid, String)> {
+    let live: Vec<(Uuid, String)> = sqlx::query_as(
+        "SELECT id, filepath FROM recordings WHERE user_id = $1 AND status = 'recording' AND deleted_at IS NULL"
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    let mut finalizing = Vec::new();
+    for (id, filepath) in live {
+        match transition_status(pool, id, RecordingStatus::Finalizing, reason).await {
+            Ok(_) => finalizing.push((id, filepath)),
+            Err(e) => tracing::warn!("Failed to finalize recording {}: {}", id, e),
+        }
+    }
+    finalizing
+}
+
+/// Wait for each file to settle, then hand the usable ones to the processing queue.
+async fn finish_recordings(pool: sqlx::PgPool, user_id: Uuid, recordings: Vec<(Uuid, String)>) {
+    for (id, filepath) in recordings {
+        match finalize_recording(&pool, id, &filepath).await {
+            Ok(RecordingStatus::Failed) => tracing::warn!("Recording {} produced no usable file", id),
+            Ok(status) => {
+                tracing::info!("Recording {} finalized as {}", id, status.as_str());
+                if let Err(e) = enqueue_post_stop(&pool, id, Some(user_id)).await {
+                    tracing::warn!("Failed to queue processing for {}: {}", id, e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to finalize recording {}: {}", id, e),
+        }
+    }
+}
+
+// How often to check whether cli-capture exited on its own
+const EXIT_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Finalize recordings whose process exited without a stop request. On the first pass with
+/// a database it also picks up rows left in progress by a previous run of the server.
+pub(crate) async fn recording_watch_loop(state: Arc<AppState>, mut shutdown_rx: tokio::sync::watch::Receiver<bool>) {
+    let mut recovered = false;
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(EXIT_WATCH_INTERVAL) => {}
+            _ = shutdown_rx.changed() => break,
+        }
+
+        let pool = match state.db.read().await.clone() {
+            Some(p) => p,
+            None => continue,
+        };
+        if !recovered {
+            recover_interrupted(&state, &pool).await;
+            recovered = true;
+        }
+
+        for user_id in state.recorder_manager.reap_exited().await {
+            tracing::warn!("cli-capture for user {} exited without being stopped", user_id);
+            let exited = begin_finalizing(&pool, user_id, "process exited").await;
+            tokio::spawn(finish_recordings(pool.clone(), user_id, exited));
+        }
+    }
+}
+
+#[derive(FromRow)]
+struct InterruptedRecording {
+    id: Uuid,
+    user_id: Option<Uuid>,
+    filepath: String,
+    status: String,
+}
+
+/// Rows still pending, recording or finalizing whose process is gone, e.g. after a restart.
+async fn recover_interrupted(state: &Arc<AppState>, pool: &sqlx::PgPool) {
+    let active = state.recorder_manager.get_active_users().await;
+    let rows: Vec<InterruptedRecording> = sqlx::query_as(&format!(
+        "SELECT id, user_id, filepath, status FROM recordings WHERE status IN {} AND deleted_at IS NULL",
+        IN_PROGRESS_SQL
+    ))
+    .fetch_all(pool)
+    .await
+    .unwrap_or_default();
+
+    for row in rows {
+        if row.user_id.is_some_and(|u| active.contains(&u)) {
+            continue;
+        }
+        let result = match RecordingStatus::parse(&row.status) {
+            Some(RecordingStatus::Pending) => {
+                transition_status(pool, row.id, RecordingStatus::Failed, "server restarted before capture started").await.map(drop)
+            }
+            Some(RecordingStatus::Recording) => {
+                transition_status(pool, row.id, RecordingStatus::Finalizing, "server restarted during capture").await.map(drop)
+            }
+            _ => Ok(()),
+        };
+        if let Err(e) = result {
+            tracing::warn!("Failed to recover recording {}: {}", row.id, e);
+            continue;
+        }
+        if row.status != RecordingStatus::Pending.as_str() {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                if let Err(e) = finalize_recording(&pool, row.id, &row.filepath).await {
+                    tracing::warn!("Failed to finalize recording {}: {}", row.id, e);
+                }
+            });
+        }
+    }
+}
+
+/// Flag the current moment of the caller's recording, stored as seconds from its start.
+async fn add_marker(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<MarkerPayload>,
+) -> impl IntoResponse {
+    let (user_id, _, _) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    let label = payload.label.map(|l| l.trim().to_string()).filter(|l| !l.is_empty());
+    if let Some(l) = &label {
+        if let Err(e) = validate_label(l) {
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    }
+    if !state.recorder_manager.is_recording(user_id).await {
+        return (StatusCode::CONFLICT, "No active recording").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    // status_changed_at is when the row entered `recording`, i.e. when capture started.
+    // Streams have no row, so there is nothing to mark.
+    let active: Option<(Uuid, Option<chrono::DateTime<chrono::Utc>>)> = sqlx::query_as(
+        "SELECT id, status_changed_at FROM recordings WHERE user_id = $1 AND status = 'recording' AND deleted_at IS NULL
+         ORDER BY created_at DESC LIMIT 1"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    let (recording_id, started_at) = match active {
+        Some((id, Some(started))) => (id, started),
+        _ => return (StatusCode::CONFLICT, "No active recording").into_response(),
+    };
+    let offset_secs = ((chrono::Utc::now() - started_at).num_milliseconds() as f64 / 1000.0).max(0.0);
+
+    match sqlx::query_as::<_, RecordingMarker>(&format!(
+        "INSERT INTO recording_markers (recording_id, user_id, offset_secs, label) VALUES ($1, $2, $3, $4) RETURNING {}",
+        MARKER_COLUMNS
+    ))
+    .bind(recording_id)
+    .bind(user_id)
+    .bind(offset_secs)
+    .bind(&label)
+    .fetch_one(pool)
+    .await
+    {
+        Ok(marker) => (StatusCode::CREATED, Json(marker)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Insert Error: {}", e)).into_response(),
+    }
+}
+
 async fn get_status(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -703,23 +1325,31 @@ async fn respond_stop(
         if !state.recorder_manager.is_recording(user_id).await {
             return (StatusCode::BAD_REQUEST, "Target user is not recording").into_response();
         }
-        let response = perform_stop(&state, user_id).await;
+        let response = perform_stop(&state, user_id, None).await;
         if response.status() != StatusCode::OK {
             return response;
         }
 
+        let _claim = state.encoder_lock.lock().await;
+        if state.scan_jobs.is_running().await {
+            return (StatusCode::CONFLICT, "Hardware scan in progress").into_response();
+        }
+
         let db_guard = state.db.read().await;
         let pool = match db_guard.as_ref() {
             Some(p) => p,
             None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
         };
 
-        let (args, filename, cli_path) = match build_start_params(pool, request.requester_id, &request.requester_name, "record".to_string(), None).await {
+        if let Err(e) = check_storage_quota(pool, request.requester_id, 0).await {
+            return e.into_response();
+        }
+        let params = match build_start_params(pool, request.requester_id, &request.requester_name, "record".to_string(), None, false).await {
             Ok(v) => v,
             Err(r) => return r,
         };
 
-        if let Err(e) = state.recorder_manager.start_recording(request.requester_id, cli_path, args, "record".to_string()).await {
+        if let Err(e) = start_tracked(&state, pool, request.requester_id, params, "record").await {
             let msg = e.to_string();
             if msg.contains("Another recording in progress") {
                 return (StatusCode::CONFLICT, "Another user is recording").into_response();
@@ -730,17 +1360,6 @@ async fn respond_stop(
             return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start: {}", msg)).into_response();
         }
 
-        if let Some(name) = filename {
-            let _ = sqlx::query(
-                "INSERT INTO recordings (user_id, filename, filepath, status) VALUES ($1, $2, $3, 'recording')"
-            )
-            .bind(request.requester_id)
-            .bind(&name)
-            .bind(format!("/recordings/{}", name))
-            .execute(pool)
-            .await;
-        }
-
         let mut requests = state.stop_requests.write().await;
         if let Some(req) = requests.get_mut(&user_id) {
             req.status = RequestStatus::Accepted;
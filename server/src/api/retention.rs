@@ -0,0 +1,370 @@
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+    routing::post,
+    Router,
+};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sqlx::FromRow;
+use std::path::Path as FsPath;
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::files::{get_recording_base, log_status, settle_recording, status_before, transition_status};
+use crate::api::recorder::get_user_from_header;
+use crate::api::settings::load_retention_policy;
+use crate::api::trash::{delete_recording, purge_recording, TrashedRecording, TRASHED_COLUMNS};
+use crate::core::recording_dir::{list_recording_files, normalize_relative};
+use crate::core::recording_state::{RecordingStatus, IN_PROGRESS_SQL};
+use crate::core::retention::{select_expired, RetentionCandidate, RetentionReason};
+
+// Files modified more recently than this may still be written by something else
+const ORPHAN_MIN_AGE: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Deserialize)]
+struct CleanupQuery {
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(FromRow)]
+struct RetentionRow {
+    id: Uuid,
+    user_id: Option<Uuid>,
+    created_at: Option<chrono::DateTime<Utc>>,
+    size_bytes: Option<i64>,
+    pinned: bool,
+    filename: String,
+    filepath: String,
+}
+
+#[derive(Serialize)]
+pub(crate) struct RemovedRecording {
+    pub id: Uuid,
+    pub filename: String,
+    pub size_bytes: i64,
+    pub reason: RetentionReason,
+}
+
+#[derive(Serialize, Default)]
+pub(crate) struct CleanupReport {
+    pub dry_run: bool,
+    pub removed: Vec<RemovedRecording>,
+    pub freed_bytes: i64,
+}
+
+#[derive(Serialize, Default)]
+pub(crate) struct ReconcileReport {
+    pub scanned: usize,
+    pub missing: Vec<String>,
+    pub restored: Vec<String>,
+    pub imported: Vec<String>,
+    pub fixed_paths: Vec<String>,
+}
+
+/// Admin maintenance routes, mounted under `/api/files`.
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/reconcile", post(reconcile_files))
+        .route("/cleanup", post(cleanup_files))
+}
+
+/// Bring the recordings table in line with what is on disk under `base`:
+/// rows whose file vanished become `missing` (and come back when it reappears),
+/// `filepath` is rewritten to the real location, and unknown files are imported unowned.
+pub(crate) async fn reconcile_recordings(pool: &sqlx::PgPool, base: &FsPath) -> anyhow::Result<ReconcileReport> {
+    let walk_base = base.to_path_buf();
+    let disk = tokio::task::spawn_blocking(move || list_recording_files(&walk_base)).await??;
+
+    let rows: Vec<(Uuid, String, String, String, bool)> = sqlx::query_as(
+        "SELECT id, filename, filepath, status, local_copy FROM recordings WHERE deleted_at IS NULL"
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut report = ReconcileReport { scanned: disk.len(), ..Default::default() };
+    let mut known = std::collections::HashSet::new();
+
+    for (id, filename, filepath, status, local_copy) in rows {
+        known.insert(normalize_relative(FsPath::new(&filename)));
+        // Live recordings are still being written; leave them to the recorder.
+        // Failed recordings may never have had a file, and archive-only ones have no local file to find.
+        let status = RecordingStatus::parse(&status);
+        if status.is_none_or(|s| s.in_progress() || s == RecordingStatus::Failed) || !local_copy {
+            continue;
+        }
+
+        let path = base.join(&filename);
+        let exists = tokio::fs::metadata(&path).await.map(|m| m.is_file()).unwrap_or(false);
+        if !exists {
+            if status != Some(RecordingStatus::Missing) {
+                transition_status(pool, id, RecordingStatus::Missing, "file not found by reconcile").await?;
+                report.missing.push(filename);
+            }
+            continue;
+        }
+
+        if status == Some(RecordingStatus::Missing) {
+            let previous = status_before(pool, id, RecordingStatus::Missing).await.unwrap_or(RecordingStatus::Saved);
+            transition_status(pool, id, previous, "file found again by reconcile").await?;
+            report.restored.push(filename.clone());
+        }
+
+        let actual = path.to_string_lossy().to_string();
+        if filepath != actual {
+            sqlx::query("UPDATE recordings SET filepath = $1 WHERE id = $2")
+                .bind(&actual)
+                .bind(id)
+                .execute(pool)
+                .await?;
+            report.fixed_paths.push(filename);
+        }
+    }
+
+    let now = std::time::SystemTime::now();
+    for file in disk {
+        if known.contains(&file.relative) {
+            continue;
+        }
+        let settled = file
+            .modified
+            .and_then(|m| now.duration_since(m).ok())
+            .map(|age| age >= ORPHAN_MIN_AGE)
+            .unwrap_or(true);
+        if !settled {
+            continue;
+        }
+
+        let filepath = base.join(&file.relative).to_string_lossy().to_string();
+        let id: (Uuid,) = sqlx::query_as(
+            "INSERT INTO recordings (user_id, filename, filepath, status) VALUES (NULL, $1, $2, 'finalizing') RETURNING id"
+        )
+        .bind(&file.relative)
+        .bind(&filepath)
+        .fetch_one(pool)
+        .await?;
+        log_status(pool, id.0, None, RecordingStatus::Finalizing, "imported by reconcile").await?;
+        if let Err(e) = settle_recording(pool, id.0, &filepath, false, "imported file probed").await {
+            tracing::warn!("Failed to settle imported recording {}: {}", file.relative, e);
+        }
+        report.imported.push(file.relative);
+    }
+
+    Ok(report)
+}
+
+/// Periodic reconcile, driven by `reconcile_interval_minutes` (0 or unset disables it).
+pub(crate) async fn reconcile_loop(state: Arc<AppState>, mut shutdown_rx: tokio::sync::watch::Receiver<bool>) {
+    let tick = std::time::Duration::from_secs(60);
+    let mut last_run = std::time::Instant::now();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(tick) => {}
+            _ = shutdown_rx.changed() => break,
+        }
+
+        let pool = match state.db.read().await.clone() {
+            Some(p) => p,
+            None => continue,
+        };
+        let minutes = get_reconcile_interval_minutes(&pool).await;
+        if minutes == 0 || last_run.elapsed() < std::time::Duration::from_secs(minutes * 60) {
+            continue;
+        }
+        last_run = std::time::Instant::now();
+
+        let base = match get_recording_base(&pool).await {
+            Ok(b) => b,
+            Err(_) => continue,
+        };
+        let _guard = match state.reconcile_lock.try_lock() {
+            Ok(g) => g,
+            Err(_) => continue,
+        };
+        match reconcile_recordings(&pool, &base).await {
+            Ok(report) => tracing::info!(
+                "Reconciled recordings: {} missing, {} restored, {} imported, {} paths fixed",
+                report.missing.len(),
+                report.restored.len(),
+                report.imported.len(),
+                report.fixed_paths.len()
+            ),
+            Err(e) => tracing::warn!("Recording reconcile failed: {}", e),
+        }
+    }
+}
+
+pub(crate) async fn get_reconcile_interval_minutes(pool: &sqlx::PgPool) -> u64 {
+    let row: Option<(Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'reconcile_interval_minutes'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| v.0.as_u64()).unwrap_or(0)
+}
+
+/// Apply the retention policy to finished recordings, deleting files and rows unless `dry_run`.
+pub(crate) async fn apply_retention(pool: &sqlx::PgPool, dry_run: bool) -> anyhow::Result<CleanupReport> {
+    let policy = load_retention_policy(pool).await;
+    let rows: Vec<RetentionRow> = sqlx::query_as(&format!(
+        "SELECT id, user_id, created_at, size_bytes, pinned, filename, filepath FROM recordings WHERE status NOT IN {} AND deleted_at IS NULL",
+        IN_PROGRESS_SQL
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let candidates: Vec<RetentionCandidate> = rows
+        .iter()
+        .map(|r| RetentionCandidate {
+            id: r.id,
+            user_id: r.user_id,
+            created_at: r.created_at.unwrap_or_else(Utc::now),
+            size_bytes: r.size_bytes.unwrap_or(0),
+            pinned: r.pinned,
+        })
+        .collect();
+
+    let mut report = CleanupReport { dry_run, ..Default::default() };
+    for (id, reason) in select_expired(&policy, &candidates, Utc::now()) {
+        let row = match rows.iter().find(|r| r.id == id) {
+            Some(r) => r,
+            None => continue,
+        };
+        let (filename, size) = (&row.filename, row.size_bytes.unwrap_or(0));
+        if !dry_run {
+            // Through the trash like a manual delete, so archived copies and thumbnails go
+            // when the trash is purged and a mistaken policy can still be undone
+            if let Err((_, e)) = delete_recording(pool, id, &row.filepath).await {
+                tracing::warn!("Retention could not move {} to trash: {}", filename, e);
+                continue;
+            }
+            tracing::info!("Retention moved {} to trash ({} bytes, {:?})", filename, size, reason);
+        }
+        report.freed_bytes += size;
+        report.removed.push(RemovedRecording {
+            id,
+            filename: filename.clone(),
+            size_bytes: size,
+            reason,
+        });
+    }
+
+    if policy.trash_retention_days > 0 {
+        let expired: Vec<TrashedRecording> = sqlx::query_as(&format!(
+            "SELECT {} FROM recordings WHERE deleted_at < NOW() - make_interval(days => $1)",
+            TRASHED_COLUMNS
+        ))
+        .bind(policy.trash_retention_days as i32)
+        .fetch_all(pool)
+        .await?;
+        for row in expired {
+            let size = row.size_bytes.unwrap_or(0);
+            if !dry_run {
+                if let Err((_, e)) = purge_recording(pool, row.id, row.trash_path.as_deref()).await {
+                    tracing::warn!("Retention could not purge {} from trash: {}", row.filename, e);
+                    continue;
+                }
+                tracing::info!("Retention purged {} from trash ({} bytes)", row.filename, size);
+            }
+            report.freed_bytes += size;
+            report.removed.push(RemovedRecording {
+                id: row.id,
+                filename: row.filename,
+                size_bytes: size,
+                reason: RetentionReason::TrashExpired,
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// Scheduled retention cleanup, run every `interval_minutes` of the retention policy.
+pub(crate) async fn cleanup_loop(state: Arc<AppState>, mut shutdown_rx: tokio::sync::watch::Receiver<bool>) {
+    let tick = std::time::Duration::from_secs(60);
+    let mut last_run = std::time::Instant::now();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(tick) => {}
+            _ = shutdown_rx.changed() => break,
+        }
+
+        let pool = match state.db.read().await.clone() {
+            Some(p) => p,
+            None => continue,
+        };
+        let minutes = load_retention_policy(&pool).await.interval_minutes;
+        if minutes == 0 || last_run.elapsed() < std::time::Duration::from_secs(minutes * 60) {
+            continue;
+        }
+        last_run = std::time::Instant::now();
+
+        match apply_retention(&pool, false).await {
+            Ok(report) if !report.removed.is_empty() => tracing::info!(
+                "Retention cleanup removed {} recordings, freed {} bytes",
+                report.removed.len(),
+                report.freed_bytes
+            ),
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Retention cleanup failed: {}", e),
+        }
+    }
+}
+
+async fn cleanup_files(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<CleanupQuery>,
+) -> impl IntoResponse {
+    match get_user_from_header(&headers) {
+        Ok((_, _, role)) if role == "admin" => {}
+        Ok(_) => return (StatusCode::FORBIDDEN, "Admin only").into_response(),
+        Err(e) => return e.into_response(),
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    match apply_retention(pool, params.dry_run).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Cleanup failed: {}", e)).into_response(),
+    }
+}
+
+async fn reconcile_files(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    match get_user_from_header(&headers) {
+        Ok((_, _, role)) if role == "admin" => {}
+        Ok(_) => return (StatusCode::FORBIDDEN, "Admin only").into_response(),
+        Err(e) => return e.into_response(),
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let base = match get_recording_base(pool).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
+
+    let _guard = match state.reconcile_lock.try_lock() {
+        Ok(g) => g,
+        Err(_) => return (StatusCode::CONFLICT, "Reconcile already running").into_response(),
+    };
+
+    match reconcile_recordings(pool, &base).await {
+        Ok(report) => Json(report).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Reconcile failed: {}", e)).into_response(),
+    }
+}
@@ -1,6 +1,6 @@
 use axum::{
     extract::State,
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::{IntoResponse, Json},
     routing::get,
     Router,
@@ -8,7 +8,13 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use crate::AppState;
-use crate::api::recorder::{validate_encoder_id, validate_max_bitrate, validate_max_fps, validate_resolution_value};
+use crate::core::retention::RetentionPolicy;
+use crate::api::recorder::{get_user_from_header, validate_encoder_id, validate_max_bitrate, validate_max_fps, validate_resolution_value};
+use crate::core::encoders::EncoderEntry;
+use crate::core::path_template::{render, TemplateVars, DEFAULT_PATH_TEMPLATE, PLACEHOLDERS};
+use crate::core::storage::{StorageConfig, StorageKind};
+use crate::core::jobs::{PipelineConfig, MAX_WORKERS};
+use crate::core::upload::UploadPolicy;
 
 #[derive(Serialize, Deserialize)]
 pub struct CliPathConfig {
@@ -33,6 +39,65 @@ pub struct DownloadTokenTtlConfig {
     pub minutes: i64,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct ScanTimeoutConfig {
+    pub seconds: u64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct ReconcileIntervalConfig {
+    /// 0 disables the periodic reconcile.
+    pub minutes: u64,
+}
+
+/// QR hand-off shown when a recording stops.
+#[derive(Serialize, Deserialize)]
+pub struct HandoffConfig {
+    /// Attach a QR code to the stop response.
+    #[serde(default = "default_handoff_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_handoff_minutes")]
+    pub expires_in_minutes: i64,
+    #[serde(default)]
+    pub max_downloads: Option<i32>,
+    /// Base URL phones should use, e.g. `http://192.168.1.20:3000`. Falls back to the request's Host header.
+    #[serde(default)]
+    pub public_base_url: Option<String>,
+}
+
+fn default_handoff_enabled() -> bool {
+    true
+}
+
+fn default_handoff_minutes() -> i64 {
+    15
+}
+
+impl Default for HandoffConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            expires_in_minutes: default_handoff_minutes(),
+            max_downloads: None,
+            public_base_url: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct PathTemplateConfig {
+    pub template: String,
+}
+
+#[derive(Deserialize)]
+pub struct EncoderPolicyUpdate {
+    pub id: String,
+    pub allowed: bool,
+    pub max_bitrate: Option<i32>,
+    pub max_fps: Option<i32>,
+    pub max_res: Option<String>,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct ServerNameConfig {
     pub name: String,
@@ -45,6 +110,15 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/global-path", get(get_global_path).post(set_global_path))
         .route("/download-token-ttl", get(get_download_token_ttl).post(set_download_token_ttl))
         .route("/server-name", get(get_server_name).post(set_server_name))
+        .route("/scan-timeout", get(get_scan_timeout).post(set_scan_timeout))
+        .route("/encoders", get(get_encoders).post(set_encoders))
+        .route("/reconcile-interval", get(get_reconcile_interval).post(set_reconcile_interval))
+        .route("/retention", get(get_retention).post(set_retention))
+        .route("/handoff", get(get_handoff).post(set_handoff))
+        .route("/path-template", get(get_path_template).post(set_path_template))
+        .route("/storage", get(get_storage).post(set_storage))
+        .route("/pipeline", get(get_pipeline).post(set_pipeline))
+        .route("/uploads", get(get_upload_policy).post(set_upload_policy))
 }
 
 async fn get_global_path(
@@ -134,6 +208,602 @@ async fn set_download_token_ttl(
     (StatusCode::OK, "Updated").into_response()
 }
 
+async fn get_scan_timeout(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let seconds = crate::api::hardware::get_scan_timeout(pool).await.as_secs();
+    Json(ScanTimeoutConfig { seconds }).into_response()
+}
+
+async fn set_scan_timeout(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ScanTimeoutConfig>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let seconds = payload.seconds.clamp(5, 600);
+    let val = serde_json::Value::Number(serde_json::Number::from(seconds));
+
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('hardware_scan_timeout_secs', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+async fn get_reconcile_interval(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let minutes = crate::api::retention::get_reconcile_interval_minutes(pool).await;
+    Json(ReconcileIntervalConfig { minutes }).into_response()
+}
+
+async fn set_reconcile_interval(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<ReconcileIntervalConfig>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let minutes = payload.minutes.min(7 * 24 * 60);
+    let val = serde_json::Value::Number(serde_json::Number::from(minutes));
+
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('reconcile_interval_minutes', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+pub(crate) async fn load_retention_policy(pool: &sqlx::PgPool) -> RetentionPolicy {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'retention_policy'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| serde_json::from_value(v.0).ok()).unwrap_or_default()
+}
+
+async fn get_retention(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    Json(load_retention_policy(pool).await).into_response()
+}
+
+async fn set_retention(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<RetentionPolicy>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    if payload.max_age_days == Some(0) {
+        return (StatusCode::BAD_REQUEST, "max_age_days must be at least 1").into_response();
+    }
+    if payload.max_total_bytes.is_some_and(|b| b <= 0) {
+        return (StatusCode::BAD_REQUEST, "max_total_bytes must be positive").into_response();
+    }
+    if payload.keep_newest_per_user == Some(0) {
+        return (StatusCode::BAD_REQUEST, "keep_newest_per_user must be at least 1").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let val = serde_json::to_value(&payload).unwrap_or_default();
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('retention_policy', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+pub(crate) async fn load_handoff_config(pool: &sqlx::PgPool) -> HandoffConfig {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'handoff'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| serde_json::from_value(v.0).ok()).unwrap_or_default()
+}
+
+async fn get_handoff(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    Json(load_handoff_config(pool).await).into_response()
+}
+
+async fn set_handoff(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut payload): Json<HandoffConfig>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    if !(1..=24 * 60).contains(&payload.expires_in_minutes) {
+        return (StatusCode::BAD_REQUEST, "expires_in_minutes must be between 1 and 1440").into_response();
+    }
+    if payload.max_downloads.is_some_and(|m| m < 1) {
+        return (StatusCode::BAD_REQUEST, "max_downloads must be at least 1").into_response();
+    }
+    payload.public_base_url = payload
+        .public_base_url
+        .map(|u| u.trim().trim_end_matches('/').to_string())
+        .filter(|u| !u.is_empty());
+    if payload
+        .public_base_url
+        .as_deref()
+        .is_some_and(|u| !u.starts_with("http://") && !u.starts_with("https://"))
+    {
+        return (StatusCode::BAD_REQUEST, "public_base_url must start with http:// or https://").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let val = serde_json::to_value(&payload).unwrap_or_default();
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('handoff', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+pub(crate) async fn load_path_template(pool: &sqlx::PgPool) -> String {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'recording_path_template'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| v.0.as_str().map(String::from))
+        .filter(|t| !t.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_PATH_TEMPLATE.to_string())
+}
+
+fn example_path(template: &str) -> Result<String, String> {
+    let vars = TemplateVars { username: "player1", user_id: "00000000-0000-0000-0000-000000000000", now: chrono::Local::now(), ext: "mp4" };
+    render(template, &vars, 1)
+}
+
+async fn get_path_template(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let template = load_path_template(pool).await;
+    Json(serde_json::json!({
+        "example": example_path(&template).ok(),
+        "template": template,
+        "placeholders": PLACEHOLDERS,
+    }))
+    .into_response()
+}
+
+async fn set_path_template(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<PathTemplateConfig>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let template = payload.template.trim().to_string();
+    if template.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Template cannot be empty").into_response();
+    }
+    if let Err(e) = example_path(&template) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('recording_path_template', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(serde_json::Value::String(template))
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+pub(crate) async fn load_storage_config(pool: &sqlx::PgPool) -> StorageConfig {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'storage'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| serde_json::from_value(v.0).ok()).unwrap_or_default()
+}
+
+async fn get_storage(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    // Never hand the secret back; the form only needs to know one is set
+    let mut config = load_storage_config(pool).await;
+    let has_secret = !config.s3.secret_access_key.is_empty();
+    config.s3.secret_access_key.clear();
+    let mut body = serde_json::to_value(&config).unwrap_or_default();
+    body["s3"]["has_secret"] = serde_json::Value::Bool(has_secret);
+    Json(body).into_response()
+}
+
+async fn set_storage(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut payload): Json<StorageConfig>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    if !(1..=7 * 24 * 60).contains(&payload.presign_expires_minutes) {
+        return (StatusCode::BAD_REQUEST, "presign_expires_minutes must be between 1 and 10080").into_response();
+    }
+    if payload.prefix.contains("..") || payload.prefix.contains('\\') {
+        return (StatusCode::BAD_REQUEST, "Invalid object key prefix").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    // A blank secret means "unchanged" so the form can be saved without re-entering it
+    if payload.s3.secret_access_key.is_empty() {
+        payload.s3.secret_access_key = load_storage_config(pool).await.s3.secret_access_key;
+    }
+    if payload.backend == StorageKind::Local && !std::path::Path::new(payload.local_root.trim()).is_absolute() {
+        return (StatusCode::BAD_REQUEST, "Local archive directory must be an absolute path").into_response();
+    }
+    if let Err(e) = payload.backend() {
+        return (StatusCode::BAD_REQUEST, e.to_string()).into_response();
+    }
+
+    let val = serde_json::to_value(&payload).unwrap_or_default();
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('storage', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+pub(crate) async fn load_pipeline_config(pool: &sqlx::PgPool) -> PipelineConfig {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'post_stop_pipeline'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| serde_json::from_value(v.0).ok()).unwrap_or_default()
+}
+
+async fn get_pipeline(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    Json(load_pipeline_config(pool).await).into_response()
+}
+
+async fn set_pipeline(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<PipelineConfig>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    if !(1..=10).contains(&payload.max_attempts) {
+        return (StatusCode::BAD_REQUEST, "max_attempts must be between 1 and 10").into_response();
+    }
+    if !(1..=MAX_WORKERS).contains(&payload.workers) {
+        return (StatusCode::BAD_REQUEST, format!("workers must be between 1 and {}", MAX_WORKERS)).into_response();
+    }
+    for (i, step) in payload.steps.iter().enumerate() {
+        if let Err(e) = step.validate() {
+            return (StatusCode::BAD_REQUEST, format!("Step {}: {}", i + 1, e)).into_response();
+        }
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let val = serde_json::to_value(&payload).unwrap_or_default();
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('post_stop_pipeline', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+pub(crate) async fn load_upload_policy(pool: &sqlx::PgPool) -> UploadPolicy {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'upload_policy'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| serde_json::from_value(v.0).ok()).unwrap_or_default()
+}
+
+async fn get_upload_policy(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    Json(load_upload_policy(pool).await).into_response()
+}
+
+async fn set_upload_policy(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(mut payload): Json<UploadPolicy>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    payload.allowed_extensions = payload
+        .allowed_extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_ascii_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect();
+    payload.allowed_extensions.sort();
+    payload.allowed_extensions.dedup();
+    payload.folder = payload.folder.trim().trim_matches('/').to_string();
+    if let Err(e) = payload.validate() {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let val = serde_json::to_value(&payload).unwrap_or_default();
+    if let Err(e) = sqlx::query("INSERT INTO system_config (key, value) VALUES ('upload_policy', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+    {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    (StatusCode::OK, "Updated").into_response()
+}
+
+pub(crate) async fn load_encoder_catalogue(pool: &sqlx::PgPool) -> Vec<EncoderEntry> {
+    let row: Option<(serde_json::Value,)> = sqlx::query_as("SELECT value FROM system_config WHERE key = 'encoder_catalogue'")
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|v| serde_json::from_value(v.0).ok()).unwrap_or_default()
+}
+
+pub(crate) async fn save_encoder_catalogue(pool: &sqlx::PgPool, catalogue: &[EncoderEntry]) -> Result<(), sqlx::Error> {
+    let val = serde_json::to_value(catalogue).unwrap_or_default();
+    sqlx::query("INSERT INTO system_config (key, value) VALUES ('encoder_catalogue', $1) ON CONFLICT (key) DO UPDATE SET value = $1")
+        .bind(val)
+        .execute(pool)
+        .await
+        .map(|_| ())
+}
+
+async fn get_encoders(
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    Json(load_encoder_catalogue(pool).await).into_response()
+}
+
+async fn set_encoders(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<Vec<EncoderPolicyUpdate>>,
+) -> impl IntoResponse {
+    let (_, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if role != "admin" {
+        return (StatusCode::FORBIDDEN, "Admin access required").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let mut catalogue = load_encoder_catalogue(pool).await;
+    for update in payload {
+        if let Some(max_bitrate) = update.max_bitrate {
+            if let Err(e) = validate_max_bitrate(max_bitrate) {
+                return (StatusCode::BAD_REQUEST, e).into_response();
+            }
+        }
+        if let Some(max_fps) = update.max_fps {
+            if let Err(e) = validate_max_fps(max_fps) {
+                return (StatusCode::BAD_REQUEST, e).into_response();
+            }
+        }
+        let max_res = update.max_res.filter(|r| !r.trim().is_empty());
+        if let Some(max_res) = max_res.as_ref() {
+            if let Err(e) = validate_resolution_value(max_res, false) {
+                return (StatusCode::BAD_REQUEST, e).into_response();
+            }
+        }
+        let entry = match catalogue.iter_mut().find(|e| e.id == update.id) {
+            Some(e) => e,
+            None => return (StatusCode::BAD_REQUEST, format!("Unknown encoder: {}", update.id)).into_response(),
+        };
+        entry.allowed = update.allowed;
+        entry.max_bitrate = update.max_bitrate;
+        entry.max_fps = update.max_fps;
+        entry.max_res = max_res;
+    }
+
+    if let Err(e) = save_encoder_catalogue(pool, &catalogue).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update config: {}", e)).into_response();
+    }
+
+    Json(catalogue).into_response()
+}
+
 async fn get_server_name(
     State(state): State<Arc<AppState>>,
 ) -> impl IntoResponse {
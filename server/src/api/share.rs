@@ -0,0 +1,489 @@
+use axum::{
+    extract::{ConnectInfo, Path, Query, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{delete, get},
+    Router,
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use uuid::Uuid;
+use crate::AppState;
+use crate::api::recorder::get_user_from_header;
+use crate::api::files::{locate_recording, recording_digest, serve_source};
+use crate::api::settings::load_handoff_config;
+use crate::core::download::parse_range;
+use crate::core::qr::{render_png, render_svg};
+use crate::core::recording_state::{is_in_progress, wait_while_finalizing, RecordingStatus, SETTLE_POLL};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+
+#[derive(Serialize, FromRow)]
+pub struct ShareLink {
+    pub id: Uuid,
+    pub recording_id: Uuid,
+    pub token: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub max_downloads: Option<i32>,
+    pub download_count: i32,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: Option<DateTime<Utc>>,
+    pub password_protected: bool,
+}
+
+/// A share link plus its QR code, ready to put on screen.
+#[derive(Serialize)]
+pub struct Handoff {
+    pub recording_id: Uuid,
+    pub share_id: Uuid,
+    pub url: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub qr_svg: String,
+    /// `data:image/png;base64,...` so it can go straight into an `<img>`.
+    pub qr_png: String,
+    /// The same PNG as raw bytes, for callers that serve the image directly.
+    #[serde(skip)]
+    pub png: Vec<u8>,
+}
+
+#[derive(Deserialize)]
+pub struct CreateSharePayload {
+    pub recording_id: Uuid,
+    pub expires_in_minutes: Option<i64>,
+    pub max_downloads: Option<i32>,
+    pub password: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct ListSharesQuery {
+    recording_id: Uuid,
+}
+
+#[derive(Deserialize)]
+struct ShareDownloadQuery {
+    #[serde(default)]
+    inline: bool,
+}
+
+#[derive(FromRow)]
+struct ShareTarget {
+    recording_id: Uuid,
+    password_hash: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+    max_downloads: Option<i32>,
+    download_count: i32,
+    revoked_at: Option<DateTime<Utc>>,
+    filename: String,
+    status: String,
+    size_bytes: Option<i64>,
+    duration_secs: Option<f64>,
+}
+
+const SHARE_COLUMNS: &str =
+    "id, recording_id, token, expires_at, max_downloads, download_count, revoked_at, created_at, password_hash IS NOT NULL AS password_protected";
+
+// A reused hand-off link must stay valid at least this long after the QR code is shown
+const HANDOFF_MIN_REMAINING_MINUTES: i64 = 1;
+
+// How long a share request waits for a just-stopped recording to finish finalizing
+const SHARE_SETTLE_WAIT: std::time::Duration = std::time::Duration::from_secs(30);
+
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/links", get(list_shares).post(create_share))
+        .route("/links/:id", delete(revoke_share))
+        .route("/:token", get(get_share_info))
+        .route("/:token/download", get(download_share))
+}
+
+/// Check that the caller owns the recording (or is an admin).
+pub(crate) async fn ensure_recording_access(
+    pool: &sqlx::PgPool,
+    headers: &HeaderMap,
+    recording_id: Uuid,
+) -> Result<Uuid, (StatusCode, &'static str)> {
+    let (user_id, _, role) = get_user_from_header(headers)?;
+    let owner: Option<(Option<Uuid>,)> = sqlx::query_as("SELECT user_id FROM recordings WHERE id = $1 AND deleted_at IS NULL")
+        .bind(recording_id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    match owner {
+        None => Err((StatusCode::NOT_FOUND, "File not found")),
+        Some((owner,)) if role == "admin" || owner == Some(user_id) => Ok(user_id),
+        Some(_) => Err((StatusCode::FORBIDDEN, "Access denied")),
+    }
+}
+
+async fn insert_share(
+    pool: &sqlx::PgPool,
+    recording_id: Uuid,
+    created_by: Uuid,
+    password_hash: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+    max_downloads: Option<i32>,
+    handoff: bool,
+) -> Result<ShareLink, sqlx::Error> {
+    // Two v4 UUIDs give 244 random bits, plenty for an unguessable URL
+    let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+    sqlx::query_as::<_, ShareLink>(&format!(
+        "INSERT INTO recording_shares (recording_id, created_by, token, password_hash, expires_at, max_downloads, handoff)
+         VALUES ($1, $2, $3, $4, $5, $6, $7) RETURNING {}",
+        SHARE_COLUMNS
+    ))
+    .bind(recording_id)
+    .bind(created_by)
+    .bind(&token)
+    .bind(password_hash)
+    .bind(expires_at)
+    .bind(max_downloads)
+    .bind(handoff)
+    .fetch_one(pool)
+    .await
+}
+
+async fn create_share(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateSharePayload>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let user_id = match ensure_recording_access(pool, &headers, payload.recording_id).await {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    if payload.expires_in_minutes.is_some_and(|m| m < 1) {
+        return (StatusCode::BAD_REQUEST, "expires_in_minutes must be at least 1").into_response();
+    }
+    if payload.max_downloads.is_some_and(|m| m < 1) {
+        return (StatusCode::BAD_REQUEST, "max_downloads must be at least 1").into_response();
+    }
+
+    let password_hash = match payload.password.filter(|p| !p.is_empty()) {
+        Some(p) => match tokio::task::spawn_blocking(move || bcrypt::hash(p, bcrypt::DEFAULT_COST)).await {
+            Ok(Ok(h)) => Some(h),
+            _ => return (StatusCode::INTERNAL_SERVER_ERROR, "Failed to hash password").into_response(),
+        },
+        None => None,
+    };
+
+    let expires_at = payload.expires_in_minutes.map(|m| Utc::now() + Duration::minutes(m));
+    let share = insert_share(pool, payload.recording_id, user_id, password_hash, expires_at, payload.max_downloads, false).await;
+
+    match share {
+        Ok(s) => (StatusCode::CREATED, Json(s)).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create share: {}", e)).into_response(),
+    }
+}
+
+async fn list_shares(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Query(params): Query<ListSharesQuery>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    if let Err(e) = ensure_recording_access(pool, &headers, params.recording_id).await {
+        return e.into_response();
+    }
+
+    let shares = sqlx::query_as::<_, ShareLink>(&format!(
+        "SELECT {} FROM recording_shares WHERE recording_id = $1 ORDER BY created_at DESC",
+        SHARE_COLUMNS
+    ))
+    .bind(params.recording_id)
+    .fetch_all(pool)
+    .await;
+
+    match shares {
+        Ok(s) => Json(s).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+async fn revoke_share(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let recording: Option<(Uuid,)> = sqlx::query_as("SELECT recording_id FROM recording_shares WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let recording_id = match recording {
+        Some(r) => r.0,
+        None => return (StatusCode::NOT_FOUND, "Share not found").into_response(),
+    };
+
+    if let Err(e) = ensure_recording_access(pool, &headers, recording_id).await {
+        return e.into_response();
+    }
+
+    match sqlx::query("UPDATE recording_shares SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        Ok(_) => (StatusCode::OK, "Share revoked").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+    }
+}
+
+/// Origin phones should open share links on: the configured public URL, else what the
+/// browser used to reach us.
+fn public_base_url(configured: Option<&str>, headers: &HeaderMap) -> String {
+    if let Some(url) = configured.filter(|u| !u.is_empty()) {
+        return url.trim_end_matches('/').to_string();
+    }
+    // Proxies may append a comma-separated chain; the first entry is the client-facing one
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.split(',').next().unwrap_or("").trim().to_string())
+    };
+    let host = header("X-Forwarded-Host")
+        .or_else(|| header("Host"))
+        .filter(|h| !h.is_empty())
+        .unwrap_or_else(|| "localhost:3000".to_string());
+    let proto = header("X-Forwarded-Proto").filter(|p| p == "https").unwrap_or_else(|| "http".to_string());
+    format!("{}://{}", proto, host)
+}
+
+/// A hand-off link the user already has for this recording that is still worth showing.
+async fn reusable_handoff(pool: &sqlx::PgPool, user_id: Uuid, recording_id: Uuid) -> Result<Option<ShareLink>, sqlx::Error> {
+    sqlx::query_as::<_, ShareLink>(&format!(
+        "SELECT {} FROM recording_shares
+         WHERE handoff AND recording_id = $1 AND created_by = $2 AND revoked_at IS NULL
+           AND expires_at > $3
+           AND (max_downloads IS NULL OR download_count < max_downloads)
+         ORDER BY expires_at DESC LIMIT 1",
+        SHARE_COLUMNS
+    ))
+    .bind(recording_id)
+    .bind(user_id)
+    .bind(Utc::now() + Duration::minutes(HANDOFF_MIN_REMAINING_MINUTES))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Get a short-lived share for a finished recording and render its URL as a QR code.
+/// A still-valid hand-off link for the same user and recording is reused rather than
+/// minting a new row on every request.
+pub(crate) async fn create_handoff(
+    pool: &sqlx::PgPool,
+    headers: &HeaderMap,
+    user_id: Uuid,
+    recording_id: Uuid,
+) -> Result<Handoff, (StatusCode, String)> {
+    let config = load_handoff_config(pool).await;
+    let db_error = |e: sqlx::Error| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create share: {}", e));
+
+    // Expired hand-off links can never be opened again, so nothing is lost by dropping them
+    if let Err(e) = sqlx::query("DELETE FROM recording_shares WHERE handoff AND expires_at <= NOW()")
+        .execute(pool)
+        .await
+    {
+        tracing::warn!("Failed to prune expired hand-off links: {}", e);
+    }
+
+    let share = match reusable_handoff(pool, user_id, recording_id).await.map_err(db_error)? {
+        Some(share) => share,
+        None => {
+            let expires_at = Utc::now() + Duration::minutes(config.expires_in_minutes.max(1));
+            insert_share(pool, recording_id, user_id, None, Some(expires_at), config.max_downloads, true)
+                .await
+                .map_err(db_error)?
+        }
+    };
+
+    // Opening the link plays the recording in the phone's browser instead of forcing a download
+    let url = format!(
+        "{}/api/share/{}/download?inline=true",
+        public_base_url(config.public_base_url.as_deref(), headers),
+        share.token
+    );
+    let qr_svg = render_svg(&url).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let png = render_png(&url).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    Ok(Handoff {
+        recording_id,
+        share_id: share.id,
+        url,
+        expires_at: share.expires_at,
+        qr_svg,
+        qr_png: format!("data:image/png;base64,{}", BASE64.encode(&png)),
+        png,
+    })
+}
+
+async fn load_share(pool: &sqlx::PgPool, token: &str) -> Result<ShareTarget, (StatusCode, &'static str)> {
+    let mut share = find_share(pool, token).await?;
+    // A hand-off QR is handed out the moment recording stops, so a phone can ask before the
+    // file has settled; hold the request until finalizing is done instead of turning it away
+    if RecordingStatus::parse(&share.status) == Some(RecordingStatus::Finalizing) {
+        let id = share.recording_id;
+        wait_while_finalizing(|| recording_status(pool, id), SETTLE_POLL, SHARE_SETTLE_WAIT).await;
+        share = find_share(pool, token).await?;
+    }
+
+    // Revoked, expired and used-up links all look the same to the outside
+    if share.revoked_at.is_some() || share.expires_at.is_some_and(|e| e <= Utc::now()) {
+        return Err((StatusCode::GONE, "Share link has expired"));
+    }
+    if share.max_downloads.is_some_and(|m| share.download_count >= m) {
+        return Err((StatusCode::GONE, "Share link download limit reached"));
+    }
+    if is_in_progress(&share.status) {
+        return Err((StatusCode::CONFLICT, "Recording still in progress"));
+    }
+    Ok(share)
+}
+
+async fn find_share(pool: &sqlx::PgPool, token: &str) -> Result<ShareTarget, (StatusCode, &'static str)> {
+    let share: Option<ShareTarget> = sqlx::query_as(
+        "SELECT s.recording_id, s.password_hash, s.expires_at, s.max_downloads, s.download_count, s.revoked_at,
+                r.filename, r.status, r.size_bytes, r.duration_secs
+         FROM recording_shares s JOIN recordings r ON r.id = s.recording_id
+         WHERE s.token = $1 AND r.deleted_at IS NULL"
+    )
+    .bind(token)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    share.ok_or((StatusCode::NOT_FOUND, "Share link not found"))
+}
+
+async fn recording_status(pool: &sqlx::PgPool, id: Uuid) -> Option<RecordingStatus> {
+    let row: Option<(String,)> = sqlx::query_as("SELECT status FROM recordings WHERE id = $1 AND deleted_at IS NULL")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    row.and_then(|r| RecordingStatus::parse(&r.0))
+}
+
+/// Public summary so a landing page can show what is being shared and whether it needs a password.
+async fn get_share_info(
+    State(state): State<Arc<AppState>>,
+    Path(token): Path<String>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let share = match load_share(pool, &token).await {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
+
+    Json(serde_json::json!({
+        "filename": share.filename,
+        "size_bytes": share.size_bytes,
+        "duration_secs": share.duration_secs,
+        "password_protected": share.password_hash.is_some(),
+        "expires_at": share.expires_at,
+        "downloads_remaining": share.max_downloads.map(|m| (m - share.download_count).max(0)),
+    }))
+    .into_response()
+}
+
+async fn download_share(
+    State(state): State<Arc<AppState>>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    Path(token): Path<String>,
+    Query(params): Query<ShareDownloadQuery>,
+) -> Response {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let share = match load_share(pool, &token).await {
+        Ok(s) => s,
+        Err(e) => return e.into_response(),
+    };
+
+    // Header only, so the password never ends up in access logs or browser history
+    if let Some(hash) = share.password_hash.clone() {
+        let supplied = headers
+            .get("X-Share-Password")
+            .and_then(|v| v.to_str().ok())
+            .map(String::from);
+        let valid = match supplied {
+            // bcrypt is deliberately slow; keep it off the async workers
+            Some(p) => tokio::task::spawn_blocking(move || bcrypt::verify(p, &hash).unwrap_or(false))
+                .await
+                .unwrap_or(false),
+            None => false,
+        };
+        if !valid {
+            return (StatusCode::UNAUTHORIZED, "Password required").into_response();
+        }
+    }
+
+    let source = match locate_recording(pool, share.recording_id, &share.filename).await {
+        Ok(s) => s,
+        Err(r) => return r,
+    };
+
+    // A player's follow-up range requests while seeking belong to the same download, so
+    // counting goes by client session rather than by request.
+    let len = share.size_bytes.and_then(|s| u64::try_from(s).ok());
+    let range = parse_range(headers.get("Range").and_then(|v| v.to_str().ok()), len.unwrap_or(u64::MAX));
+    let user_agent = headers.get("User-Agent").and_then(|v| v.to_str().ok()).unwrap_or("");
+    let client = format!("{} {}", addr.ip(), user_agent);
+    let counts = state
+        .share_sessions
+        .lock()
+        .unwrap()
+        .should_count(&token, &client, &range, len, std::time::Instant::now());
+    if counts {
+        let counted = sqlx::query(
+            "UPDATE recording_shares SET download_count = download_count + 1
+             WHERE token = $1 AND revoked_at IS NULL
+             AND (max_downloads IS NULL OR download_count < max_downloads)"
+        )
+        .bind(&token)
+        .execute(pool)
+        .await;
+        match counted {
+            Ok(r) if r.rows_affected() == 0 => {
+                return (StatusCode::GONE, "Share link download limit reached").into_response();
+            }
+            Ok(_) => {}
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+        }
+    }
+
+    let digest = recording_digest(pool, share.recording_id).await;
+    drop(db_guard);
+
+    tracing::debug!("Serving shared recording {}", share.recording_id);
+    let disposition = if params.inline { "inline" } else { "attachment" };
+    serve_source(&headers, source, &share.filename, disposition, digest.as_deref()).await
+}
@@ -0,0 +1,322 @@
+use axum::{
+    extract::{Path, State},
+    http::{HeaderMap, StatusCode},
+    response::{IntoResponse, Json},
+    routing::{delete, get, post},
+    Router,
+};
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::{FromRow, Postgres, QueryBuilder};
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::files::{
+    archive_backend, get_recording_base, resolve_file_path, revoke_download_tokens, status_before, transition_status,
+    THUMBNAIL_DIR,
+};
+use crate::api::recorder::get_user_from_header;
+use crate::api::settings::{load_retention_policy, load_storage_config};
+use crate::core::recording_state::{finalized_status, RecordingStatus};
+
+// Recycle bin under the recording root; dot-prefixed so reconcile never imports it
+const TRASH_DIR: &str = ".trash";
+
+#[derive(Serialize, FromRow)]
+pub(crate) struct TrashedRecording {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub filename: String,
+    pub size_bytes: Option<i64>,
+    pub created_at: Option<chrono::DateTime<Utc>>,
+    pub deleted_at: chrono::DateTime<Utc>,
+    #[serde(skip)]
+    pub trash_path: Option<String>,
+    pub local_copy: bool,
+    pub archive_state: Option<String>,
+}
+
+pub(crate) const TRASHED_COLUMNS: &str = "id, user_id, filename, size_bytes, created_at, deleted_at, trash_path, local_copy, archive_state";
+
+#[derive(Serialize)]
+struct TrashEntry {
+    #[serde(flatten)]
+    recording: TrashedRecording,
+    /// When retention empties this entry; null if the bin is never emptied automatically.
+    purge_at: Option<chrono::DateTime<Utc>>,
+}
+
+/// Recycle bin routes, mounted under `/api/files` next to the rest of the file routes.
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/trash", get(list_trash))
+        .route("/trash/:id", delete(purge_file))
+        .route("/trash/:id/restore", post(restore_file))
+}
+
+/// Soft delete: move the file into the recycle bin under the recording root and mark the row.
+pub(crate) async fn delete_recording(pool: &sqlx::PgPool, id: Uuid, filepath: &str) -> Result<(), (StatusCode, String)> {
+    let source = PathBuf::from(resolve_file_path(pool, filepath).await);
+    let exists = tokio::fs::metadata(&source).await.map(|m| m.is_file()).unwrap_or(false);
+
+    let trash_name = if exists {
+        let trash = trash_dir(pool).await?;
+        tokio::fs::create_dir_all(&trash)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Trash Error: {}", e)))?;
+        let basename = source.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        // Prefix with the id so identically named recordings don't collide in the bin
+        let name = format!("{}_{}", id.simple(), basename);
+        tokio::fs::rename(&source, trash.join(&name))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("File Delete Error: {}", e)))?;
+        Some(name)
+    } else {
+        None
+    };
+
+    sqlx::query("UPDATE recordings SET deleted_at = NOW(), trash_path = $2 WHERE id = $1")
+        .bind(id)
+        .bind(trash_name)
+        .execute(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)))?;
+    if let Err(e) = transition_status(pool, id, RecordingStatus::Deleted, "moved to trash").await {
+        tracing::warn!("Failed to mark recording {} deleted: {}", id, e);
+    }
+    revoke_download_tokens(pool, id).await;
+    Ok(())
+}
+
+/// Put a restored recording back into the status it had before it was trashed.
+async fn undo_deleted_status(pool: &sqlx::PgPool, id: Uuid) {
+    let previous = match status_before(pool, id, RecordingStatus::Deleted).await {
+        Some(s) => s,
+        // Trashed before statuses were tracked; go by the last probe
+        None => {
+            let complete: Option<(Option<bool>,)> = sqlx::query_as("SELECT is_complete FROM recordings WHERE id = $1")
+                .bind(id)
+                .fetch_optional(pool)
+                .await
+                .unwrap_or(None);
+            finalized_status(complete.and_then(|c| c.0).unwrap_or(true), false)
+        }
+    };
+    if let Err(e) = transition_status(pool, id, previous, "restored from trash").await {
+        tracing::warn!("Failed to restore status of recording {}: {}", id, e);
+    }
+}
+
+async fn trash_dir(pool: &sqlx::PgPool) -> Result<PathBuf, (StatusCode, String)> {
+    match get_recording_base(pool).await {
+        Ok(base) => Ok(base.join(TRASH_DIR)),
+        Err(r) => Err((r.status(), "Recording directory unavailable".to_string())),
+    }
+}
+
+/// Remove a recycled recording for good.
+pub(crate) async fn purge_recording(pool: &sqlx::PgPool, id: Uuid, trash_path: Option<&str>) -> Result<(), (StatusCode, String)> {
+    if let Some(name) = trash_path {
+        let path = trash_dir(pool).await?.join(name);
+        if let Err(e) = tokio::fs::remove_file(&path).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                return Err((StatusCode::INTERNAL_SERVER_ERROR, format!("File Delete Error: {}", e)));
+            }
+        }
+    }
+
+    // The archived object goes too, otherwise retention never frees archive space
+    let archived: Option<(Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT archive_backend, archive_key FROM recordings WHERE id = $1 AND archive_state = 'archived'"
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    if let Some((kind, Some(key))) = archived {
+        let config = load_storage_config(pool).await;
+        let deleted = match archive_backend(&config, kind.as_deref()) {
+            Ok(backend) => backend.delete(&key).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = deleted {
+            return Err((StatusCode::BAD_GATEWAY, format!("Archive Delete Error: {}", e)));
+        }
+    }
+
+    let thumbnail: Option<(Option<String>,)> = sqlx::query_as("SELECT thumbnail_path FROM recordings WHERE id = $1")
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    if let Some((Some(name),)) = thumbnail {
+        if let Ok(base) = get_recording_base(pool).await {
+            let _ = tokio::fs::remove_file(base.join(THUMBNAIL_DIR).join(name)).await;
+        }
+    }
+
+    sqlx::query("DELETE FROM recordings WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Delete Error: {}", e)))?;
+    Ok(())
+}
+
+async fn load_trashed(
+    pool: &sqlx::PgPool,
+    headers: &HeaderMap,
+    id: Uuid,
+) -> Result<TrashedRecording, (StatusCode, &'static str)> {
+    let (user_id, _, role) = get_user_from_header(headers)?;
+    let row: Option<TrashedRecording> = sqlx::query_as(&format!(
+        "SELECT {} FROM recordings WHERE id = $1 AND deleted_at IS NOT NULL",
+        TRASHED_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+
+    match row {
+        None => Err((StatusCode::NOT_FOUND, "File not found in trash")),
+        Some(r) if role == "admin" || r.user_id == Some(user_id) => Ok(r),
+        Some(_) => Err((StatusCode::FORBIDDEN, "Access denied")),
+    }
+}
+
+async fn list_trash(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let mut query = QueryBuilder::<Postgres>::new(format!(
+        "SELECT {} FROM recordings WHERE deleted_at IS NOT NULL",
+        TRASHED_COLUMNS
+    ));
+    if role != "admin" {
+        query.push(" AND user_id = ").push_bind(user_id);
+    }
+    query.push(" ORDER BY deleted_at DESC");
+
+    let rows: Vec<TrashedRecording> = match query.build_query_as().fetch_all(pool).await {
+        Ok(r) => r,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    };
+
+    let days = load_retention_policy(pool).await.trash_retention_days;
+    let items: Vec<TrashEntry> = rows
+        .into_iter()
+        .map(|r| TrashEntry {
+            purge_at: (days > 0).then(|| r.deleted_at + chrono::Duration::days(days as i64)),
+            recording: r,
+        })
+        .collect();
+    Json(items).into_response()
+}
+
+async fn restore_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let row = match load_trashed(pool, &headers, id).await {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+    let name = match row.trash_path.as_deref() {
+        Some(n) => n,
+        // Nothing was moved into the bin for archive-only recordings
+        None if !row.local_copy && row.archive_state.as_deref() == Some("archived") => {
+            return match sqlx::query("UPDATE recordings SET deleted_at = NULL WHERE id = $1")
+                .bind(id)
+                .execute(pool)
+                .await
+            {
+                Ok(_) => {
+                    undo_deleted_status(pool, id).await;
+                    (StatusCode::OK, "File restored").into_response()
+                }
+                Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+            };
+        }
+        None => return (StatusCode::GONE, "File was already missing when deleted").into_response(),
+    };
+
+    let base = match get_recording_base(pool).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
+    // The stored name is relative to the root; refuse anything that would land outside it
+    if FsPath::new(&row.filename).components().any(|c| !matches!(c, std::path::Component::Normal(_))) {
+        return (StatusCode::FORBIDDEN, "Access denied").into_response();
+    }
+    let target = base.join(&row.filename);
+    if tokio::fs::metadata(&target).await.is_ok() {
+        return (StatusCode::CONFLICT, "A file with the same name already exists").into_response();
+    }
+    if let Some(parent) = target.parent() {
+        if let Err(e) = tokio::fs::create_dir_all(parent).await {
+            return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Restore Error: {}", e)).into_response();
+        }
+    }
+    if let Err(e) = tokio::fs::rename(base.join(TRASH_DIR).join(name), &target).await {
+        if e.kind() == std::io::ErrorKind::NotFound {
+            return (StatusCode::GONE, "File is no longer in the trash").into_response();
+        }
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Restore Error: {}", e)).into_response();
+    }
+
+    match sqlx::query("UPDATE recordings SET deleted_at = NULL, trash_path = NULL, filepath = $2 WHERE id = $1")
+        .bind(id)
+        .bind(target.to_string_lossy().to_string())
+        .execute(pool)
+        .await
+    {
+        Ok(_) => {
+            undo_deleted_status(pool, id).await;
+            (StatusCode::OK, "File restored").into_response()
+        }
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+    }
+}
+
+async fn purge_file(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let row = match load_trashed(pool, &headers, id).await {
+        Ok(r) => r,
+        Err(e) => return e.into_response(),
+    };
+
+    match purge_recording(pool, id, row.trash_path.as_deref()).await {
+        Ok(()) => (StatusCode::OK, "File permanently deleted").into_response(),
+        Err(e) => e.into_response(),
+    }
+}
@@ -0,0 +1,542 @@
+use axum::{
+    body::Body,
+    extract::{Path, State},
+    http::{header::{CACHE_CONTROL, CONTENT_TYPE, LOCATION}, HeaderMap, StatusCode},
+    response::{IntoResponse, Json, Response},
+    routing::{head, post},
+    Router,
+};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use serde::Serialize;
+use sqlx::FromRow;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Arc;
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use uuid::Uuid;
+
+use crate::AppState;
+use crate::api::files::{get_recording_base, log_status, settle_recording, unused_name};
+use crate::api::recorder::{check_storage_quota, get_user_from_header};
+use crate::api::settings::load_upload_policy;
+use crate::core::path_template::validate_component;
+use crate::core::recording_state::RecordingStatus;
+use crate::core::upload::{client_file_name, parse_metadata, UploadPolicy, TUS_VERSION, UPLOAD_STAGING_DIR};
+
+const OFFSET_CONTENT_TYPE: &str = "application/offset+octet-stream";
+const SWEEP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(600);
+
+#[derive(Serialize, FromRow)]
+struct UploadRow {
+    id: Uuid,
+    user_id: Uuid,
+    filename: String,
+    upload_length: i64,
+    upload_offset: i64,
+    recording_id: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+    completed_at: Option<DateTime<Utc>>,
+}
+
+const UPLOAD_COLUMNS: &str = "id, user_id, filename, upload_length, upload_offset, recording_id, created_at, expires_at, completed_at";
+
+/// Marks an upload as receiving data so a second PATCH can't interleave with it.
+struct ActiveUpload<'a> {
+    state: &'a AppState,
+    id: Uuid,
+}
+
+impl<'a> ActiveUpload<'a> {
+    fn acquire(state: &'a AppState, id: Uuid) -> Option<Self> {
+        let mut active = state.active_uploads.lock().ok()?;
+        active.insert(id).then_some(Self { state, id })
+    }
+}
+
+impl Drop for ActiveUpload<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut active) = self.state.active_uploads.lock() {
+            active.remove(&self.id);
+        }
+    }
+}
+
+/// A tus 1.0 server (creation, expiration and termination extensions) for adding videos
+/// recorded elsewhere to the library. There's no OPTIONS discovery: the CORS layer answers
+/// every OPTIONS request.
+pub fn router() -> Router<Arc<AppState>> {
+    Router::new()
+        .route("/", post(create_upload).get(list_uploads))
+        .route("/:id", head(head_upload).patch(patch_upload).delete(delete_upload))
+}
+
+fn tus_response(status: StatusCode) -> axum::http::response::Builder {
+    Response::builder().status(status).header("Tus-Resumable", TUS_VERSION)
+}
+
+/// The 412 to send when the client doesn't speak our tus version.
+fn unsupported_version(headers: &HeaderMap) -> Option<Response> {
+    match headers.get("Tus-Resumable").and_then(|v| v.to_str().ok()) {
+        Some(TUS_VERSION) => None,
+        _ => Some(tus_response(StatusCode::PRECONDITION_FAILED)
+            .header("Tus-Version", TUS_VERSION)
+            .body(Body::from("Unsupported or missing Tus-Resumable version"))
+            .unwrap()),
+    }
+}
+
+fn header_i64(headers: &HeaderMap, name: &str) -> Option<i64> {
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse::<i64>().ok())
+        .filter(|n| *n >= 0)
+}
+
+fn expires_header(expires_at: DateTime<Utc>) -> String {
+    httpdate::fmt_http_date(std::time::SystemTime::from(expires_at))
+}
+
+fn staging_path(base: &FsPath, id: Uuid) -> PathBuf {
+    base.join(UPLOAD_STAGING_DIR).join(format!("{}.part", id.simple()))
+}
+
+/// Load an upload the caller may touch. Unfinished uploads past their expiry are gone.
+async fn load_upload(pool: &sqlx::PgPool, id: Uuid, user_id: Uuid, role: &str) -> Result<UploadRow, (StatusCode, &'static str)> {
+    let upload: Option<UploadRow> = sqlx::query_as(&format!("SELECT {} FROM uploads WHERE id = $1", UPLOAD_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+        .unwrap_or(None);
+    let upload = upload.ok_or((StatusCode::NOT_FOUND, "Upload not found"))?;
+    if role != "admin" && upload.user_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Access denied"));
+    }
+    if upload.completed_at.is_none() && upload.expires_at <= Utc::now() {
+        return Err((StatusCode::GONE, "Upload expired"));
+    }
+    Ok(upload)
+}
+
+/// Start an upload. The file name comes from `Upload-Metadata`; extension, size limit and
+/// quota (counting other unfinished uploads) are checked before any bytes are accepted.
+async fn create_upload(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> Response {
+    let (user_id, _, _) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if let Some(r) = unsupported_version(&headers) {
+        return r;
+    }
+    if headers.contains_key("Upload-Defer-Length") {
+        return (StatusCode::BAD_REQUEST, "Deferred upload length is not supported").into_response();
+    }
+    let length = match header_i64(&headers, "Upload-Length") {
+        Some(0) => return (StatusCode::BAD_REQUEST, "Empty files can't be uploaded").into_response(),
+        Some(n) => n,
+        None => return (StatusCode::BAD_REQUEST, "Missing or invalid Upload-Length").into_response(),
+    };
+    let metadata = match parse_metadata(headers.get("Upload-Metadata").and_then(|v| v.to_str().ok()).unwrap_or("")) {
+        Ok(m) => m,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+    let name = match metadata.get("filename").map(|n| client_file_name(n)).filter(|n| !n.is_empty()) {
+        Some(n) => n.to_string(),
+        None => return (StatusCode::BAD_REQUEST, "Upload-Metadata must include a filename").into_response(),
+    };
+    if let Err(e) = validate_component(&name) {
+        return (StatusCode::BAD_REQUEST, e).into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let policy = load_upload_policy(pool).await;
+    if !policy.enabled {
+        return (StatusCode::FORBIDDEN, "Uploads are disabled").into_response();
+    }
+    if !policy.allows(&name) {
+        return (
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Only {} files can be uploaded", policy.allowed_extensions.join(", ")),
+        )
+            .into_response();
+    }
+    if let Some(max) = policy.max_size_bytes.filter(|max| length > *max) {
+        return (StatusCode::PAYLOAD_TOO_LARGE, format!("File is larger than the {} byte upload limit", max)).into_response();
+    }
+
+    // Bytes promised to unfinished uploads aren't in `recordings` yet but will be
+    let reserved: Option<(Option<i64>,)> = sqlx::query_as(
+        "SELECT SUM(upload_length)::BIGINT FROM uploads WHERE user_id = $1 AND completed_at IS NULL AND expires_at > NOW()"
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await
+    .unwrap_or(None);
+    let reserved = reserved.and_then(|r| r.0).unwrap_or(0);
+    if let Err((status, msg)) = check_storage_quota(pool, user_id, reserved + length).await {
+        return (status, msg).into_response();
+    }
+
+    let base = match get_recording_base(pool).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
+    if let Err(e) = tokio::fs::create_dir_all(base.join(UPLOAD_STAGING_DIR)).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create upload folder: {}", e)).into_response();
+    }
+
+    let inserted: Result<(Uuid, DateTime<Utc>), _> = sqlx::query_as(
+        "INSERT INTO uploads (user_id, filename, upload_length, expires_at)
+         VALUES ($1, $2, $3, NOW() + make_interval(hours => $4)) RETURNING id, expires_at"
+    )
+    .bind(user_id)
+    .bind(&name)
+    .bind(length)
+    .bind(policy.expire_hours as i32)
+    .fetch_one(pool)
+    .await;
+    let (id, expires_at) = match inserted {
+        Ok(row) => row,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Insert Error: {}", e)).into_response(),
+    };
+    if let Err(e) = tokio::fs::File::create(staging_path(&base, id)).await {
+        let _ = sqlx::query("DELETE FROM uploads WHERE id = $1").bind(id).execute(pool).await;
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create upload file: {}", e)).into_response();
+    }
+
+    tracing::info!("Upload {} started: {} ({} bytes)", id, name, length);
+    tus_response(StatusCode::CREATED)
+        .header(LOCATION, format!("/api/uploads/{}", id))
+        .header("Upload-Offset", 0)
+        .header("Upload-Expires", expires_header(expires_at))
+        .body(Body::empty())
+        .unwrap()
+}
+
+/// The caller's unfinished uploads, so a client can offer to resume them.
+async fn list_uploads(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    let (user_id, _, _) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    match sqlx::query_as::<_, UploadRow>(&format!(
+        "SELECT {} FROM uploads WHERE user_id = $1 AND completed_at IS NULL AND expires_at > NOW() ORDER BY created_at DESC",
+        UPLOAD_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_all(pool)
+    .await
+    {
+        Ok(uploads) => Json(uploads).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Error: {}", e)).into_response(),
+    }
+}
+
+/// Report how much of an upload the server has, so the client knows where to resume.
+async fn head_upload(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if let Some(r) = unsupported_version(&headers) {
+        return r;
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let upload = match load_upload(pool, id, user_id, &role).await {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    let mut builder = tus_response(StatusCode::OK)
+        .header(CACHE_CONTROL, "no-store")
+        .header("Upload-Offset", upload.upload_offset)
+        .header("Upload-Length", upload.upload_length);
+    builder = match upload.recording_id {
+        Some(rid) => builder.header("Upload-Recording-Id", rid.to_string()),
+        None => builder.header("Upload-Expires", expires_header(upload.expires_at)),
+    };
+    builder.body(Body::empty()).unwrap()
+}
+
+/// Append the request body at `Upload-Offset`. Whatever arrives is kept even if the
+/// connection drops, and the last byte moves the file into the library.
+async fn patch_upload(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+    body: Body,
+) -> Response {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if let Some(r) = unsupported_version(&headers) {
+        return r;
+    }
+    if headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) != Some(OFFSET_CONTENT_TYPE) {
+        return (StatusCode::UNSUPPORTED_MEDIA_TYPE, format!("Content-Type must be {}", OFFSET_CONTENT_TYPE)).into_response();
+    }
+    let offset = match header_i64(&headers, "Upload-Offset") {
+        Some(o) => o,
+        None => return (StatusCode::BAD_REQUEST, "Missing or invalid Upload-Offset").into_response(),
+    };
+
+    // The body can take hours; don't hold the database lock while it streams
+    let pool = match state.db.read().await.clone() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+    // Claim the upload before reading its offset, so a PATCH that just finished can't leave
+    // this one checking against a stale row
+    let _active = match ActiveUpload::acquire(&state, id) {
+        Some(a) => a,
+        None => return (StatusCode::CONFLICT, "Upload is already receiving data").into_response(),
+    };
+    let upload = match load_upload(&pool, id, user_id, &role).await {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if upload.completed_at.is_some() {
+        return (StatusCode::CONFLICT, "Upload already completed").into_response();
+    }
+    if offset != upload.upload_offset {
+        return (StatusCode::CONFLICT, format!("Upload-Offset must be {}", upload.upload_offset)).into_response();
+    }
+
+    let policy = load_upload_policy(&pool).await;
+    let base = match get_recording_base(&pool).await {
+        Ok(b) => b,
+        Err(r) => return r,
+    };
+    let mut file = match tokio::fs::OpenOptions::new().write(true).open(staging_path(&base, id)).await {
+        Ok(f) => f,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return (StatusCode::GONE, "Upload data is gone").into_response();
+        }
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Open Error: {}", e)).into_response(),
+    };
+    // Drop anything an interrupted request wrote past the recorded offset
+    if let Err(e) = file.set_len(offset as u64).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Write Error: {}", e)).into_response();
+    }
+    if let Err(e) = file.seek(std::io::SeekFrom::Start(offset as u64)).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Write Error: {}", e)).into_response();
+    }
+
+    let remaining = upload.upload_length - offset;
+    let mut written: i64 = 0;
+    let mut failure: Option<Response> = None;
+    let mut stream = body.into_data_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(c) => c,
+            Err(e) => {
+                failure = Some((StatusCode::BAD_REQUEST, format!("Upload interrupted: {}", e)).into_response());
+                break;
+            }
+        };
+        if written + chunk.len() as i64 > remaining {
+            failure = Some((StatusCode::PAYLOAD_TOO_LARGE, "Body runs past Upload-Length").into_response());
+            break;
+        }
+        if let Err(e) = file.write_all(&chunk).await {
+            failure = Some((StatusCode::INTERNAL_SERVER_ERROR, format!("File Write Error: {}", e)).into_response());
+            break;
+        }
+        written += chunk.len() as i64;
+    }
+    if let Err(e) = file.sync_data().await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("File Write Error: {}", e)).into_response();
+    }
+    drop(file);
+
+    let new_offset = offset + written;
+    let updated: Result<(DateTime<Utc>,), _> = sqlx::query_as(
+        "UPDATE uploads SET upload_offset = $2, updated_at = NOW(), expires_at = NOW() + make_interval(hours => $3)
+         WHERE id = $1 RETURNING expires_at"
+    )
+    .bind(id)
+    .bind(new_offset)
+    .bind(policy.expire_hours as i32)
+    .fetch_one(&pool)
+    .await;
+    let expires_at = match updated {
+        Ok(row) => row.0,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Update Error: {}", e)).into_response(),
+    };
+    if let Some(r) = failure {
+        return r;
+    }
+
+    let builder = tus_response(StatusCode::NO_CONTENT).header("Upload-Offset", new_offset);
+    if new_offset < upload.upload_length {
+        return builder.header("Upload-Expires", expires_header(expires_at)).body(Body::empty()).unwrap();
+    }
+    match finalize_upload(&pool, &upload, &base, &policy).await {
+        Ok(recording_id) => builder
+            .header("Upload-Recording-Id", recording_id.to_string())
+            .body(Body::empty())
+            .unwrap(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to add upload to the library: {}", e)).into_response(),
+    }
+}
+
+/// Move a completed upload into the library and give it a recording row.
+async fn finalize_upload(pool: &sqlx::PgPool, upload: &UploadRow, base: &FsPath, policy: &UploadPolicy) -> anyhow::Result<Uuid> {
+    let relative = unused_name(base, &policy.destination(&upload.filename)).await;
+    let target = base.join(&relative);
+    if let Some(parent) = target.parent() {
+        tokio::fs::create_dir_all(parent).await?;
+    }
+    let staged = staging_path(base, upload.id);
+    tokio::fs::rename(&staged, &target).await?;
+
+    let filepath = target.to_string_lossy().to_string();
+    let inserted: Result<(Uuid,), _> = sqlx::query_as(
+        "INSERT INTO recordings (user_id, filename, filepath, status) VALUES ($1, $2, $3, 'finalizing') RETURNING id"
+    )
+    .bind(upload.user_id)
+    .bind(&relative)
+    .bind(&filepath)
+    .fetch_one(pool)
+    .await;
+    let recording_id = match inserted {
+        Ok(row) => row.0,
+        Err(e) => {
+            // Put it back so the client can finish with a retry of the last PATCH
+            let _ = tokio::fs::rename(&target, &staged).await;
+            return Err(e.into());
+        }
+    };
+    sqlx::query("UPDATE uploads SET recording_id = $2, completed_at = NOW() WHERE id = $1")
+        .bind(upload.id)
+        .bind(recording_id)
+        .execute(pool)
+        .await?;
+    if let Err(e) = log_status(pool, recording_id, None, RecordingStatus::Finalizing, "upload completed").await {
+        tracing::warn!("Failed to log status of uploaded recording {}: {}", recording_id, e);
+    }
+    tracing::info!("Upload {} added to the library as {}", upload.id, relative);
+
+    let pool = pool.clone();
+    tokio::spawn(async move {
+        if let Err(e) = settle_recording(&pool, recording_id, &filepath, true, "uploaded file probed").await {
+            tracing::warn!("Failed to settle uploaded recording {}: {}", recording_id, e);
+        }
+    });
+    Ok(recording_id)
+}
+
+/// Abandon an upload, discarding what was received. A finished upload only loses its
+/// bookkeeping row; the recording stays.
+async fn delete_upload(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+    Path(id): Path<Uuid>,
+) -> Response {
+    let (user_id, _, role) = match get_user_from_header(&headers) {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    if let Some(r) = unsupported_version(&headers) {
+        return r;
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let upload = match load_upload(pool, id, user_id, &role).await {
+        Ok(u) => u,
+        Err(e) => return e.into_response(),
+    };
+    let _active = match ActiveUpload::acquire(&state, id) {
+        Some(a) => a,
+        None => return (StatusCode::CONFLICT, "Upload is receiving data").into_response(),
+    };
+    if upload.completed_at.is_none() {
+        if let Ok(base) = get_recording_base(pool).await {
+            let _ = tokio::fs::remove_file(staging_path(&base, id)).await;
+        }
+    }
+    if let Err(e) = sqlx::query("DELETE FROM uploads WHERE id = $1").bind(id).execute(pool).await {
+        return (StatusCode::INTERNAL_SERVER_ERROR, format!("DB Delete Error: {}", e)).into_response();
+    }
+
+    tus_response(StatusCode::NO_CONTENT).body(Body::empty()).unwrap()
+}
+
+/// Discard unfinished uploads past their expiry, and forget finished ones after the same delay.
+pub(crate) async fn upload_sweep_loop(state: Arc<AppState>, mut shutdown_rx: tokio::sync::watch::Receiver<bool>) {
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(SWEEP_INTERVAL) => {}
+            _ = shutdown_rx.changed() => break,
+        }
+
+        let pool = match state.db.read().await.clone() {
+            Some(p) => p,
+            None => continue,
+        };
+        let expired: Vec<(Uuid, Option<DateTime<Utc>>)> = match sqlx::query_as(
+            "SELECT id, completed_at FROM uploads WHERE expires_at <= NOW()"
+        )
+        .fetch_all(&pool)
+        .await
+        {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::warn!("Upload sweep failed: {}", e);
+                continue;
+            }
+        };
+        if expired.is_empty() {
+            continue;
+        }
+        let base = get_recording_base(&pool).await.ok();
+        let mut swept = 0;
+        for (id, completed_at) in expired {
+            let _active = match ActiveUpload::acquire(&state, id) {
+                Some(a) => a,
+                None => continue,
+            };
+            if let (None, Some(base)) = (completed_at, base.as_ref()) {
+                let _ = tokio::fs::remove_file(staging_path(base, id)).await;
+            }
+            if sqlx::query("DELETE FROM uploads WHERE id = $1").bind(id).execute(&pool).await.is_ok() {
+                swept += 1;
+            }
+        }
+        tracing::debug!("Swept {} expired uploads", swept);
+    }
+}
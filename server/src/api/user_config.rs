@@ -10,18 +10,22 @@ use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use std::sync::Arc;
 use crate::AppState;
+use crate::api::settings::load_encoder_catalogue;
 use crate::api::recorder::{
     get_user_from_header,
     validate_capture_method,
     validate_capture_mode,
     validate_device_id,
     validate_window_id,
+    validate_window_rule_value,
+    validate_encoder_id,
     validate_max_bitrate,
     validate_max_fps,
     validate_resolution_limit,
     validate_resolution_value,
     validate_rtmp_key,
     validate_rtmp_url,
+    validate_scale_mode,
 };
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -37,11 +41,19 @@ pub struct UserConfig {
     pub capture_mode: Option<String>,
     pub capture_method: Option<String>,
     pub window_id: Option<String>,
+    pub window_exe: Option<String>,
+    pub window_class: Option<String>,
+    pub window_title_pattern: Option<String>,
+    /// Preferred encoders in fallback order; each must be allowed in the encoder catalogue.
+    pub video_encoders: Option<Vec<String>>,
+    /// How the output size follows the source monitor: fit (default), fill or exact.
+    pub scale_mode: Option<String>,
 }
 
 pub fn router() -> Router<Arc<AppState>> {
     Router::new()
         .route("/config", get(get_config).post(update_config))
+        .route("/encoders", get(get_allowed_encoders))
 }
 
 async fn get_sys_val(pool: &sqlx::PgPool, key: &str) -> Option<serde_json::Value> {
@@ -68,7 +80,7 @@ async fn get_config(
         None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
     };
 
-    let config = sqlx::query_as::<_, UserConfig>("SELECT max_bitrate, max_fps, resolution, monitor_id, desktop_audio, mic_audio, rtmp_url, rtmp_key, capture_mode, capture_method, window_id FROM user_configs WHERE user_id = $1")
+    let config = sqlx::query_as::<_, UserConfig>("SELECT max_bitrate, max_fps, resolution, monitor_id, desktop_audio, mic_audio, rtmp_url, rtmp_key, capture_mode, capture_method, window_id, window_exe, window_class, window_title_pattern, video_encoders, scale_mode FROM user_configs WHERE user_id = $1")
         .bind(user_id)
         .fetch_optional(pool)
         .await
@@ -88,10 +100,33 @@ async fn get_config(
             capture_mode: None,
             capture_method: None,
             window_id: None,
+            window_exe: None,
+            window_class: None,
+            window_title_pattern: None,
+            video_encoders: None,
+            scale_mode: None,
         }).into_response(),
     }
 }
 
+async fn get_allowed_encoders(
+    State(state): State<Arc<AppState>>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(e) = get_user_from_header(&headers) {
+        return e.into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    let allowed: Vec<_> = load_encoder_catalogue(pool).await.into_iter().filter(|e| e.allowed).collect();
+    Json(allowed).into_response()
+}
+
 async fn update_config(
     State(state): State<Arc<AppState>>,
     headers: HeaderMap,
@@ -185,11 +220,34 @@ async fn update_config(
             return (StatusCode::BAD_REQUEST, e).into_response();
         }
     }
+    for value in [&payload.window_exe, &payload.window_class, &payload.window_title_pattern].into_iter().flatten() {
+        if let Err(e) = validate_window_rule_value(value) {
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    }
+
+    if let Some(scale_mode) = payload.scale_mode.as_ref() {
+        if let Err(e) = validate_scale_mode(scale_mode) {
+            return (StatusCode::BAD_REQUEST, e).into_response();
+        }
+    }
+
+    if let Some(encoders) = payload.video_encoders.as_ref() {
+        let catalogue = load_encoder_catalogue(pool).await;
+        for id in encoders {
+            if let Err(e) = validate_encoder_id(id) {
+                return (StatusCode::BAD_REQUEST, e).into_response();
+            }
+            if !catalogue.iter().any(|entry| &entry.id == id && entry.allowed) {
+                return (StatusCode::BAD_REQUEST, format!("Encoder not allowed: {}", id)).into_response();
+            }
+        }
+    }
 
     let result = sqlx::query(
         r#"
-        INSERT INTO user_configs (user_id, max_bitrate, max_fps, resolution, monitor_id, desktop_audio, mic_audio, rtmp_url, rtmp_key, capture_mode, capture_method, window_id)
-        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+        INSERT INTO user_configs (user_id, max_bitrate, max_fps, resolution, monitor_id, desktop_audio, mic_audio, rtmp_url, rtmp_key, capture_mode, capture_method, window_id, window_exe, window_class, window_title_pattern, video_encoders, scale_mode)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17)
         ON CONFLICT (user_id)
         DO UPDATE SET
             max_bitrate = EXCLUDED.max_bitrate,
@@ -202,7 +260,12 @@ async fn update_config(
             rtmp_key = EXCLUDED.rtmp_key,
             capture_mode = EXCLUDED.capture_mode,
             capture_method = EXCLUDED.capture_method,
-            window_id = EXCLUDED.window_id
+            window_id = EXCLUDED.window_id,
+            window_exe = EXCLUDED.window_exe,
+            window_class = EXCLUDED.window_class,
+            window_title_pattern = EXCLUDED.window_title_pattern,
+            video_encoders = EXCLUDED.video_encoders,
+            scale_mode = EXCLUDED.scale_mode
         "#
     )
     .bind(user_id)
@@ -217,6 +280,11 @@ async fn update_config(
     .bind(payload.capture_mode)
     .bind(payload.capture_method)
     .bind(payload.window_id)
+    .bind(payload.window_exe)
+    .bind(payload.window_class)
+    .bind(payload.window_title_pattern)
+    .bind(payload.video_encoders)
+    .bind(payload.scale_mode)
     .execute(pool)
     .await;
 
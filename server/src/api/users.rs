@@ -19,6 +19,14 @@ pub struct UserInfo {
     pub username: String,
     pub role: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// None means unlimited.
+    pub storage_quota_bytes: Option<i64>,
+    pub storage_used_bytes: i64,
+}
+
+#[derive(Deserialize)]
+pub struct QuotaPayload {
+    pub storage_quota_bytes: Option<i64>,
 }
 
 #[derive(Deserialize)]
@@ -31,6 +39,7 @@ pub fn router() -> Router<Arc<AppState>> {
         .route("/", get(list_users))
         .route("/:id", delete(delete_user))
         .route("/:id/reset-password", post(reset_password))
+        .route("/:id/quota", post(set_quota))
 }
 
 // Middleware check for admin would be better, but we'll check manually
@@ -56,7 +65,9 @@ async fn list_users(
         None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
     };
 
-    let users = sqlx::query_as::<_, UserInfo>("SELECT id, username, role, created_at FROM users ORDER BY created_at DESC")
+    let users = sqlx::query_as::<_, UserInfo>("SELECT u.id, u.username, u.role, u.created_at, u.storage_quota_bytes,
+                COALESCE((SELECT SUM(COALESCE(r.size_bytes, 0)) FROM recordings r WHERE r.user_id = u.id AND r.status <> 'missing' AND r.deleted_at IS NULL), 0)::BIGINT AS storage_used_bytes
+         FROM users u ORDER BY u.created_at DESC")
         .fetch_all(pool)
         .await;
 
@@ -124,3 +135,34 @@ async fn reset_password(
         Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to reset password: {}", e)).into_response(),
     }
 }
+
+async fn set_quota(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    Json(payload): Json<QuotaPayload>,
+) -> impl IntoResponse {
+    if let Err(e) = ensure_admin(&headers).await {
+        return e.into_response();
+    }
+    if payload.storage_quota_bytes.is_some_and(|q| q < 0) {
+        return (StatusCode::BAD_REQUEST, "Quota must not be negative").into_response();
+    }
+
+    let db_guard = state.db.read().await;
+    let pool = match db_guard.as_ref() {
+        Some(p) => p,
+        None => return (StatusCode::SERVICE_UNAVAILABLE, "Database not connected").into_response(),
+    };
+
+    match sqlx::query("UPDATE users SET storage_quota_bytes = $1 WHERE id = $2")
+        .bind(payload.storage_quota_bytes)
+        .bind(id)
+        .execute(pool)
+        .await
+    {
+        Ok(r) if r.rows_affected() == 0 => (StatusCode::NOT_FOUND, "User not found").into_response(),
+        Ok(_) => (StatusCode::OK, "Quota updated").into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update quota: {}", e)).into_response(),
+    }
+}
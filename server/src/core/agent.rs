@@ -1,4 +1,4 @@
-use tokio::net::TcpListener;
+use tokio::net::{TcpListener, TcpStream};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
@@ -40,7 +40,19 @@ impl AgentServer {
                         match socket.read(&mut buf).await {
                             Ok(n) if n > 0 => {
                                 if let Ok(cmd) = serde_json::from_slice::<AgentCommand>(&buf[..n]) {
-                                    let response = handle_command(cmd).await;
+                                    let response = if cmd.command == "scan" {
+                                        // The server cancels a scan by closing the connection;
+                                        // dropping the scan then kills the CLI
+                                        tokio::select! {
+                                            response = run_cli_scan(cmd.cli_path, cmd.args) => response,
+                                            _ = wait_for_close(&mut socket) => {
+                                                tracing::info!("Scan cancelled by the server");
+                                                return;
+                                            }
+                                        }
+                                    } else {
+                                        handle_command(cmd).await
+                                    };
                                     if let Ok(json) = serde_json::to_vec(&response) {
                                         let _ = socket.write_all(&json).await;
                                     }
@@ -58,6 +70,18 @@ impl AgentServer {
     }
 }
 
+/// Resolve once the peer closes its end. The server sends a single command and then only
+/// reads, so any EOF or error means it gave up on the request.
+async fn wait_for_close(socket: &mut TcpStream) {
+    let mut probe = [0u8; 1];
+    loop {
+        match socket.read(&mut probe).await {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {}
+        }
+    }
+}
+
 async fn handle_command(cmd: AgentCommand) -> AgentResponse {
     match cmd.command.as_str() {
         "start" => start_cli_process(cmd.cli_path, cmd.args).await,
@@ -105,7 +129,7 @@ async fn start_cli_process(cli_path: String, args: Vec<String>) -> AgentResponse
 async fn run_cli_scan(cli_path: String, args: Vec<String>) -> AgentResponse {
     use tokio::process::Command;
 
-    match Command::new(&cli_path).args(&args).output().await {
+    match Command::new(&cli_path).args(&args).kill_on_drop(true).output().await {
         Ok(output) => {
             if output.status.success() {
                 AgentResponse {
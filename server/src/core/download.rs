@@ -0,0 +1,187 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Outcome of matching a `Range` header against a file of known length.
+#[derive(Debug, PartialEq)]
+pub enum ByteRange {
+    /// No usable range: send the whole file with 200.
+    Full,
+    /// Inclusive `start..=end`, sent with 206.
+    Partial { start: u64, end: u64 },
+    /// The range lies outside the file: answer 416.
+    Unsatisfiable,
+}
+
+/// Parse a single `bytes=` range. Multi-range requests fall back to the full body,
+/// which RFC 9110 allows and saves us a multipart encoder.
+pub fn parse_range(header: Option<&str>, len: u64) -> ByteRange {
+    let spec = match header.and_then(|h| h.trim().strip_prefix("bytes=")) {
+        Some(s) if !s.contains(',') => s.trim(),
+        _ => return ByteRange::Full,
+    };
+    let (start, end) = match spec.split_once('-') {
+        Some(v) => v,
+        None => return ByteRange::Full,
+    };
+
+    if start.is_empty() {
+        // Suffix range: the last N bytes
+        let suffix = match end.parse::<u64>() {
+            Ok(n) => n,
+            Err(_) => return ByteRange::Full,
+        };
+        if suffix == 0 || len == 0 {
+            return ByteRange::Unsatisfiable;
+        }
+        return ByteRange::Partial { start: len.saturating_sub(suffix), end: len - 1 };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(n) => n,
+        Err(_) => return ByteRange::Full,
+    };
+    if start >= len {
+        return ByteRange::Unsatisfiable;
+    }
+    let end = if end.is_empty() {
+        len - 1
+    } else {
+        match end.parse::<u64>() {
+            Ok(n) if n >= start => n.min(len - 1),
+            _ => return ByteRange::Full,
+        }
+    };
+    ByteRange::Partial { start, end }
+}
+
+/// Validator derived from size and mtime, which changes whenever the recording is rewritten.
+pub fn etag(len: u64, modified: Option<SystemTime>) -> String {
+    let nanos = modified
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    format!("\"{:x}-{:x}\"", len, nanos)
+}
+
+/// `If-None-Match` check; accepts `*` and comma-separated lists, ignoring weak prefixes.
+pub fn etag_matches(header: &str, etag: &str) -> bool {
+    header
+        .split(',')
+        .map(|t| t.trim().trim_start_matches("W/"))
+        .any(|t| t == "*" || t == etag)
+}
+
+/// `Content-Disposition` value naming only the file's basename. The quoted `filename` is an
+/// ASCII fallback; `filename*` (RFC 6266) carries the real name for clients that read it.
+pub fn content_disposition(disposition: &str, filename: &str) -> String {
+    let name = filename.rsplit(['/', '\\']).next().filter(|n| !n.is_empty()).unwrap_or("download");
+    let mut fallback = String::with_capacity(name.len());
+    for c in name.chars() {
+        match c {
+            '"' => fallback.push_str("\\\""),
+            c if c.is_ascii() && !c.is_ascii_control() => fallback.push(c),
+            _ => fallback.push('_'),
+        }
+    }
+    let mut encoded = String::with_capacity(name.len() * 3);
+    for b in name.bytes() {
+        // attr-char from RFC 8187; everything else is percent-encoded
+        if b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", b));
+        }
+    }
+    format!("{}; filename=\"{}\"; filename*=UTF-8''{}", disposition, fallback, encoded)
+}
+
+/// Content type from the file extension so browsers can play recordings inline.
+pub fn content_type(filename: &str) -> String {
+    mime_guess::from_path(filename)
+        .first()
+        .map(|m| m.essence_str().to_string())
+        .unwrap_or_else(|| "application/octet-stream".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn parse_range_table() {
+        use ByteRange::*;
+        let cases = [
+            (None, 1000, Full),
+            (Some("bytes=0-99"), 1000, Partial { start: 0, end: 99 }),
+            (Some(" bytes= 0-9 "), 1000, Partial { start: 0, end: 9 }),
+            // Open-ended runs to the last byte
+            (Some("bytes=500-"), 1000, Partial { start: 500, end: 999 }),
+            // An end past the file is clamped
+            (Some("bytes=0-5000"), 1000, Partial { start: 0, end: 999 }),
+            // Suffix ranges take the last N bytes, or the whole file if N is larger
+            (Some("bytes=-100"), 1000, Partial { start: 900, end: 999 }),
+            (Some("bytes=-2000"), 1000, Partial { start: 0, end: 999 }),
+            (Some("bytes=-0"), 1000, Unsatisfiable),
+            (Some("bytes=1000-"), 1000, Unsatisfiable),
+            (Some("bytes=0-"), 0, Unsatisfiable),
+            (Some("bytes=-5"), 0, Unsatisfiable),
+            // Multi-range and malformed headers fall back to the whole body
+            (Some("bytes=0-1,5-6"), 1000, Full),
+            (Some("bytes=5-1"), 1000, Full),
+            (Some("bytes=x-5"), 1000, Full),
+            (Some("bytes=-x"), 1000, Full),
+            (Some("bytes=abc"), 1000, Full),
+            (Some("items=0-1"), 1000, Full),
+        ];
+        for (header, len, expected) in cases {
+            assert_eq!(parse_range(header, len), expected, "{:?} of {}", header, len);
+        }
+    }
+
+    #[test]
+    fn etag_changes_with_size_and_mtime() {
+        let t = UNIX_EPOCH + Duration::from_secs(1);
+        assert_eq!(etag(0, None), "\"0-0\"");
+        assert_eq!(etag(255, Some(t)), "\"ff-3b9aca00\"");
+        assert_ne!(etag(255, Some(t)), etag(256, Some(t)));
+        assert_ne!(etag(255, Some(t)), etag(255, Some(t + Duration::from_nanos(1))));
+    }
+
+    #[test]
+    fn etag_matches_table() {
+        let tag = "\"ff-1\"";
+        let cases = [
+            ("\"ff-1\"", true),
+            ("*", true),
+            // Weak comparison, as If-None-Match calls for
+            ("W/\"ff-1\"", true),
+            ("\"aa-1\", W/\"ff-1\"", true),
+            ("\"aa-1\",\"ff-1\"", true),
+            ("\"aa-1\"", false),
+            ("ff-1", false),
+            ("", false),
+        ];
+        for (header, expected) in cases {
+            assert_eq!(etag_matches(header, tag), expected, "header {:?}", header);
+        }
+    }
+
+    #[test]
+    fn content_disposition_table() {
+        let cases = [
+            ("rec.mp4", r#"attachment; filename="rec.mp4"; filename*=UTF-8''rec.mp4"#),
+            // Only the basename leaves the server
+            ("alice/2026/rec.mp4", r#"attachment; filename="rec.mp4"; filename*=UTF-8''rec.mp4"#),
+            (r"C:\rec\old.mp4", r#"attachment; filename="old.mp4"; filename*=UTF-8''old.mp4"#),
+            // Quotes can't end the quoted string early
+            (r#"a"b.mp4"#, r#"attachment; filename="a\"b.mp4"; filename*=UTF-8''a%22b.mp4"#),
+            ("my rec;x.mp4", r#"attachment; filename="my rec;x.mp4"; filename*=UTF-8''my%20rec%3Bx.mp4"#),
+            ("\u{e9}t\u{e9}.mp4", r#"attachment; filename="_t_.mp4"; filename*=UTF-8''%C3%A9t%C3%A9.mp4"#),
+            ("line\nbreak.mp4", r#"attachment; filename="line_break.mp4"; filename*=UTF-8''line%0Abreak.mp4"#),
+            ("dir/", r#"attachment; filename="download"; filename*=UTF-8''download"#),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(content_disposition("attachment", name), expected, "name {:?}", name);
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use crate::core::hardware::Device;
+
+/// One encoder reported by `--scan`, annotated with admin policy.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EncoderEntry {
+    pub id: String,
+    pub name: String,
+    pub hardware: bool,
+    #[serde(default = "default_allowed")]
+    pub allowed: bool,
+    #[serde(default)]
+    pub max_bitrate: Option<i32>,
+    #[serde(default)]
+    pub max_fps: Option<i32>,
+    #[serde(default)]
+    pub max_res: Option<String>,
+}
+
+fn default_allowed() -> bool {
+    true
+}
+
+// OBS encoder ids carry the vendor API in their name (jim_nvenc, h264_texture_amf, obs_qsv11, ...)
+const HARDWARE_MARKERS: [&str; 6] = ["nvenc", "amf", "qsv", "vaapi", "videotoolbox", "_mf"];
+
+pub fn is_hardware_encoder(id: &str) -> bool {
+    let id = id.to_lowercase();
+    HARDWARE_MARKERS.iter().any(|m| id.contains(m))
+}
+
+/// Rebuild the catalogue from a fresh scan, keeping admin settings for encoders that are still present.
+pub fn merge_catalogue(existing: &[EncoderEntry], scanned: &[Device]) -> Vec<EncoderEntry> {
+    scanned
+        .iter()
+        .map(|device| match existing.iter().find(|e| e.id == device.id) {
+            Some(prev) => EncoderEntry {
+                name: device.name.clone(),
+                hardware: is_hardware_encoder(&device.id),
+                ..prev.clone()
+            },
+            None => EncoderEntry {
+                id: device.id.clone(),
+                name: device.name.clone(),
+                hardware: is_hardware_encoder(&device.id),
+                allowed: true,
+                max_bitrate: None,
+                max_fps: None,
+                max_res: None,
+            },
+        })
+        .collect()
+}
+
+/// Ordered list of encoders to try at start: the user's allowed preferences, then the system default.
+pub fn encoder_chain(catalogue: &[EncoderEntry], preferred: &[String], system_default: &str) -> Vec<String> {
+    let mut chain: Vec<String> = Vec::new();
+    for id in preferred {
+        let allowed = catalogue.iter().any(|e| &e.id == id && e.allowed);
+        if allowed && !chain.contains(id) {
+            chain.push(id.clone());
+        }
+    }
+    if !chain.iter().any(|id| id == system_default) {
+        chain.push(system_default.to_string());
+    }
+    chain
+}
@@ -0,0 +1,105 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_PAGE_SIZE: i64 = 50;
+pub const MAX_PAGE_SIZE: i64 = 200;
+
+/// Column the file list is ordered by. Nullable columns are coalesced so keyset
+/// comparisons never see NULL.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    CreatedAt,
+    Filename,
+    Size,
+    Duration,
+}
+
+impl SortKey {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "created_at" => Some(Self::CreatedAt),
+            "filename" => Some(Self::Filename),
+            "size" => Some(Self::Size),
+            "duration" => Some(Self::Duration),
+            _ => None,
+        }
+    }
+
+    /// SQL expression used both in ORDER BY and in the cursor comparison.
+    pub fn expr(self) -> &'static str {
+        match self {
+            Self::CreatedAt => "COALESCE(created_at, 'epoch'::timestamptz)",
+            Self::Filename => "filename",
+            Self::Size => "COALESCE(size_bytes, -1)",
+            Self::Duration => "COALESCE(duration_secs, -1)",
+        }
+    }
+
+    /// Cast applied to the cursor value, which travels as text.
+    pub fn cast(self) -> &'static str {
+        match self {
+            Self::CreatedAt => "::timestamptz",
+            Self::Filename => "::text",
+            Self::Size => "::bigint",
+            Self::Duration => "::double precision",
+        }
+    }
+}
+
+/// Position after the last row of a page: the sort value and id of that row, plus the
+/// ordering it was taken under, since the value means nothing under any other.
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct Cursor {
+    pub sort: SortKey,
+    pub descending: bool,
+    pub value: String,
+    pub id: Uuid,
+}
+
+impl Cursor {
+    /// Opaque, URL-safe form handed to clients.
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(value: &str) -> Option<Self> {
+        let bytes = URL_SAFE_NO_PAD.decode(value).ok()?;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    pub fn matches(&self, sort: SortKey, descending: bool) -> bool {
+        self.sort == sort && self.descending == descending
+    }
+}
+
+/// Escape `%`, `_` and `\` so a filename search matches literally under `ILIKE`.
+pub fn like_pattern(needle: &str) -> String {
+    let mut escaped = String::with_capacity(needle.len() + 2);
+    escaped.push('%');
+    for c in needle.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped.push('%');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cursor_round_trips_with_its_ordering() {
+        let cursor = Cursor { sort: SortKey::Size, descending: false, value: "1024".into(), id: Uuid::from_u128(7) };
+        let decoded = Cursor::decode(&cursor.encode()).unwrap();
+        assert_eq!(decoded, cursor);
+        assert!(decoded.matches(SortKey::Size, false));
+        assert!(!decoded.matches(SortKey::Size, true));
+        assert!(!decoded.matches(SortKey::CreatedAt, false));
+        assert!(Cursor::decode("not a cursor").is_none());
+    }
+}
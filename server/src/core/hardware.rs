@@ -29,6 +29,66 @@ pub struct WindowItem {
     pub id: String,
 }
 
+impl WindowItem {
+    /// The window class is the last segment of the `title:exe:class` id emitted by cli-capture.
+    pub fn class_name(&self) -> &str {
+        self.id.rsplit(':').next().unwrap_or("")
+    }
+}
+
+/// A saved window selection that survives title changes: exe and class must match exactly
+/// (case-insensitive), the title only has to match an optional `*`/`?` wildcard pattern.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct WindowMatchRule {
+    pub exe: String,
+    #[serde(default)]
+    pub class: Option<String>,
+    #[serde(default)]
+    pub title_pattern: Option<String>,
+}
+
+impl WindowMatchRule {
+    pub fn matches(&self, window: &WindowItem) -> bool {
+        if !window.exe.eq_ignore_ascii_case(self.exe.trim()) {
+            return false;
+        }
+        if let Some(class) = self.class.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            if !window.class_name().eq_ignore_ascii_case(class) {
+                return false;
+            }
+        }
+        if let Some(pattern) = self.title_pattern.as_deref().filter(|p| !p.trim().is_empty()) {
+            if !wildcard_match(&pattern.to_lowercase(), &window.title.to_lowercase()) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let t: Vec<char> = text.chars().collect();
+    let (mut pi, mut ti) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ti < t.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == t[ti]) {
+            pi += 1;
+            ti += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ti));
+            pi += 1;
+        } else if let Some((sp, st)) = star {
+            pi = sp + 1;
+            ti = st + 1;
+            star = Some((sp, st + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct WindowsInfo {
     #[serde(default)]
@@ -44,33 +104,114 @@ pub async fn probe_hardware(cli_path: String) -> anyhow::Result<HardwareInfo> {
     let mut info: HardwareInfo = serde_json::from_str(&stdout)
         .map_err(|e| anyhow::anyhow!("Failed to parse scan output: {} (Output: {})", e, stdout))?;
 
-    if let Ok(win_stdout) = run_scan(&cli_path, vec!["--scan-windows".to_string()]).await {
-        if let Ok(win_info) = serde_json::from_str::<WindowsInfo>(&win_stdout) {
-            info.windows = win_info.windows;
-        }
+    if let Ok(windows) = scan_windows(&cli_path).await {
+        info.windows = windows;
     }
 
     Ok(info)
 }
 
+/// Run a fresh `--scan-windows` and return the currently capturable windows.
+pub async fn scan_windows(cli_path: &str) -> anyhow::Result<Vec<WindowItem>> {
+    let stdout = run_scan(cli_path, vec!["--scan-windows".to_string()]).await?;
+    let info: WindowsInfo = serde_json::from_str(&stdout)
+        .map_err(|e| anyhow::anyhow!("Failed to parse window scan output: {} (Output: {})", e, stdout))?;
+    Ok(info.windows)
+}
+
+/// Why a window rule didn't pick out exactly one window.
+#[derive(Debug)]
+pub enum WindowMatchError {
+    NoMatch { exe: String },
+    Ambiguous { exe: String, titles: Vec<String> },
+}
+
+impl std::fmt::Display for WindowMatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::NoMatch { exe } => write!(f, "No open window matches exe '{}'", exe),
+            Self::Ambiguous { exe, titles } => {
+                let titles: Vec<String> = titles.iter().map(|t| format!("\"{}\"", t)).collect();
+                write!(
+                    f,
+                    "{} windows match exe '{}': {}. Narrow the rule with a class or title pattern",
+                    titles.len(),
+                    exe,
+                    titles.join(", ")
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for WindowMatchError {}
+
+/// The one window in `windows` the rule matches.
+pub fn pick_window(rule: &WindowMatchRule, windows: Vec<WindowItem>) -> Result<WindowItem, WindowMatchError> {
+    let mut matches: Vec<WindowItem> = windows.into_iter().filter(|w| rule.matches(w)).collect();
+    match matches.len() {
+        0 => Err(WindowMatchError::NoMatch { exe: rule.exe.clone() }),
+        1 => Ok(matches.remove(0)),
+        _ => Err(WindowMatchError::Ambiguous {
+            exe: rule.exe.clone(),
+            titles: matches.into_iter().map(|w| w.title).collect(),
+        }),
+    }
+}
+
+/// Resolve a match rule against a fresh window scan. Exactly one window must match;
+/// otherwise the error is a `WindowMatchError`.
+pub async fn resolve_window(cli_path: &str, rule: &WindowMatchRule) -> anyhow::Result<WindowItem> {
+    validate_cli_path(cli_path).await?;
+    let windows = scan_windows(cli_path).await?;
+    Ok(pick_window(rule, windows)?)
+}
+
 async fn run_scan(cli_path: &str, args: Vec<String>) -> anyhow::Result<String> {
+    let output = run_cli(cli_path, args).await?;
+    if !output.success {
+        return Err(anyhow::anyhow!("CLI scan failed: {}", output.stderr));
+    }
+    Ok(output.stdout)
+}
+
+/// What a short-lived cli-capture run printed, and how it exited.
+pub(crate) struct CliOutput {
+    pub success: bool,
+    pub exit_code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Run cli-capture to completion, directly or through the agent in service mode.
+pub(crate) async fn run_cli(cli_path: &str, args: Vec<String>) -> anyhow::Result<CliOutput> {
     if is_service_mode() {
-        let agent_addr = std::env::var("AGENT_ADDR").unwrap_or_else(|_| "127.0.0.1:3001".to_string());
-        let agent_client = AgentClient::new(agent_addr);
-        return agent_client.scan_hardware_with_args(cli_path.to_string(), args).await;
+        // Dropping this call closes the agent connection, and the agent kills the scan.
+        // The agent only reports stdout on success and the error text otherwise.
+        return Ok(match agent_client().scan_hardware_with_args(cli_path.to_string(), args).await {
+            Ok(stdout) => CliOutput { success: true, exit_code: Some(0), stdout, stderr: String::new() },
+            Err(e) => CliOutput { success: false, exit_code: None, stdout: String::new(), stderr: e.to_string() },
+        });
     }
+    // kill_on_drop lets a timed-out or cancelled scan job take the OBS process down with it
     let output = tokio::process::Command::new(cli_path)
         .args(&args)
+        .kill_on_drop(true)
         .output()
         .await
         .map_err(|e| anyhow::anyhow!("Failed to execute CLI '{}': {}", cli_path, e))?;
 
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(anyhow::anyhow!("CLI scan failed: {}", stderr));
-    }
+    Ok(CliOutput {
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    })
+}
 
-    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+fn agent_client() -> AgentClient {
+    let agent_addr = std::env::var("AGENT_ADDR").unwrap_or_else(|_| "127.0.0.1:3001".to_string());
+    AgentClient::new(agent_addr)
 }
 
 fn is_service_mode() -> bool {
@@ -78,7 +219,7 @@ fn is_service_mode() -> bool {
         || std::env::var("RUN_AS_SERVICE").map(|v| v == "1").unwrap_or(false)
 }
 
-async fn validate_cli_path(cli_path: &str) -> anyhow::Result<()> {
+pub(crate) async fn validate_cli_path(cli_path: &str) -> anyhow::Result<()> {
     let path = cli_path.trim();
     if path.is_empty() {
         return Err(anyhow::anyhow!("CLI path not configured"));
@@ -98,3 +239,80 @@ async fn validate_cli_path(cli_path: &str) -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn window(title: &str, exe: &str, class: &str) -> WindowItem {
+        WindowItem { title: title.into(), exe: exe.into(), id: format!("{}:{}:{}", title, exe, class) }
+    }
+
+    fn rule(exe: &str, class: Option<&str>, title_pattern: Option<&str>) -> WindowMatchRule {
+        WindowMatchRule { exe: exe.into(), class: class.map(String::from), title_pattern: title_pattern.map(String::from) }
+    }
+
+    #[test]
+    fn wildcard_match_table() {
+        let cases = [
+            ("", "", true),
+            ("", "a", false),
+            ("*", "", true),
+            ("*", "anything", true),
+            ("abc", "abc", true),
+            ("abc", "abcd", false),
+            ("a?c", "abc", true),
+            ("a?c", "ac", false),
+            ("*.txt", "notes.txt", true),
+            ("*.txt", "notes.txt.bak", false),
+            ("*game*", "my game - level 2", true),
+            ("a*b*c", "aXbYbZc", true),
+            ("a*b*c", "aXbYbZ", false),
+            ("**", "x", true),
+            ("*?", "", false),
+        ];
+        for (pattern, text, expected) in cases {
+            assert_eq!(wildcard_match(pattern, text), expected, "{:?} vs {:?}", pattern, text);
+        }
+    }
+
+    #[test]
+    fn match_rule_table() {
+        let game = window("Game - Level 2", "game.exe", "UnityWndClass");
+        let cases = [
+            (rule("game.exe", None, None), true),
+            // Exe and class compare case-insensitively and ignore surrounding spaces
+            (rule(" GAME.EXE ", Some(" unitywndclass "), None), true),
+            (rule("other.exe", None, None), false),
+            (rule("game.exe", Some("OtherClass"), None), false),
+            // Blank class or pattern means "any"
+            (rule("game.exe", Some(""), Some("  ")), true),
+            (rule("game.exe", None, Some("game - *")), true),
+            (rule("game.exe", None, Some("*menu*")), false),
+        ];
+        for (i, (rule, expected)) in cases.iter().enumerate() {
+            assert_eq!(rule.matches(&game), *expected, "case {}", i);
+        }
+    }
+
+    #[test]
+    fn pick_window_needs_exactly_one_match() {
+        let windows = || {
+            vec![
+                window("Editor", "code.exe", "Chrome_WidgetWin_1"),
+                window("Terminal", "code.exe", "Chrome_WidgetWin_1"),
+                window("Game", "game.exe", "UnityWndClass"),
+            ]
+        };
+        assert_eq!(pick_window(&rule("game.exe", None, None), windows()).unwrap().title, "Game");
+        assert!(matches!(
+            pick_window(&rule("missing.exe", None, None), windows()),
+            Err(WindowMatchError::NoMatch { .. })
+        ));
+        match pick_window(&rule("code.exe", None, None), windows()) {
+            Err(WindowMatchError::Ambiguous { titles, .. }) => assert_eq!(titles, ["Editor", "Terminal"]),
+            other => panic!("expected ambiguous match, got {:?}", other.map(|w| w.title)),
+        }
+        assert_eq!(pick_window(&rule("code.exe", None, Some("term*")), windows()).unwrap().title, "Terminal");
+    }
+}
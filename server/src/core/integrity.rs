@@ -0,0 +1,49 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::Path;
+
+pub const HASH_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Outcome of the last verification, stored in `recordings.integrity_state`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityState {
+    /// The file still hashes to the value taken when it was finalized.
+    Ok,
+    Mismatch,
+    /// Neither the local file nor the archived object could be found.
+    Missing,
+}
+
+impl IntegrityState {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ok => "ok",
+            Self::Mismatch => "mismatch",
+            Self::Missing => "missing",
+        }
+    }
+}
+
+/// Lowercase hex SHA-256 of a file, read in chunks so large recordings don't sit in memory.
+pub fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0u8; HASH_CHUNK_SIZE];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// `Digest` header value (RFC 3230) for a stored hex hash, or None if it isn't a SHA-256.
+pub fn digest_header(sha256_hex: &str) -> Option<String> {
+    let bytes = hex::decode(sha256_hex).ok().filter(|b| b.len() == 32)?;
+    Some(format!("sha-256={}", base64::engine::general_purpose::STANDARD.encode(bytes)))
+}
@@ -0,0 +1,186 @@
+use serde::{Deserialize, Serialize};
+
+/// Upper bound on worker tasks; `PipelineConfig::workers` picks how many of them take jobs.
+pub const MAX_WORKERS: usize = 4;
+/// Only the tail of a job's output is kept.
+pub const MAX_LOG_BYTES: usize = 64 * 1024;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// Rewrite the container via an external command, replacing the original.
+    Remux,
+    /// Re-encode via an external command, replacing the original.
+    Transcode,
+    /// Grab a still via an external command into the thumbnail folder.
+    Thumbnail,
+    /// Hash the file and compare it against the hash stored when it was finalized.
+    Checksum,
+    /// Archive to the configured storage backend.
+    Upload,
+    /// Re-hash every recording; queued on its own by an admin, never part of a pipeline.
+    Verify,
+}
+
+impl JobKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Remux => "remux",
+            Self::Transcode => "transcode",
+            Self::Thumbnail => "thumbnail",
+            Self::Checksum => "checksum",
+            Self::Upload => "upload",
+            Self::Verify => "verify",
+        }
+    }
+
+    /// Kinds that hand the file to a configured program.
+    pub fn runs_command(self) -> bool {
+        matches!(self, Self::Remux | Self::Transcode | Self::Thumbnail)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Cancelled => "cancelled",
+        }
+    }
+}
+
+/// One step of the post-stop pipeline, copied into the job's `params` when queued so
+/// later config edits don't change jobs already waiting.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PipelineStep {
+    pub kind: JobKind,
+    /// Program to run for remux/transcode/thumbnail, e.g. `ffmpeg`. Not run through a shell.
+    #[serde(default)]
+    pub command: String,
+    /// Arguments; `{input}`, `{output}`, `{dir}`, `{stem}` and `{duration}` are substituted.
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extension of the produced file. Defaults to the input's for remux/transcode and `jpg` for thumbnails.
+    #[serde(default)]
+    pub output_ext: Option<String>,
+    #[serde(default = "default_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+fn default_timeout_secs() -> u64 {
+    3600
+}
+
+impl PipelineStep {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.kind == JobKind::Verify {
+            return Err("verify runs across all recordings and can't be a pipeline step".to_string());
+        }
+        if self.kind.runs_command() {
+            if self.command.trim().is_empty() {
+                return Err(format!("{} step needs a command", self.kind.as_str()));
+            }
+            if !self.args.iter().any(|a| a.contains("{input}")) || !self.args.iter().any(|a| a.contains("{output}")) {
+                return Err(format!("{} step arguments must use {{input}} and {{output}}", self.kind.as_str()));
+            }
+        }
+        if let Some(ext) = self.output_ext.as_deref() {
+            if ext.is_empty() || !ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+                return Err("output_ext must be alphanumeric, without the dot".to_string());
+            }
+        }
+        if !(1..=24 * 3600).contains(&self.timeout_secs) {
+            return Err("timeout_secs must be between 1 and 86400".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Steps queued for every recording when it stops, stored under `post_stop_pipeline`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PipelineConfig {
+    #[serde(default)]
+    pub steps: Vec<PipelineStep>,
+    #[serde(default = "default_max_attempts")]
+    pub max_attempts: i32,
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+}
+
+fn default_max_attempts() -> i32 {
+    3
+}
+
+fn default_workers() -> usize {
+    1
+}
+
+impl Default for PipelineConfig {
+    fn default() -> Self {
+        Self {
+            steps: Vec::new(),
+            max_attempts: default_max_attempts(),
+            workers: default_workers(),
+        }
+    }
+}
+
+/// Substitute `{name}` placeholders in each argument.
+pub fn expand_args(args: &[String], vars: &[(&str, &str)]) -> Vec<String> {
+    args.iter()
+        .map(|arg| {
+            vars.iter()
+                .fold(arg.clone(), |acc, (name, value)| acc.replace(&format!("{{{}}}", name), value))
+        })
+        .collect()
+}
+
+/// Back-off before retry `attempt` (1-based): 30s, 60s, 120s, ... capped at an hour.
+pub fn retry_delay(attempt: i32) -> chrono::Duration {
+    let exp = attempt.clamp(1, 8) - 1;
+    chrono::Duration::seconds((30i64 << exp).min(3600))
+}
+
+/// Read progress from ffmpeg's `-progress` output (`out_time_us=`, `out_time_ms=`, both
+/// microseconds) or a plain `progress=<0..1>` line, as a fraction of `duration_secs`.
+pub fn parse_progress(line: &str, duration_secs: Option<f64>) -> Option<f32> {
+    let (key, value) = line.trim().split_once('=')?;
+    match key {
+        "out_time_us" | "out_time_ms" => {
+            let micros: f64 = value.trim().parse().ok()?;
+            let duration = duration_secs.filter(|d| *d > 0.0)?;
+            Some(((micros / 1_000_000.0) / duration).clamp(0.0, 1.0) as f32)
+        }
+        "progress" => match value.trim() {
+            "end" => Some(1.0),
+            v => v.parse::<f32>().ok().map(|p| p.clamp(0.0, 1.0)),
+        },
+        _ => None,
+    }
+}
+
+/// Append a line, dropping the oldest output once the log passes `MAX_LOG_BYTES`.
+pub fn append_log(log: &mut String, line: &str) {
+    log.push_str(line);
+    log.push('\n');
+    if log.len() > MAX_LOG_BYTES {
+        let mut cut = log.len() - MAX_LOG_BYTES;
+        while !log.is_char_boundary(cut) {
+            cut += 1;
+        }
+        log.drain(..cut);
+    }
+}
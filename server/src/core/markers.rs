@@ -0,0 +1,210 @@
+use std::fmt::Write;
+
+pub const MAX_LABEL_LEN: usize = 200;
+/// How much of the recording a marker clip takes before and after the marker by default.
+pub const DEFAULT_CLIP_BEFORE_SECS: f64 = 30.0;
+pub const DEFAULT_CLIP_AFTER_SECS: f64 = 30.0;
+
+/// Labels end up in subtitle and metadata files, so keep them to a single line.
+pub fn validate_label(label: &str) -> Result<(), &'static str> {
+    if label.chars().count() > MAX_LABEL_LEN {
+        return Err("Label is too long (max 200 characters)");
+    }
+    if label.chars().any(char::is_control) {
+        return Err("Label must be a single line without control characters");
+    }
+    Ok(())
+}
+
+/// Sidecar formats markers can be exported as.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChapterFormat {
+    WebVtt,
+    FfMetadata,
+}
+
+impl ChapterFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "vtt" | "webvtt" => Some(Self::WebVtt),
+            "ffmetadata" => Some(Self::FfMetadata),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::WebVtt => "vtt",
+            Self::FfMetadata => "ffmetadata",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::WebVtt => "text/vtt; charset=utf-8",
+            Self::FfMetadata => "text/plain; charset=utf-8",
+        }
+    }
+}
+
+/// One chapter per marker, running until the next marker or the end of the recording.
+#[derive(Debug, PartialEq)]
+pub struct Chapter {
+    pub start_secs: f64,
+    pub end_secs: f64,
+    pub title: String,
+}
+
+/// Turn markers (offset, label) into chapters. Markers past the end are pulled back to it;
+/// without a known duration the last chapter lasts one second.
+pub fn chapters(markers: &[(f64, Option<String>)], duration_secs: Option<f64>) -> Vec<Chapter> {
+    let mut sorted: Vec<&(f64, Option<String>)> = markers.iter().collect();
+    sorted.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let starts: Vec<f64> = sorted
+        .iter()
+        .map(|(offset, _)| {
+            let offset = offset.max(0.0);
+            duration_secs.map_or(offset, |d| offset.min(d))
+        })
+        .collect();
+    sorted
+        .iter()
+        .enumerate()
+        .map(|(i, (_, label))| {
+            let start = starts[i];
+            let end = match starts.get(i + 1) {
+                Some(next) => *next,
+                None => duration_secs.unwrap_or(start + 1.0),
+            };
+            Chapter {
+                start_secs: start,
+                // Players drop zero-length cues, so every chapter gets at least a millisecond
+                end_secs: end.max(start + 0.001),
+                title: label.clone().unwrap_or_else(|| format!("Marker {}", i + 1)),
+            }
+        })
+        .collect()
+}
+
+pub fn render(format: ChapterFormat, chapters: &[Chapter], title: &str) -> String {
+    match format {
+        ChapterFormat::WebVtt => to_webvtt(chapters),
+        ChapterFormat::FfMetadata => to_ffmetadata(chapters, title),
+    }
+}
+
+/// WebVTT chapter track, one cue per chapter.
+pub fn to_webvtt(chapters: &[Chapter]) -> String {
+    let mut out = String::from("WEBVTT\n");
+    for (i, chapter) in chapters.iter().enumerate() {
+        let _ = write!(
+            out,
+            "\n{}\n{} --> {}\n{}\n",
+            i + 1,
+            vtt_timestamp(chapter.start_secs),
+            vtt_timestamp(chapter.end_secs),
+            escape_vtt(&chapter.title)
+        );
+    }
+    out
+}
+
+/// FFmpeg metadata file with millisecond chapters, for `ffmpeg -i video -i chapters -map_metadata 1`.
+pub fn to_ffmetadata(chapters: &[Chapter], title: &str) -> String {
+    let mut out = String::from(";FFMETADATA1\n");
+    let _ = writeln!(out, "title={}", escape_ffmetadata(title));
+    for chapter in chapters {
+        let _ = write!(
+            out,
+            "\n[CHAPTER]\nTIMEBASE=1/1000\nSTART={}\nEND={}\ntitle={}\n",
+            millis(chapter.start_secs),
+            millis(chapter.end_secs),
+            escape_ffmetadata(&chapter.title)
+        );
+    }
+    out
+}
+
+fn millis(secs: f64) -> u64 {
+    (secs.max(0.0) * 1000.0).round() as u64
+}
+
+fn vtt_timestamp(secs: f64) -> String {
+    let ms = millis(secs);
+    format!("{:02}:{:02}:{:02}.{:03}", ms / 3_600_000, ms / 60_000 % 60, ms / 1000 % 60, ms % 1000)
+}
+
+fn escape_vtt(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+fn escape_ffmetadata(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '=' | ';' | '#' | '\\' | '\n') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chapter(start_secs: f64, end_secs: f64, title: &str) -> Chapter {
+        Chapter { start_secs, end_secs, title: title.to_string() }
+    }
+
+    #[test]
+    fn chapters_table() {
+        let cases = [
+            (vec![], Some(60.0), vec![]),
+            // Sorted by offset; negative offsets clamp to the start, late ones to the end
+            (
+                vec![(30.0, Some("b".to_string())), (-5.0, None), (500.0, Some("late".to_string()))],
+                Some(100.0),
+                vec![chapter(0.0, 30.0, "Marker 1"), chapter(30.0, 100.0, "b"), chapter(100.0, 100.0 + 0.001, "late")],
+            ),
+            // Without a duration the last chapter lasts a second
+            (vec![(12.5, None)], None, vec![chapter(12.5, 13.5, "Marker 1")]),
+            // Markers at the same offset still get distinct, non-empty cues
+            (
+                vec![(10.0, Some("a".to_string())), (10.0, Some("b".to_string()))],
+                Some(20.0),
+                vec![chapter(10.0, 10.0 + 0.001, "a"), chapter(10.0, 20.0, "b")],
+            ),
+        ];
+        for (i, (markers, duration, expected)) in cases.iter().enumerate() {
+            assert_eq!(&chapters(markers, *duration), expected, "case {}", i);
+        }
+    }
+
+    #[test]
+    fn webvtt_escapes_titles_and_formats_long_times() {
+        let out = to_webvtt(&[chapter(0.0, 61.5, "Intro <b> & more"), chapter(3725.0042, 3726.0, "End")]);
+        assert_eq!(
+            out,
+            "WEBVTT\n\n1\n00:00:00.000 --> 00:01:01.500\nIntro &lt;b&gt; &amp; more\n\n2\n01:02:05.004 --> 01:02:06.000\nEnd\n"
+        );
+    }
+
+    #[test]
+    fn ffmetadata_escapes_special_characters() {
+        let out = to_ffmetadata(&[chapter(1.2345, 2.0, "a=b;c#d\\e")], "my;title");
+        assert_eq!(
+            out,
+            ";FFMETADATA1\ntitle=my\\;title\n\n[CHAPTER]\nTIMEBASE=1/1000\nSTART=1235\nEND=2000\ntitle=a\\=b\\;c\\#d\\\\e\n"
+        );
+    }
+
+    #[test]
+    fn labels_are_single_short_lines() {
+        assert!(validate_label("Boss fight").is_ok());
+        assert!(validate_label(&"x".repeat(MAX_LABEL_LEN)).is_ok());
+        assert!(validate_label(&"x".repeat(MAX_LABEL_LEN + 1)).is_err());
+        assert!(validate_label("two\nlines").is_err());
+    }
+}
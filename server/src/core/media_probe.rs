@@ -0,0 +1,485 @@
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use serde::Serialize;
+
+// moov is normally a few hundred KB; anything past this is not a file we wrote
+pub(crate) const MAX_MOOV_SIZE: u64 = 64 * 1024 * 1024;
+const MAX_EBML_ELEMENT: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct MediaInfo {
+    pub container: String,
+    pub size_bytes: i64,
+    pub duration_secs: Option<f64>,
+    pub video_codec: Option<String>,
+    pub width: Option<i32>,
+    pub height: Option<i32>,
+    pub frame_rate: Option<f64>,
+    pub audio_tracks: i32,
+    /// False when the container's index is missing, e.g. an MP4 without `moov`
+    /// because cli-capture was killed before it could finalise the file.
+    pub complete: bool,
+}
+
+/// Read container metadata from an MP4/MOV or Matroska file.
+pub fn probe_file(path: &Path) -> anyhow::Result<MediaInfo> {
+    let mut file = std::fs::File::open(path)?;
+    let size = file.metadata()?.len();
+
+    let mut magic = [0u8; 8];
+    let read = file.read(&mut magic)?;
+    file.seek(SeekFrom::Start(0))?;
+
+    let mut info = if read >= 4 && magic[..4] == [0x1A, 0x45, 0xDF, 0xA3] {
+        probe_mkv(&mut file, size)?
+    } else if read >= 8 {
+        probe_mp4(&mut file, size)?
+    } else {
+        MediaInfo { container: "unknown".to_string(), ..Default::default() }
+    };
+    info.size_bytes = size as i64;
+    Ok(info)
+}
+
+// ---------- MP4 ----------
+
+pub(crate) struct BoxHeader {
+    pub kind: [u8; 4],
+    pub header_len: u64,
+    pub size: u64,
+}
+
+pub(crate) fn read_box_header<R: Read + Seek>(r: &mut R, remaining: u64) -> std::io::Result<Option<BoxHeader>> {
+    if remaining < 8 {
+        return Ok(None);
+    }
+    let mut buf = [0u8; 8];
+    r.read_exact(&mut buf)?;
+    let mut size = u32::from_be_bytes([buf[0], buf[1], buf[2], buf[3]]) as u64;
+    let kind = [buf[4], buf[5], buf[6], buf[7]];
+    let mut header_len = 8;
+    if size == 1 {
+        let mut large = [0u8; 8];
+        r.read_exact(&mut large)?;
+        size = u64::from_be_bytes(large);
+        header_len = 16;
+    } else if size == 0 {
+        size = remaining;
+    }
+    if size < header_len {
+        return Ok(None);
+    }
+    Ok(Some(BoxHeader { kind, header_len, size: size.min(remaining) }))
+}
+
+fn probe_mp4<R: Read + Seek>(r: &mut R, len: u64) -> anyhow::Result<MediaInfo> {
+    let mut info = MediaInfo { container: "mp4".to_string(), ..Default::default() };
+    let mut pos = 0;
+    let mut moov = None;
+
+    while let Some(header) = read_box_header(r, len - pos)? {
+        if &header.kind == b"moov" && header.size - header.header_len <= MAX_MOOV_SIZE {
+            let mut body = vec![0u8; (header.size - header.header_len) as usize];
+            r.read_exact(&mut body)?;
+            moov = Some(body);
+            break;
+        }
+        pos += header.size;
+        r.seek(SeekFrom::Start(pos))?;
+    }
+
+    if let Some(moov) = moov {
+        info.complete = true;
+        parse_moov(&moov, &mut info);
+    }
+    Ok(info)
+}
+
+/// Iterate the child boxes in an in-memory box body.
+pub(crate) fn children(data: &[u8]) -> impl Iterator<Item = ([u8; 4], &[u8])> {
+    let mut pos = 0usize;
+    std::iter::from_fn(move || {
+        if pos + 8 > data.len() {
+            return None;
+        }
+        let mut size = be_u32(data, pos) as usize;
+        let kind = [data[pos + 4], data[pos + 5], data[pos + 6], data[pos + 7]];
+        let mut header = 8;
+        if size == 1 {
+            size = usize::try_from(be_u64(data, pos + 8)?).ok()?;
+            header = 16;
+        } else if size == 0 {
+            size = data.len() - pos;
+        }
+        // Sizes come from the file, so a corrupt largesize must not overflow
+        let end = pos.checked_add(size).filter(|e| *e <= data.len())?;
+        if size < header {
+            return None;
+        }
+        let body = &data[pos + header..end];
+        pos = end;
+        Some((kind, body))
+    })
+}
+
+pub(crate) fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Option<&'a [u8]> {
+    children(data).find(|(k, _)| k == kind).map(|(_, body)| body)
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    data.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+pub(crate) fn be_u32(data: &[u8], at: usize) -> u32 {
+    data.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]])).unwrap_or(0)
+}
+
+pub(crate) fn be_u64(data: &[u8], at: usize) -> Option<u64> {
+    data.get(at..at + 8).map(|b| u64::from_be_bytes([b[0], b[1], b[2], b[3], b[4], b[5], b[6], b[7]]))
+}
+
+/// (timescale, duration) from an mvhd or mdhd full box.
+pub(crate) fn header_timing(body: &[u8]) -> Option<(u32, u64)> {
+    match body.first()? {
+        1 => Some((be_u32(body, 20), be_u64(body, 24)?)),
+        _ => Some((be_u32(body, 12), be_u32(body, 16) as u64)),
+    }
+}
+
+fn seconds(timescale: u32, duration: u64) -> Option<f64> {
+    if timescale == 0 || duration == 0 || duration == u32::MAX as u64 || duration == u64::MAX {
+        return None;
+    }
+    Some(duration as f64 / timescale as f64)
+}
+
+fn parse_moov(moov: &[u8], info: &mut MediaInfo) {
+    info.duration_secs = child(moov, b"mvhd").and_then(header_timing).and_then(|(ts, d)| seconds(ts, d));
+
+    for (kind, trak) in children(moov) {
+        if &kind != b"trak" {
+            continue;
+        }
+        let mdia = match child(trak, b"mdia") {
+            Some(m) => m,
+            None => continue,
+        };
+        // hdlr: version/flags(4) pre_defined(4) handler_type(4)
+        let handler = child(mdia, b"hdlr").and_then(|h| h.get(8..12)).unwrap_or_default();
+        let media_secs = child(mdia, b"mdhd").and_then(header_timing).and_then(|(ts, d)| seconds(ts, d));
+        let stbl = child(mdia, b"minf").and_then(|m| child(m, b"stbl"));
+        // stsd: version/flags(4) entry_count(4) then sample entries
+        let entry = stbl.and_then(|s| child(s, b"stsd")).and_then(|s| s.get(8..));
+        let format = entry.and_then(|e| e.get(4..8));
+
+        match handler {
+            b"vide" if info.video_codec.is_none() => {
+                info.video_codec = format.map(codec_from_fourcc);
+                // Visual sample entry: header(8) reserved(6) dref(2) pre_defined/reserved(16) width height
+                if let Some(e) = entry {
+                    info.width = be_u16(e, 32).map(|v| v as i32).filter(|v| *v > 0);
+                    info.height = be_u16(e, 34).map(|v| v as i32).filter(|v| *v > 0);
+                }
+                // stts: version/flags(4) entry_count(4) then (sample_count, delta) pairs
+                let samples: u64 = stbl
+                    .and_then(|s| child(s, b"stts"))
+                    .map(|stts| {
+                        let count = be_u32(stts, 4) as usize;
+                        (0..count).map(|i| be_u32(stts, 8 + i * 8) as u64).sum()
+                    })
+                    .unwrap_or(0);
+                if let Some(secs) = media_secs.filter(|_| samples > 0) {
+                    info.frame_rate = Some((samples as f64 / secs * 100.0).round() / 100.0);
+                }
+            }
+            b"soun" => info.audio_tracks += 1,
+            _ => {}
+        }
+    }
+}
+
+fn codec_from_fourcc(fourcc: &[u8]) -> String {
+    match fourcc {
+        b"avc1" | b"avc3" => "h264".to_string(),
+        b"hev1" | b"hvc1" => "hevc".to_string(),
+        b"av01" => "av1".to_string(),
+        b"vp09" => "vp9".to_string(),
+        other => String::from_utf8_lossy(other).trim().to_string(),
+    }
+}
+
+// ---------- Matroska ----------
+
+const EBML_SEGMENT: u64 = 0x1853_8067;
+const EBML_INFO: u64 = 0x1549_A966;
+const EBML_TIMECODE_SCALE: u64 = 0x2A_D7B1;
+const EBML_DURATION: u64 = 0x4489;
+const EBML_TRACKS: u64 = 0x1654_AE6B;
+const EBML_TRACK_ENTRY: u64 = 0xAE;
+const EBML_TRACK_TYPE: u64 = 0x83;
+const EBML_CODEC_ID: u64 = 0x86;
+const EBML_DEFAULT_DURATION: u64 = 0x23_E383;
+const EBML_VIDEO: u64 = 0xE0;
+const EBML_PIXEL_WIDTH: u64 = 0xB0;
+const EBML_PIXEL_HEIGHT: u64 = 0xBA;
+const EBML_CLUSTER: u64 = 0x1F43_B675;
+
+/// Read an EBML variable-length integer. IDs keep their marker bit; sizes drop it.
+/// Returns (value, length, all_ones) where all_ones marks an unknown size.
+fn read_vint<R: Read>(r: &mut R, keep_marker: bool) -> std::io::Result<Option<(u64, u64, bool)>> {
+    let mut first = [0u8; 1];
+    if r.read(&mut first)? == 0 {
+        return Ok(None);
+    }
+    let len = first[0].leading_zeros() as u64 + 1;
+    if len > 8 {
+        return Ok(None);
+    }
+    let marker = 0x80u8 >> (len - 1);
+    let mut value = if keep_marker { first[0] as u64 } else { (first[0] & !marker) as u64 };
+    for _ in 1..len {
+        let mut b = [0u8; 1];
+        r.read_exact(&mut b)?;
+        value = (value << 8) | b[0] as u64;
+    }
+    let all_ones = !keep_marker && value == (1u64 << (7 * len)) - 1;
+    Ok(Some((value, len, all_ones)))
+}
+
+fn read_element_header<R: Read>(r: &mut R) -> std::io::Result<Option<(u64, u64, Option<u64>)>> {
+    let (id, id_len, _) = match read_vint(r, true)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    let (size, size_len, unknown) = match read_vint(r, false)? {
+        Some(v) => v,
+        None => return Ok(None),
+    };
+    Ok(Some((id, id_len + size_len, if unknown { None } else { Some(size) })))
+}
+
+/// Walk the elements of a master element body held in memory.
+fn ebml_children(data: &[u8]) -> Vec<(u64, &[u8])> {
+    let mut out = Vec::new();
+    let mut cursor = std::io::Cursor::new(data);
+    while let Ok(Some((id, _, size))) = read_element_header(&mut cursor) {
+        let start = cursor.position() as usize;
+        let end = size.map(|s| start.saturating_add(s as usize)).unwrap_or(data.len()).min(data.len());
+        out.push((id, &data[start..end]));
+        cursor.set_position(end as u64);
+    }
+    out
+}
+
+fn ebml_uint(data: &[u8]) -> u64 {
+    data.iter().take(8).fold(0, |acc, b| (acc << 8) | *b as u64)
+}
+
+fn ebml_float(data: &[u8]) -> Option<f64> {
+    match data.len() {
+        4 => Some(f32::from_be_bytes([data[0], data[1], data[2], data[3]]) as f64),
+        8 => Some(f64::from_be_bytes([data[0], data[1], data[2], data[3], data[4], data[5], data[6], data[7]])),
+        _ => None,
+    }
+}
+
+fn probe_mkv<R: Read + Seek>(r: &mut R, len: u64) -> anyhow::Result<MediaInfo> {
+    let mut info = MediaInfo { container: "mkv".to_string(), ..Default::default() };
+
+    // Skip the EBML header, then enter the Segment
+    let (_, header_len, size) = read_element_header(r)?.ok_or_else(|| anyhow::anyhow!("Truncated EBML header"))?;
+    let mut pos = header_len + size.unwrap_or(0);
+    r.seek(SeekFrom::Start(pos))?;
+    let segment_end = match read_element_header(r)? {
+        Some((EBML_SEGMENT, hl, size)) => {
+            pos += hl;
+            size.map(|s| pos + s).unwrap_or(len).min(len)
+        }
+        _ => return Ok(info),
+    };
+
+    let mut timecode_scale = 1_000_000u64;
+    let mut raw_duration = None;
+    let (mut have_info, mut have_tracks) = (false, false);
+
+    while pos < segment_end && !(have_info && have_tracks) {
+        r.seek(SeekFrom::Start(pos))?;
+        let (id, hl, size) = match read_element_header(r)? {
+            Some(v) => v,
+            None => break,
+        };
+        // Clusters carry the media itself; everything we need comes before them
+        if id == EBML_CLUSTER || size.is_none() {
+            break;
+        }
+        let size = size.unwrap_or(0);
+        if (id == EBML_INFO || id == EBML_TRACKS) && size <= MAX_EBML_ELEMENT {
+            let mut body = vec![0u8; size as usize];
+            r.read_exact(&mut body)?;
+            if id == EBML_INFO {
+                have_info = true;
+                for (cid, data) in ebml_children(&body) {
+                    match cid {
+                        EBML_TIMECODE_SCALE => timecode_scale = ebml_uint(data),
+                        EBML_DURATION => raw_duration = ebml_float(data),
+                        _ => {}
+                    }
+                }
+            } else {
+                have_tracks = true;
+                parse_mkv_tracks(&body, &mut info);
+            }
+        }
+        pos += hl + size;
+    }
+
+    info.duration_secs = raw_duration
+        .map(|d| d * timecode_scale as f64 / 1_000_000_000.0)
+        .filter(|d| *d > 0.0);
+    // Matroska is written progressively; Duration is only filled in when the muxer finalises
+    info.complete = have_info && have_tracks && info.duration_secs.is_some();
+    Ok(info)
+}
+
+fn parse_mkv_tracks(body: &[u8], info: &mut MediaInfo) {
+    for (id, entry) in ebml_children(body) {
+        if id != EBML_TRACK_ENTRY {
+            continue;
+        }
+        let fields = ebml_children(entry);
+        let track_type = fields.iter().find(|(i, _)| *i == EBML_TRACK_TYPE).map(|(_, d)| ebml_uint(d));
+        match track_type {
+            Some(1) if info.video_codec.is_none() => {
+                info.video_codec = fields
+                    .iter()
+                    .find(|(i, _)| *i == EBML_CODEC_ID)
+                    .map(|(_, d)| codec_from_mkv_id(&String::from_utf8_lossy(d)));
+                if let Some((_, ns)) = fields.iter().find(|(i, _)| *i == EBML_DEFAULT_DURATION) {
+                    let ns = ebml_uint(ns);
+                    if ns > 0 {
+                        info.frame_rate = Some((1_000_000_000.0 / ns as f64 * 100.0).round() / 100.0);
+                    }
+                }
+                if let Some((_, video)) = fields.iter().find(|(i, _)| *i == EBML_VIDEO) {
+                    for (vid, data) in ebml_children(video) {
+                        match vid {
+                            EBML_PIXEL_WIDTH => info.width = Some(ebml_uint(data) as i32),
+                            EBML_PIXEL_HEIGHT => info.height = Some(ebml_uint(data) as i32),
+                            _ => {}
+                        }
+                    }
+                }
+            }
+            Some(2) => info.audio_tracks += 1,
+            _ => {}
+        }
+    }
+}
+
+fn codec_from_mkv_id(id: &str) -> String {
+    let id = id.trim_end_matches('\0');
+    match id {
+        "V_MPEG4/ISO/AVC" => "h264".to_string(),
+        "V_MPEGH/ISO/HEVC" => "hevc".to_string(),
+        "V_AV1" => "av1".to_string(),
+        "V_VP9" => "vp9".to_string(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mp4_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+        let mut out = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn kinds(data: &[u8]) -> Vec<[u8; 4]> {
+        children(data).map(|(kind, _)| kind).collect()
+    }
+
+    #[test]
+    fn walks_sibling_boxes() {
+        let mut data = mp4_box(b"ftyp", b"isom");
+        data.extend(mp4_box(b"free", &[]));
+        data.extend(mp4_box(b"mdat", &[1, 2, 3]));
+        assert_eq!(kinds(&data), vec![*b"ftyp", *b"free", *b"mdat"]);
+        assert_eq!(child(&data, b"mdat"), Some(&[1u8, 2, 3][..]));
+        assert_eq!(child(&data, b"moov"), None);
+    }
+
+    #[test]
+    fn stops_at_truncated_box() {
+        let mut data = mp4_box(b"ftyp", b"isom");
+        let mut cut = mp4_box(b"mdat", &[0; 16]);
+        cut.truncate(12);
+        data.extend(cut);
+        assert_eq!(kinds(&data), vec![*b"ftyp"]);
+        // A header shorter than 8 bytes is ignored too
+        assert_eq!(kinds(&data[..6]), Vec::<[u8; 4]>::new());
+    }
+
+    #[test]
+    fn rejects_sizes_smaller_than_the_header() {
+        let mut data = 4u32.to_be_bytes().to_vec();
+        data.extend_from_slice(b"free");
+        assert_eq!(kinds(&data), Vec::<[u8; 4]>::new());
+    }
+
+    #[test]
+    fn size_zero_runs_to_the_end() {
+        let mut data = mp4_box(b"ftyp", b"isom");
+        data.extend_from_slice(&0u32.to_be_bytes());
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&[9; 5]);
+        assert_eq!(child(&data, b"mdat"), Some(&[9u8; 5][..]));
+    }
+
+    #[test]
+    fn large_size() {
+        let mut data = 1u32.to_be_bytes().to_vec();
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&20u64.to_be_bytes());
+        data.extend_from_slice(&[7; 4]);
+        assert_eq!(child(&data, b"mdat"), Some(&[7u8; 4][..]));
+    }
+
+    #[test]
+    fn huge_large_size_does_not_overflow() {
+        let mut data = mp4_box(b"ftyp", b"isom");
+        data.extend_from_slice(&1u32.to_be_bytes());
+        data.extend_from_slice(b"mdat");
+        data.extend_from_slice(&u64::MAX.to_be_bytes());
+        data.extend_from_slice(&[0; 8]);
+        assert_eq!(kinds(&data), vec![*b"ftyp"]);
+        // Largesize header cut short
+        let mut short = 1u32.to_be_bytes().to_vec();
+        short.extend_from_slice(b"mdat");
+        short.extend_from_slice(&[0; 4]);
+        assert_eq!(kinds(&short), Vec::<[u8; 4]>::new());
+    }
+
+    #[test]
+    fn finds_nested_boxes() {
+        let hdlr = mp4_box(b"hdlr", b"\0\0\0\0\0\0\0\0vide");
+        let mdia = mp4_box(b"mdia", &hdlr);
+        let trak = mp4_box(b"trak", &mdia);
+        let mut moov_body = mp4_box(b"mvhd", &[0; 20]);
+        moov_body.extend(&trak);
+        moov_body.extend(&trak);
+        let mut file = mp4_box(b"ftyp", b"isom");
+        file.extend(mp4_box(b"moov", &moov_body));
+
+        let moov = child(&file, b"moov").unwrap();
+        assert_eq!(kinds(moov), vec![*b"mvhd", *b"trak", *b"trak"]);
+        let handler = child(moov, b"trak")
+            .and_then(|t| child(t, b"mdia"))
+            .and_then(|m| child(m, b"hdlr"))
+            .and_then(|h| h.get(8..12));
+        assert_eq!(handler, Some(&b"vide"[..]));
+    }
+}
@@ -1,5 +1,28 @@
 pub mod auth;
 pub mod hardware;
+pub mod encoders;
 pub mod recorder;
+pub mod scan_job;
+pub mod preflight;
+pub mod resolution;
+pub mod download;
+pub mod media_probe;
+pub mod recording_dir;
+pub mod retention;
+pub mod qr;
+pub mod zip_stream;
+pub mod file_query;
+pub mod path_template;
+pub mod storage;
+pub mod s3;
+pub mod jobs;
+pub mod mp4_edit;
+pub mod integrity;
+pub mod upload;
+pub mod recording_state;
+pub mod markers;
+pub mod share_session;
+// The agent only runs as the elevated half of the Windows service
+#[cfg(windows)]
 pub mod agent;
 pub mod agent_client;
@@ -0,0 +1,996 @@
+use std::io::{BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::core::media_probe::{be_u32, be_u64, child, children, header_timing, read_box_header, MAX_MOOV_SIZE};
+
+// Guards against sample tables that would need absurd amounts of memory to expand
+const MAX_SAMPLES: usize = 50_000_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ClipRange {
+    /// Where the clip actually starts, snapped back to the previous keyframe.
+    pub start_secs: f64,
+    pub end_secs: f64,
+}
+
+#[derive(Clone, Copy)]
+struct Sample {
+    /// Index of the input file the sample is read from.
+    source: usize,
+    offset: u64,
+    size: u32,
+    dts: u64,
+    duration: u32,
+    cts_offset: i64,
+    sync: bool,
+    description: u32,
+}
+
+struct Track {
+    handler: [u8; 4],
+    timescale: u32,
+    /// Raw stsd body; inputs can only be joined when these match exactly.
+    stsd: Vec<u8>,
+    samples: Vec<Sample>,
+    has_ctts: bool,
+    has_stss: bool,
+    /// media_time of the first non-empty edit, so B-frame delay survives the cut.
+    edit_media_time: Option<i64>,
+}
+
+impl Track {
+    fn duration(&self) -> u64 {
+        self.samples.last().map(|s| s.dts + s.duration as u64).unwrap_or(0)
+    }
+}
+
+/// A parsed input file.
+struct Source {
+    file: std::fs::File,
+    ftyp: Option<Vec<u8>>,
+    moov: Vec<u8>,
+    movie_timescale: u32,
+    tracks: Vec<Track>,
+}
+
+/// The samples of one track that go into the output.
+struct Selection {
+    timescale: u32,
+    has_ctts: bool,
+    has_stss: bool,
+    edit_media_time: Option<i64>,
+    samples: Vec<Sample>,
+    /// Absolute offset of each sample in the output file.
+    offsets: Vec<u64>,
+}
+
+impl Selection {
+    fn new(track: &Track, samples: Vec<Sample>) -> Self {
+        Self {
+            timescale: track.timescale,
+            has_ctts: track.has_ctts,
+            has_stss: track.has_stss,
+            edit_media_time: track.edit_media_time,
+            samples,
+            offsets: Vec::new(),
+        }
+    }
+
+    fn media_duration(&self) -> u64 {
+        self.samples.iter().map(|s| s.duration as u64).sum()
+    }
+}
+
+/// Copy `start_secs..end_secs` of an MP4 into `output` without re-encoding. The start is
+/// moved back to the keyframe at or before it, since nothing before a keyframe decodes.
+pub fn clip_mp4(input: &Path, output: &Path, start_secs: f64, end_secs: f64) -> anyhow::Result<ClipRange> {
+    if !(start_secs >= 0.0 && end_secs > start_secs) {
+        anyhow::bail!("End must be after start");
+    }
+
+    let mut source = open_source(input, 0)?;
+    // Cut on the first video track's keyframes; audio follows by timestamp
+    let reference = source
+        .tracks
+        .iter()
+        .position(|t| &t.handler == b"vide")
+        .or(if source.tracks.is_empty() { None } else { Some(0) })
+        .ok_or_else(|| anyhow::anyhow!("No tracks in file"))?;
+    let (range, mut selections) = select_samples(&source.tracks, reference, start_secs, end_secs)?;
+
+    let movie_duration = scaled(range.end_secs - range.start_secs, source.movie_timescale);
+    write_output(
+        std::slice::from_mut(&mut source.file),
+        source.ftyp.as_deref(),
+        &source.moov,
+        &mut selections,
+        source.movie_timescale,
+        movie_duration,
+        output,
+    )?;
+    Ok(range)
+}
+
+/// Join `inputs` end to end into `output` without re-encoding. Every input needs the same
+/// tracks with identical sample descriptions, i.e. the same codec settings. Returns the
+/// total duration in seconds.
+pub fn concat_mp4(inputs: &[&Path], output: &Path) -> anyhow::Result<f64> {
+    if inputs.len() < 2 {
+        anyhow::bail!("At least two files are needed");
+    }
+    let mut sources = inputs
+        .iter()
+        .enumerate()
+        .map(|(i, path)| open_source(path, i).map_err(|e| anyhow::anyhow!("{}: {}", display_name(path), e)))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+
+    let first = &sources[0];
+    if first.tracks.is_empty() {
+        anyhow::bail!("{}: no tracks in file", display_name(inputs[0]));
+    }
+    for (i, other) in sources.iter().enumerate().skip(1) {
+        let compatible = other.tracks.len() == first.tracks.len()
+            && other.tracks.iter().zip(&first.tracks).all(|(a, b)| {
+                a.handler == b.handler && a.timescale == b.timescale && a.stsd == b.stsd
+            });
+        if !compatible {
+            anyhow::bail!("{} has different tracks or codec settings than {}", display_name(inputs[i]), display_name(inputs[0]));
+        }
+        // The output keeps a single edit per track, taken from the first input
+        if other.tracks.iter().zip(&first.tracks).any(|(a, b)| a.edit_media_time != b.edit_media_time) {
+            anyhow::bail!("{} has a different edit list than {}", display_name(inputs[i]), display_name(inputs[0]));
+        }
+    }
+
+    let mut selections: Vec<Selection> = first.tracks.iter().map(|t| Selection::new(t, Vec::new())).collect();
+    let mut elapsed = 0.0f64;
+    for source in &sources {
+        // Each part starts once its longest track has ended, so audio and video stay in
+        // sync across the joins; shorter tracks have their last sample stretched to fit.
+        let part_secs = source
+            .tracks
+            .iter()
+            .map(|t| t.duration() as f64 / t.timescale as f64)
+            .fold(0.0, f64::max);
+        for (sel, track) in selections.iter_mut().zip(&source.tracks) {
+            let base = scaled(elapsed, track.timescale);
+            let first_dts = track.samples.first().map(|s| s.dts).unwrap_or(0);
+            sel.has_ctts |= track.has_ctts;
+            sel.has_stss |= track.has_stss;
+            sel.samples.extend(track.samples.iter().map(|s| Sample { dts: base + s.dts - first_dts, ..*s }));
+            let target = scaled(elapsed + part_secs, track.timescale);
+            if let Some(last) = sel.samples.last_mut() {
+                let end = last.dts + last.duration as u64;
+                if target > end {
+                    last.duration = (last.duration as u64 + (target - end)).min(u32::MAX as u64) as u32;
+                }
+            }
+        }
+        elapsed += part_secs;
+    }
+
+    let first = &sources[0];
+    let (ftyp, moov, movie_timescale) = (first.ftyp.clone(), first.moov.clone(), first.movie_timescale);
+    let mut files: Vec<std::fs::File> = sources.drain(..).map(|s| s.file).collect();
+    write_output(
+        &mut files,
+        ftyp.as_deref(),
+        &moov,
+        &mut selections,
+        movie_timescale,
+        scaled(elapsed, movie_timescale),
+        output,
+    )?;
+    Ok(elapsed)
+}
+
+fn display_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default()
+}
+
+fn open_source(path: &Path, index: usize) -> anyhow::Result<Source> {
+    let mut file = std::fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    let (ftyp, moov) = read_top_level(&mut file, len)?;
+    let movie_timescale = child(&moov, b"mvhd")
+        .and_then(header_timing)
+        .map(|(ts, _)| ts)
+        .filter(|ts| *ts > 0)
+        .ok_or_else(|| anyhow::anyhow!("Missing movie header"))?;
+    let tracks = children(&moov)
+        .filter(|(kind, _)| kind == b"trak")
+        .map(|(_, trak)| parse_track(trak, index))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    Ok(Source { file, ftyp, moov, movie_timescale, tracks })
+}
+
+/// Write ftyp, a rebuilt moov and an mdat holding the selected samples, interleaved by
+/// time. Chunk offsets are always co64, so the moov size doesn't depend on the offsets
+/// and can be measured before they are known.
+fn write_output(
+    sources: &mut [std::fs::File],
+    ftyp: Option<&[u8]>,
+    moov: &[u8],
+    selections: &mut [Selection],
+    movie_timescale: u32,
+    movie_duration: u64,
+    output: &Path,
+) -> anyhow::Result<()> {
+    for sel in selections.iter_mut() {
+        sel.offsets = vec![0; sel.samples.len()];
+    }
+    let ftyp_box = ftyp.map(|body| make_box(b"ftyp", body)).unwrap_or_default();
+    let moov_len = build_moov(moov, selections, movie_timescale, movie_duration)?.len() as u64;
+    let mdat_start = ftyp_box.len() as u64 + moov_len + 16;
+
+    let mut order: Vec<(f64, usize, usize)> = selections
+        .iter()
+        .enumerate()
+        .flat_map(|(t, sel)| {
+            sel.samples.iter().enumerate().map(move |(i, s)| (s.dts as f64 / sel.timescale as f64, t, i))
+        })
+        .collect();
+    order.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut pos = mdat_start;
+    for &(_, t, i) in &order {
+        selections[t].offsets[i] = pos;
+        pos += selections[t].samples[i].size as u64;
+    }
+    let moov_box = build_moov(moov, selections, movie_timescale, movie_duration)?;
+    debug_assert_eq!(moov_box.len() as u64, moov_len);
+
+    let mut out = BufWriter::new(std::fs::File::create(output)?);
+    out.write_all(&ftyp_box)?;
+    out.write_all(&moov_box)?;
+    // 64-bit mdat header: size 1, type, largesize
+    out.write_all(&1u32.to_be_bytes())?;
+    out.write_all(b"mdat")?;
+    out.write_all(&(pos - mdat_start + 16).to_be_bytes())?;
+
+    let mut buf = Vec::new();
+    for &(_, t, i) in &order {
+        let sample = selections[t].samples[i];
+        let src = &mut sources[sample.source];
+        buf.resize(sample.size as usize, 0);
+        src.seek(SeekFrom::Start(sample.offset))?;
+        src.read_exact(&mut buf)?;
+        out.write_all(&buf)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn read_top_level(src: &mut std::fs::File, len: u64) -> anyhow::Result<(Option<Vec<u8>>, Vec<u8>)> {
+    let mut pos = 0;
+    let mut ftyp = None;
+    let mut moov = None;
+    while let Some(header) = read_box_header(src, len - pos)? {
+        let body_len = header.size - header.header_len;
+        match &header.kind {
+            b"ftyp" if body_len <= 1024 => {
+                let mut body = vec![0u8; body_len as usize];
+                src.read_exact(&mut body)?;
+                ftyp = Some(body);
+            }
+            b"moov" if body_len <= MAX_MOOV_SIZE => {
+                let mut body = vec![0u8; body_len as usize];
+                src.read_exact(&mut body)?;
+                moov = Some(body);
+            }
+            b"moof" => anyhow::bail!("Fragmented MP4 files can't be clipped"),
+            _ => {}
+        }
+        pos += header.size;
+        src.seek(SeekFrom::Start(pos))?;
+    }
+    let moov = moov.ok_or_else(|| anyhow::anyhow!("File has no index; it was probably not finalised"))?;
+    if child(&moov, b"mvex").is_some() {
+        anyhow::bail!("Fragmented MP4 files can't be clipped");
+    }
+    Ok((ftyp, moov))
+}
+
+fn parse_track(trak: &[u8], source: usize) -> anyhow::Result<Track> {
+    let mdia = child(trak, b"mdia").ok_or_else(|| anyhow::anyhow!("Track without media"))?;
+    let mut handler = [0u8; 4];
+    if let Some(h) = child(mdia, b"hdlr").and_then(|h| h.get(8..12)) {
+        handler.copy_from_slice(h);
+    }
+    let timescale = child(mdia, b"mdhd")
+        .and_then(header_timing)
+        .map(|(ts, _)| ts)
+        .filter(|ts| *ts > 0)
+        .ok_or_else(|| anyhow::anyhow!("Track without timescale"))?;
+    let stbl = child(mdia, b"minf")
+        .and_then(|m| child(m, b"stbl"))
+        .ok_or_else(|| anyhow::anyhow!("Track without sample table"))?;
+    let table = |kind: &[u8; 4]| child(stbl, kind).ok_or_else(|| anyhow::anyhow!("Missing {}", String::from_utf8_lossy(kind)));
+
+    if child(stbl, b"stz2").is_some() {
+        anyhow::bail!("Compact sample sizes (stz2) are not supported");
+    }
+
+    // stsz: version/flags(4) sample_size(4) sample_count(4) then per-sample sizes when sample_size is 0
+    let stsz = table(b"stsz")?;
+    let uniform = be_u32(stsz, 4);
+    let count = be_u32(stsz, 8) as usize;
+    if count > MAX_SAMPLES || (uniform == 0 && count > stsz.len().saturating_sub(12) / 4) {
+        anyhow::bail!("Too many samples");
+    }
+    let sizes: Vec<u32> = (0..count)
+        .map(|i| if uniform != 0 { uniform } else { be_u32(stsz, 12 + i * 4) })
+        .collect();
+
+    // stco/co64: version/flags(4) entry_count(4) then offsets
+    let chunk_offsets: Vec<u64> = if let Some(co64) = child(stbl, b"co64") {
+        (0..entry_count(co64, 8)).map(|i| be_u64(co64, 8 + i * 8).unwrap_or(0)).collect()
+    } else {
+        let stco = table(b"stco")?;
+        (0..entry_count(stco, 4)).map(|i| be_u32(stco, 8 + i * 4) as u64).collect()
+    };
+
+    // stsc: version/flags(4) entry_count(4) then (first_chunk, samples_per_chunk, description_index)
+    let stsc = table(b"stsc")?;
+    let stsc_entries: Vec<(u32, u32, u32)> = (0..entry_count(stsc, 12))
+        .map(|i| (be_u32(stsc, 8 + i * 12), be_u32(stsc, 12 + i * 12), be_u32(stsc, 16 + i * 12)))
+        .collect();
+
+    let mut samples = Vec::with_capacity(count);
+    let mut entry = 0;
+    for (chunk, &chunk_offset) in chunk_offsets.iter().enumerate() {
+        let chunk_no = chunk as u32 + 1;
+        while entry + 1 < stsc_entries.len() && stsc_entries[entry + 1].0 <= chunk_no {
+            entry += 1;
+        }
+        let (_, per_chunk, description) = match stsc_entries.get(entry) {
+            Some(e) => *e,
+            None => break,
+        };
+        let mut offset = chunk_offset;
+        for _ in 0..per_chunk {
+            let size = match sizes.get(samples.len()) {
+                Some(s) => *s,
+                None => break,
+            };
+            samples.push(Sample { source, offset, size, dts: 0, duration: 0, cts_offset: 0, sync: true, description });
+            offset += size as u64;
+        }
+    }
+
+    // stts: version/flags(4) entry_count(4) then (sample_count, delta)
+    let stts = table(b"stts")?;
+    let mut index = 0;
+    let mut dts = 0u64;
+    'stts: for i in 0..entry_count(stts, 8) {
+        let (n, delta) = (be_u32(stts, 8 + i * 8), be_u32(stts, 12 + i * 8));
+        for _ in 0..n {
+            let Some(sample) = samples.get_mut(index) else { break 'stts };
+            sample.dts = dts;
+            sample.duration = delta;
+            dts += delta as u64;
+            index += 1;
+        }
+    }
+    samples.truncate(index);
+
+    // ctts: version 0 offsets are unsigned, version 1 signed
+    let ctts = child(stbl, b"ctts");
+    if let Some(ctts) = ctts {
+        let signed = ctts.first() == Some(&1);
+        let mut index = 0;
+        for i in 0..entry_count(ctts, 8) {
+            let n = be_u32(ctts, 8 + i * 8);
+            let raw = be_u32(ctts, 12 + i * 8);
+            let offset = if signed { raw as i32 as i64 } else { raw as i64 };
+            for _ in 0..n {
+                let Some(sample) = samples.get_mut(index) else { break };
+                sample.cts_offset = offset;
+                index += 1;
+            }
+        }
+    }
+
+    // stss: 1-based numbers of sync samples; absent means every sample is one
+    let stss = child(stbl, b"stss");
+    if let Some(stss) = stss {
+        for sample in samples.iter_mut() {
+            sample.sync = false;
+        }
+        for i in 0..entry_count(stss, 4) {
+            let n = be_u32(stss, 8 + i * 4) as usize;
+            if let Some(sample) = n.checked_sub(1).and_then(|n| samples.get_mut(n)) {
+                sample.sync = true;
+            }
+        }
+    }
+
+    Ok(Track {
+        handler,
+        timescale,
+        stsd: child(stbl, b"stsd").map(<[u8]>::to_vec).unwrap_or_default(),
+        samples,
+        has_ctts: ctts.is_some(),
+        has_stss: stss.is_some(),
+        edit_media_time: child(trak, b"edts").and_then(|e| child(e, b"elst")).and_then(first_edit_media_time),
+    })
+}
+
+/// Entry count of a table box, limited to what actually fits in its body.
+fn entry_count(body: &[u8], entry_len: usize) -> usize {
+    (be_u32(body, 4) as usize).min(body.len().saturating_sub(8) / entry_len)
+}
+
+/// elst: version/flags(4) entry_count(4) then (segment_duration, media_time, rate) per entry.
+fn first_edit_media_time(elst: &[u8]) -> Option<i64> {
+    let wide = elst.first() == Some(&1);
+    let entry_len = if wide { 20 } else { 12 };
+    (0..entry_count(elst, entry_len))
+        .map(|i| {
+            let at = 8 + i * entry_len;
+            if wide {
+                be_u64(elst, at + 8).map(|v| v as i64).unwrap_or(-1)
+            } else {
+                be_u32(elst, at + 4) as i32 as i64
+            }
+        })
+        .find(|t| *t >= 0)
+}
+
+fn select_samples(tracks: &[Track], reference: usize, start_secs: f64, end_secs: f64) -> anyhow::Result<(ClipRange, Vec<Selection>)> {
+    let track = &tracks[reference];
+    let ts = track.timescale as f64;
+    let start_ts = (start_secs * ts) as u64;
+    let end_ts = (end_secs * ts).ceil() as u64;
+
+    let track_end = track.duration();
+    if start_ts >= track_end {
+        anyhow::bail!("Clip range is past the end of the recording");
+    }
+
+    let first = track
+        .samples
+        .iter()
+        .rposition(|s| s.sync && s.dts <= start_ts)
+        .or_else(|| track.samples.iter().position(|s| s.sync))
+        .ok_or_else(|| anyhow::anyhow!("No keyframes found"))?;
+    let last = track.samples.iter().position(|s| s.dts >= end_ts).unwrap_or(track.samples.len());
+    if last <= first {
+        anyhow::bail!("Clip range is past the end of the recording");
+    }
+    let clip_start = track.samples[first].dts as f64 / ts;
+    let end_sample = &track.samples[last - 1];
+    let clip_end = (end_sample.dts + end_sample.duration as u64) as f64 / ts;
+
+    let selections = tracks
+        .iter()
+        .enumerate()
+        .map(|(i, t)| {
+            let samples: &[Sample] = if i == reference {
+                &track.samples[first..last]
+            } else {
+                let from = t.samples.partition_point(|s| (s.dts as f64 / t.timescale as f64) < clip_start);
+                // Other video tracks also have to begin on a keyframe
+                let from = if &t.handler == b"vide" {
+                    t.samples[from..].iter().position(|s| s.sync).map(|p| from + p).unwrap_or(t.samples.len())
+                } else {
+                    from
+                };
+                let to = t.samples.partition_point(|s| (s.dts as f64 / t.timescale as f64) < clip_end).max(from);
+                &t.samples[from..to]
+            };
+            Selection::new(t, samples.to_vec())
+        })
+        .collect();
+    Ok((ClipRange { start_secs: clip_start, end_secs: clip_end }, selections))
+}
+
+fn scaled(secs: f64, timescale: u32) -> u64 {
+    (secs * timescale as f64).round().max(0.0) as u64
+}
+
+fn make_box(kind: &[u8; 4], body: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(body.len() + 8);
+    out.extend_from_slice(&((body.len() + 8) as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(body);
+    out
+}
+
+/// Full box with the given version and zero flags.
+fn make_full_box(kind: &[u8; 4], version: u8, body: &[u8]) -> Vec<u8> {
+    let mut full = Vec::with_capacity(body.len() + 4);
+    full.extend_from_slice(&[version, 0, 0, 0]);
+    full.extend_from_slice(body);
+    make_box(kind, &full)
+}
+
+/// Overwrite the duration field of an mvhd/mdhd (at 16 or 24) or tkhd (at 20 or 28).
+fn patch_duration(body: &[u8], v0_at: usize, duration: u64) -> Vec<u8> {
+    let mut body = body.to_vec();
+    if body.first() == Some(&1) {
+        let at = v0_at + 8;
+        if let Some(slot) = body.get_mut(at..at + 8) {
+            slot.copy_from_slice(&duration.to_be_bytes());
+        }
+    } else if let Some(slot) = body.get_mut(v0_at..v0_at + 4) {
+        slot.copy_from_slice(&(duration.min(u32::MAX as u64 - 1) as u32).to_be_bytes());
+    }
+    body
+}
+
+fn build_moov(moov: &[u8], selections: &[Selection], movie_timescale: u32, movie_duration: u64) -> anyhow::Result<Vec<u8>> {
+    let mut body = Vec::new();
+    let mut track = 0;
+    for (kind, data) in children(moov) {
+        match &kind {
+            b"mvhd" => body.extend(make_box(b"mvhd", &patch_duration(data, 16, movie_duration))),
+            b"trak" => {
+                let sel = selections.get(track).ok_or_else(|| anyhow::anyhow!("Track mismatch"))?;
+                body.extend(build_trak(data, sel, movie_timescale));
+                track += 1;
+            }
+            _ => body.extend(make_box(&kind, data)),
+        }
+    }
+    Ok(make_box(b"moov", &body))
+}
+
+fn build_trak(trak: &[u8], sel: &Selection, movie_timescale: u32) -> Vec<u8> {
+    let media_duration = sel.media_duration();
+    let track_duration = scaled(media_duration as f64 / sel.timescale as f64, movie_timescale);
+    let mut body = Vec::new();
+    for (kind, data) in children(trak) {
+        match &kind {
+            b"tkhd" => body.extend(make_box(b"tkhd", &patch_duration(data, 20, track_duration))),
+            b"edts" => {
+                if let Some(media_time) = sel.edit_media_time {
+                    // One edit covering the whole clip: duration(8) media_time(8) rate 1.0
+                    let mut elst = Vec::new();
+                    elst.extend_from_slice(&1u32.to_be_bytes());
+                    elst.extend_from_slice(&track_duration.to_be_bytes());
+                    elst.extend_from_slice(&media_time.to_be_bytes());
+                    elst.extend_from_slice(&[0, 1, 0, 0]);
+                    body.extend(make_box(b"edts", &make_full_box(b"elst", 1, &elst)));
+                }
+            }
+            b"mdia" => {
+                let mut mdia = Vec::new();
+                for (kind, data) in children(data) {
+                    match &kind {
+                        b"mdhd" => mdia.extend(make_box(b"mdhd", &patch_duration(data, 16, media_duration))),
+                        b"minf" => {
+                            let mut minf = Vec::new();
+                            for (kind, data) in children(data) {
+                                if &kind == b"stbl" {
+                                    minf.extend(build_stbl(data, sel));
+                                } else {
+                                    minf.extend(make_box(&kind, data));
+                                }
+                            }
+                            mdia.extend(make_box(b"minf", &minf));
+                        }
+                        _ => mdia.extend(make_box(&kind, data)),
+                    }
+                }
+                body.extend(make_box(b"mdia", &mdia));
+            }
+            _ => body.extend(make_box(&kind, data)),
+        }
+    }
+    make_box(b"trak", &body)
+}
+
+/// New sample tables with one sample per chunk. Grouping and dependency boxes (sdtp,
+/// sbgp, sgpd) describe the old sample numbering, so they are dropped.
+fn build_stbl(stbl: &[u8], sel: &Selection) -> Vec<u8> {
+    let samples = &sel.samples;
+    let mut body = Vec::new();
+    if let Some(stsd) = child(stbl, b"stsd") {
+        body.extend(make_box(b"stsd", stsd));
+    }
+
+    let stts = run_lengths(samples.iter().map(|s| s.duration as i64));
+    body.extend(make_full_box(b"stts", 0, &entries(&stts)));
+
+    if sel.has_ctts {
+        let ctts = run_lengths(samples.iter().map(|s| s.cts_offset));
+        let version = if samples.iter().any(|s| s.cts_offset < 0) { 1 } else { 0 };
+        body.extend(make_full_box(b"ctts", version, &entries(&ctts)));
+    }
+
+    if sel.has_stss {
+        let sync: Vec<u32> = samples
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| s.sync)
+            .map(|(i, _)| i as u32 + 1)
+            .collect();
+        let mut stss = (sync.len() as u32).to_be_bytes().to_vec();
+        for n in sync {
+            stss.extend_from_slice(&n.to_be_bytes());
+        }
+        body.extend(make_full_box(b"stss", 0, &stss));
+    }
+
+    let mut stsz = 0u32.to_be_bytes().to_vec();
+    stsz.extend_from_slice(&(samples.len() as u32).to_be_bytes());
+    for s in samples {
+        stsz.extend_from_slice(&s.size.to_be_bytes());
+    }
+    body.extend(make_full_box(b"stsz", 0, &stsz));
+
+    // A new stsc entry only where the sample description changes
+    let mut stsc_entries: Vec<(u32, u32)> = Vec::new();
+    for (i, s) in samples.iter().enumerate() {
+        if stsc_entries.last().map(|e| e.1) != Some(s.description) {
+            stsc_entries.push((i as u32 + 1, s.description));
+        }
+    }
+    let mut stsc = (stsc_entries.len() as u32).to_be_bytes().to_vec();
+    for (first_chunk, description) in stsc_entries {
+        stsc.extend_from_slice(&first_chunk.to_be_bytes());
+        stsc.extend_from_slice(&1u32.to_be_bytes());
+        stsc.extend_from_slice(&description.to_be_bytes());
+    }
+    body.extend(make_full_box(b"stsc", 0, &stsc));
+
+    let mut co64 = (samples.len() as u32).to_be_bytes().to_vec();
+    for offset in &sel.offsets {
+        co64.extend_from_slice(&offset.to_be_bytes());
+    }
+    body.extend(make_full_box(b"co64", 0, &co64));
+
+    make_box(b"stbl", &body)
+}
+
+fn run_lengths(values: impl Iterator<Item = i64>) -> Vec<(u32, i64)> {
+    let mut runs: Vec<(u32, i64)> = Vec::new();
+    for v in values {
+        match runs.last_mut() {
+            Some((n, last)) if *last == v => *n += 1,
+            _ => runs.push((1, v)),
+        }
+    }
+    runs
+}
+
+/// entry_count then (count, value) pairs; values are written as 32-bit two's complement.
+fn entries(runs: &[(u32, i64)]) -> Vec<u8> {
+    let mut out = (runs.len() as u32).to_be_bytes().to_vec();
+    for (n, v) in runs {
+        out.extend_from_slice(&n.to_be_bytes());
+        out.extend_from_slice(&(*v as i32).to_be_bytes());
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// One track of a synthetic MP4. Every sample gets its own recognisable payload.
+    struct TestTrack {
+        handler: &'static [u8; 4],
+        timescale: u32,
+        durations: Vec<u32>,
+        /// 1-based sync sample numbers; `None` writes no stss.
+        sync: Option<Vec<u32>>,
+        /// Composition offsets, written as a version 1 ctts.
+        ctts: Option<Vec<i32>>,
+        edit_media_time: Option<i32>,
+    }
+
+    impl TestTrack {
+        fn video(samples: usize, sync: Vec<u32>) -> Self {
+            Self { handler: b"vide", timescale: 1000, durations: vec![100; samples], sync: Some(sync), ctts: None, edit_media_time: None }
+        }
+
+        fn audio(samples: usize) -> Self {
+            Self { handler: b"soun", timescale: 48000, durations: vec![4800; samples], sync: None, ctts: None, edit_media_time: None }
+        }
+    }
+
+    fn payload(track: usize, sample: usize) -> Vec<u8> {
+        let len = 12 + sample % 5;
+        (0..len).map(|i| (track * 100 + sample + i) as u8).collect()
+    }
+
+    fn table(kind: &[u8; 4], version: u8, count: usize, fields: &[u32]) -> Vec<u8> {
+        let mut body = (count as u32).to_be_bytes().to_vec();
+        for f in fields {
+            body.extend_from_slice(&f.to_be_bytes());
+        }
+        make_full_box(kind, version, &body)
+    }
+
+    fn build_trak(track: &TestTrack, index: usize, offsets: &[u64]) -> Vec<u8> {
+        let duration: u32 = track.durations.iter().sum();
+        let mut mdhd = vec![0u8; 20];
+        mdhd[8..12].copy_from_slice(&track.timescale.to_be_bytes());
+        mdhd[12..16].copy_from_slice(&duration.to_be_bytes());
+        let mut hdlr = vec![0u8; 4];
+        hdlr.extend_from_slice(track.handler);
+        hdlr.extend_from_slice(&[0; 13]);
+        // stsd with a single entry whose format is told apart by handler
+        let mut stsd = 1u32.to_be_bytes().to_vec();
+        stsd.extend_from_slice(&16u32.to_be_bytes());
+        stsd.extend_from_slice(if track.handler == b"vide" { b"avc1" } else { b"mp4a" });
+        stsd.extend_from_slice(&[0, 0, 0, 0, 0, 0, 0, 1]);
+
+        let n = track.durations.len();
+        let mut stbl = make_full_box(b"stsd", 0, &stsd);
+        let stts: Vec<u32> = track.durations.iter().flat_map(|d| [1, *d]).collect();
+        stbl.extend(table(b"stts", 0, n, &stts));
+        if let Some(ctts) = &track.ctts {
+            let fields: Vec<u32> = ctts.iter().flat_map(|c| [1, *c as u32]).collect();
+            stbl.extend(table(b"ctts", 1, n, &fields));
+        }
+        if let Some(sync) = &track.sync {
+            stbl.extend(table(b"stss", 0, sync.len(), sync));
+        }
+        let mut stsz = 0u32.to_be_bytes().to_vec();
+        stsz.extend_from_slice(&(n as u32).to_be_bytes());
+        for i in 0..n {
+            stsz.extend_from_slice(&(payload(index, i).len() as u32).to_be_bytes());
+        }
+        stbl.extend(make_full_box(b"stsz", 0, &stsz));
+        stbl.extend(table(b"stsc", 0, 1, &[1, 1, 1]));
+        let stco: Vec<u32> = offsets.iter().map(|o| *o as u32).collect();
+        stbl.extend(table(b"stco", 0, n, &stco));
+
+        let minf = make_box(b"minf", &make_box(b"stbl", &stbl));
+        let mut mdia = make_full_box(b"mdhd", 0, &mdhd);
+        mdia.extend(make_box(b"hdlr", &hdlr));
+        mdia.extend(minf);
+
+        let mut trak = make_full_box(b"tkhd", 0, &[0; 80]);
+        if let Some(media_time) = track.edit_media_time {
+            // One edit: segment_duration, media_time, rate 1.0
+            let elst = table(b"elst", 0, 1, &[duration, media_time as u32, 0x0001_0000]);
+            trak.extend(make_box(b"edts", &elst));
+        }
+        trak.extend(make_box(b"mdia", &mdia));
+        make_box(b"trak", &trak)
+    }
+
+    /// ftyp, then an mdat with all samples, then the moov.
+    fn build_mp4(tracks: &[TestTrack]) -> Vec<u8> {
+        let ftyp = make_box(b"ftyp", b"isom\0\0\0\0isom");
+        let mut mdat = Vec::new();
+        let mut offsets = Vec::new();
+        for (t, track) in tracks.iter().enumerate() {
+            let mut track_offsets = Vec::new();
+            for i in 0..track.durations.len() {
+                track_offsets.push((ftyp.len() + 8 + mdat.len()) as u64);
+                mdat.extend(payload(t, i));
+            }
+            offsets.push(track_offsets);
+        }
+        let mut mvhd = vec![0u8; 96];
+        mvhd[8..12].copy_from_slice(&1000u32.to_be_bytes());
+        let mut moov = make_full_box(b"mvhd", 0, &mvhd);
+        for (t, track) in tracks.iter().enumerate() {
+            moov.extend(build_trak(track, t, &offsets[t]));
+        }
+
+        let mut file = ftyp;
+        file.extend(make_box(b"mdat", &mdat));
+        file.extend(make_box(b"moov", &moov));
+        file
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("mp4_edit_{}_{}.mp4", name, uuid::Uuid::new_v4().simple()))
+    }
+
+    fn write_mp4(name: &str, tracks: &[TestTrack]) -> PathBuf {
+        let path = temp_path(name);
+        std::fs::write(&path, build_mp4(tracks)).unwrap();
+        path
+    }
+
+    /// Bytes of every sample of every track, as the output's sample tables point at them.
+    fn read_samples(path: &Path) -> Vec<Vec<Vec<u8>>> {
+        let source = open_source(path, 0).unwrap();
+        let data = std::fs::read(path).unwrap();
+        source
+            .tracks
+            .iter()
+            .map(|t| t.samples.iter().map(|s| data[s.offset as usize..s.offset as usize + s.size as usize].to_vec()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn clip_snaps_back_to_keyframe() {
+        let tracks = [TestTrack::video(30, vec![1, 11, 21]), TestTrack::audio(30)];
+        let input = write_mp4("snap_in", &tracks);
+        let output = temp_path("snap_out");
+
+        let range = clip_mp4(&input, &output, 1.5, 2.5).unwrap();
+        assert_eq!(range.start_secs, 1.0);
+        assert_eq!(range.end_secs, 2.5);
+
+        let clipped = open_source(&output, 0).unwrap();
+        let video = &clipped.tracks[0];
+        assert_eq!(video.samples.len(), 15);
+        assert_eq!(video.samples[0].dts, 0);
+        let sync: Vec<usize> = video.samples.iter().enumerate().filter(|(_, s)| s.sync).map(|(i, _)| i).collect();
+        assert_eq!(sync, vec![0, 10]);
+        assert_eq!(clipped.tracks[1].samples.len(), 15);
+
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn select_samples_without_earlier_keyframe_uses_first() {
+        let tracks = [TestTrack::video(10, vec![3, 8])];
+        let path = write_mp4("select", &tracks);
+        let source = open_source(&path, 0).unwrap();
+        let (range, selections) = select_samples(&source.tracks, 0, 0.05, 0.6).unwrap();
+        assert_eq!(range.start_secs, 0.2);
+        assert_eq!(selections[0].samples.len(), 4);
+        assert!(select_samples(&source.tracks, 0, 5.0, 6.0).is_err());
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[test]
+    fn chunk_offsets_point_at_the_copied_samples() {
+        let tracks = [TestTrack::video(30, vec![1, 11, 21]), TestTrack::audio(30)];
+        let input = write_mp4("offsets_in", &tracks);
+        let output = temp_path("offsets_out");
+        clip_mp4(&input, &output, 1.0, 2.0).unwrap();
+
+        let data = std::fs::read(&output).unwrap();
+        let moov = child(&data, b"moov").unwrap();
+        let stbl = child(moov, b"trak")
+            .and_then(|t| child(t, b"mdia"))
+            .and_then(|m| child(m, b"minf"))
+            .and_then(|m| child(m, b"stbl"))
+            .unwrap();
+        assert!(child(stbl, b"co64").is_some());
+        assert!(child(stbl, b"stco").is_none());
+
+        let samples = read_samples(&output);
+        for (i, bytes) in samples[0].iter().enumerate() {
+            assert_eq!(bytes, &payload(0, 10 + i));
+        }
+        for (i, bytes) in samples[1].iter().enumerate() {
+            assert_eq!(bytes, &payload(1, 10 + i));
+        }
+
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn run_lengths_round_trip_through_entries() {
+        let values = [5i64, 5, 5, -2, -2, 7];
+        let runs = run_lengths(values.iter().copied());
+        assert_eq!(runs, vec![(3, 5), (2, -2), (1, 7)]);
+
+        let encoded = entries(&runs);
+        let mut body = vec![0u8; 4];
+        body.extend(&encoded);
+        assert_eq!(entry_count(&body, 8), 3);
+        let decoded: Vec<i64> = (0..3)
+            .flat_map(|i| {
+                let n = be_u32(&body, 8 + i * 8);
+                let v = be_u32(&body, 12 + i * 8) as i32 as i64;
+                std::iter::repeat_n(v, n as usize)
+            })
+            .collect();
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn signed_composition_offsets_survive_a_clip() {
+        let mut video = TestTrack::video(10, vec![1, 6]);
+        let offsets = vec![0, 200, -100, 200, -100, 0, 200, -100, 200, -100];
+        video.ctts = Some(offsets.clone());
+        let input = write_mp4("ctts_in", &[video]);
+        let output = temp_path("ctts_out");
+        clip_mp4(&input, &output, 0.5, 1.0).unwrap();
+
+        let clipped = open_source(&output, 0).unwrap();
+        let got: Vec<i64> = clipped.tracks[0].samples.iter().map(|s| s.cts_offset).collect();
+        let want: Vec<i64> = offsets[5..].iter().map(|o| *o as i64).collect();
+        assert_eq!(got, want);
+
+        let data = std::fs::read(&output).unwrap();
+        let ctts = child(&data, b"moov")
+            .and_then(|m| child(m, b"trak"))
+            .and_then(|t| child(t, b"mdia"))
+            .and_then(|m| child(m, b"minf"))
+            .and_then(|m| child(m, b"stbl"))
+            .and_then(|s| child(s, b"ctts"))
+            .unwrap();
+        assert_eq!(ctts[0], 1, "negative offsets need a version 1 ctts");
+
+        let _ = std::fs::remove_file(&input);
+        let _ = std::fs::remove_file(&output);
+    }
+
+    #[test]
+    fn concat_joins_parts_in_order() {
+        let first = write_mp4("concat_a", &[TestTrack::video(10, vec![1, 6]), TestTrack::audio(10)]);
+        let second = write_mp4("concat_b", &[TestTrack::video(20, vec![1, 11]), TestTrack::audio(20)]);
+        let output = temp_path("concat_out");
+
+        let secs = concat_mp4(&[&first, &second], &output).unwrap();
+        assert!((secs - 3.0).abs() < 1e-9, "{}", secs);
+
+        let joined = open_source(&output, 0).unwrap();
+        let video = &joined.tracks[0];
+        assert_eq!(video.samples.len(), 30);
+        // The second part continues where the first ended
+        assert_eq!(video.samples[10].dts, 1000);
+        let sync: Vec<usize> = video.samples.iter().enumerate().filter(|(_, s)| s.sync).map(|(i, _)| i).collect();
+        assert_eq!(sync, vec![0, 5, 10, 20]);
+        assert_eq!(joined.tracks[1].samples[10].dts, 48000);
+
+        // Both inputs used the same per-track payloads, so check each part's bytes
+        let samples = read_samples(&output);
+        for (t, track) in samples.iter().enumerate() {
+            for (i, bytes) in track.iter().enumerate() {
+                let index = if i < 10 { i } else { i - 10 };
+                assert_eq!(bytes, &payload(t, index), "track {} sample {}", t, i);
+            }
+        }
+
+        for path in [&first, &second, &output] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn concat_rejects_mismatched_inputs() {
+        let plain = write_mp4("mismatch_a", &[TestTrack::video(10, vec![1])]);
+        let mut delayed = TestTrack::video(10, vec![1]);
+        delayed.edit_media_time = Some(200);
+        let edited = write_mp4("mismatch_b", &[delayed]);
+        let two_tracks = write_mp4("mismatch_c", &[TestTrack::video(10, vec![1]), TestTrack::audio(10)]);
+        let output = temp_path("mismatch_out");
+
+        let err = concat_mp4(&[&plain, &edited], &output).unwrap_err();
+        assert!(err.to_string().contains("edit list"), "{}", err);
+        let err = concat_mp4(&[&plain, &two_tracks], &output).unwrap_err();
+        assert!(err.to_string().contains("different tracks"), "{}", err);
+        assert!(concat_mp4(&[&plain], &output).is_err());
+
+        for path in [&plain, &edited, &two_tracks] {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    #[test]
+    fn rejects_fragmented_input() {
+        let mut data = build_mp4(&[TestTrack::video(4, vec![1])]);
+        data.extend(make_box(b"moof", &[0; 8]));
+        let input = temp_path("moof_in");
+        std::fs::write(&input, &data).unwrap();
+        let output = temp_path("moof_out");
+        let err = clip_mp4(&input, &output, 0.0, 0.2).unwrap_err();
+        assert!(err.to_string().contains("Fragmented"), "{}", err);
+        let _ = std::fs::remove_file(&input);
+    }
+
+    #[test]
+    fn rejects_input_with_movie_extends() {
+        let data = build_mp4(&[TestTrack::video(4, vec![1])]);
+        // The moov comes last; append an mvex to its body
+        let moov_at = data.len() - child(&data, b"moov").unwrap().len() - 8;
+        let mut moov = data[moov_at + 8..].to_vec();
+        moov.extend(make_box(b"mvex", &[]));
+        let mut patched = data[..moov_at].to_vec();
+        patched.extend(make_box(b"moov", &moov));
+
+        let input = temp_path("mvex_in");
+        std::fs::write(&input, &patched).unwrap();
+        let err = clip_mp4(&input, &temp_path("mvex_out"), 0.0, 0.2).unwrap_err();
+        assert!(err.to_string().contains("Fragmented"), "{}", err);
+        let _ = std::fs::remove_file(&input);
+    }
+}
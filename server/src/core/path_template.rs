@@ -0,0 +1,182 @@
+use chrono::{DateTime, Local};
+use std::path::{Component, Path};
+
+/// Matches the historical flat naming so existing installs see no change.
+pub const DEFAULT_PATH_TEMPLATE: &str = "{username}_{timestamp}.{ext}";
+
+/// Placeholders a template may use.
+pub const PLACEHOLDERS: [&str; 13] = [
+    "username", "user_id", "yyyy", "mm", "dd", "HH", "MM", "SS", "date", "time", "timestamp", "seq", "ext",
+];
+
+const FORBIDDEN_CHARS: [char; 16] = ['/', '\\', ':', '*', '?', '"', '<', '>', '|', '\0', '\n', '\r', ';', '&', '$', '`'];
+
+pub struct TemplateVars<'a> {
+    pub username: &'a str,
+    pub user_id: &'a str,
+    pub now: DateTime<Local>,
+    pub ext: &'a str,
+}
+
+pub fn uses_seq(template: &str) -> bool {
+    template.contains("{seq}")
+}
+
+/// Reject a single path component that could escape its directory or upset cli-capture's shell.
+pub fn validate_component(name: &str) -> Result<(), &'static str> {
+    if name.is_empty() {
+        return Err("Empty path component");
+    }
+    if name.chars().any(|c| FORBIDDEN_CHARS.contains(&c)) {
+        return Err("Invalid characters in filename");
+    }
+    if name.contains("..") {
+        return Err("Path traversal not allowed in filename");
+    }
+    if name.starts_with('.') {
+        return Err("Hidden files are not allowed");
+    }
+    Ok(())
+}
+
+/// Check a `/`-separated path relative to the recording root.
+pub fn validate_relative(path: &str) -> Result<(), &'static str> {
+    if path.starts_with('/') || Path::new(path).is_absolute() {
+        return Err("Path must be relative to the recording directory");
+    }
+    for part in path.split('/') {
+        validate_component(part)?;
+    }
+    if Path::new(path).components().any(|c| !matches!(c, Component::Normal(_))) {
+        return Err("Path traversal not allowed in filename");
+    }
+    Ok(())
+}
+
+/// Values are substituted into single components, so keep them free of separators.
+fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| if FORBIDDEN_CHARS.contains(&c) || c.is_control() { '_' } else { c })
+        .collect();
+    let cleaned = cleaned.replace("..", "_");
+    cleaned.trim_start_matches('.').to_string()
+}
+
+/// Expand a template into a relative path. `seq` fills `{seq}` (zero-padded to 3 digits).
+pub fn render(template: &str, vars: &TemplateVars, seq: u32) -> Result<String, String> {
+    let mut out = String::with_capacity(template.len() + 32);
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        out.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find('}')
+            .map(|e| start + e)
+            .ok_or_else(|| "Unclosed '{' in path template".to_string())?;
+        let key = &rest[start + 1..end];
+        let now = vars.now;
+        let value = match key {
+            "username" => sanitize(vars.username),
+            "user_id" => vars.user_id.to_string(),
+            "yyyy" => now.format("%Y").to_string(),
+            "mm" => now.format("%m").to_string(),
+            "dd" => now.format("%d").to_string(),
+            "HH" => now.format("%H").to_string(),
+            "MM" => now.format("%M").to_string(),
+            "SS" => now.format("%S").to_string(),
+            "date" => now.format("%Y-%m-%d").to_string(),
+            "time" => now.format("%H%M%S").to_string(),
+            "timestamp" => now.timestamp().to_string(),
+            "seq" => format!("{:03}", seq),
+            "ext" => sanitize(vars.ext),
+            other => return Err(format!("Unknown placeholder {{{}}} in path template", other)),
+        };
+        out.push_str(&value);
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+
+    let out = out.replace('\\', "/");
+    validate_relative(&out).map_err(|e| e.to_string())?;
+    Ok(out)
+}
+
+/// `a/b/name.mp4` -> `a/b/name_2.mp4`, used when a template without `{seq}` collides.
+pub fn with_suffix(path: &str, n: u32) -> String {
+    let (dir, file) = match path.rsplit_once('/') {
+        Some((d, f)) => (Some(d), f),
+        None => (None, path),
+    };
+    let file = match file.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}_{}.{}", stem, n, ext),
+        _ => format!("{}_{}", file, n),
+    };
+    match dir {
+        Some(d) => format!("{}/{}", d, file),
+        None => file,
+    }
+}
+
+/// Replace the last component, keeping the template's directory.
+pub fn replace_file_name(path: &str, name: &str) -> String {
+    match path.rsplit_once('/') {
+        Some((dir, _)) => format!("{}/{}", dir, name),
+        None => name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn validate_relative_table() {
+        let cases = [
+            ("rec.mp4", true),
+            ("alice/2026/rec.mp4", true),
+            ("rec..mp4", false),
+            ("../rec.mp4", false),
+            ("alice/../../rec.mp4", false),
+            ("/etc/rec.mp4", false),
+            (r"alice\rec.mp4", false),
+            (r"C:\rec.mp4", false),
+            (".rec.mp4", false),
+            ("alice/.cache/rec.mp4", false),
+            ("alice//rec.mp4", false),
+            ("./rec.mp4", false),
+            ("", false),
+        ];
+        for (path, ok) in cases {
+            assert_eq!(validate_relative(path).is_ok(), ok, "path {:?}", path);
+        }
+    }
+
+    #[test]
+    fn render_table() {
+        let now = Local.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        let cases = [
+            ("{username}/{date}/{time}.{ext}", "alice", Some("alice/2026-03-04/050607.mp4")),
+            ("{username}_{seq}.{ext}", "alice", Some("alice_007.mp4")),
+            // Backslashes in the template become separators
+            (r"{username}\{yyyy}.{ext}", "alice", Some("alice/2026.mp4")),
+            // Usernames can't add components or climb out
+            ("{username}/{seq}.{ext}", "../x", Some("__x/007.mp4")),
+            ("{username}/{seq}.{ext}", "...", Some("_./007.mp4")),
+            ("{username}/{seq}.{ext}", "a/b", Some("a_b/007.mp4")),
+            ("{username}_{seq}.{ext}", ".hidden", Some("hidden_007.mp4")),
+            // A username that sanitizes to nothing leaves an empty component
+            ("{username}/{seq}.{ext}", ".", None),
+            ("../{username}.{ext}", "alice", None),
+            ("/srv/{username}.{ext}", "alice", None),
+            (".{username}.{ext}", "alice", None),
+            ("{nope}.{ext}", "alice", None),
+            ("{username", "alice", None),
+        ];
+        for (template, username, expected) in cases {
+            let vars = TemplateVars { username, user_id: "u1", now, ext: "mp4" };
+            let got = render(template, &vars, 7).ok();
+            assert_eq!(got.as_deref(), expected, "template {:?} username {:?}", template, username);
+        }
+    }
+}
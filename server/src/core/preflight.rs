@@ -0,0 +1,78 @@
+use serde::Serialize;
+use crate::core::hardware::{run_cli, validate_cli_path, CliOutput};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct PreflightReport {
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    pub sources_created: bool,
+    pub capture_method: Option<String>,
+    pub copy_path: Option<String>,
+    pub warnings: Vec<String>,
+    pub errors: Vec<String>,
+    /// Parts of the start arguments `--test` does not exercise, so passing says nothing about them.
+    pub untested: Vec<String>,
+    pub output: Vec<String>,
+}
+
+/// Run cli-capture with the start arguments plus `--test`, which creates the sources and exits
+/// without starting an output, and turn its output into a report.
+pub async fn run_preflight(cli_path: &str, args: &[String]) -> anyhow::Result<PreflightReport> {
+    validate_cli_path(cli_path).await?;
+    let mut test_args = args.to_vec();
+    test_args.push("--test".to_string());
+    let output = run_cli(cli_path, test_args).await?;
+    let mut report = parse_report(output);
+    report.untested = untested(args);
+    Ok(report)
+}
+
+/// `--test` returns before any encoder is created, and it always captures a monitor, even when
+/// `--window` is given.
+fn untested(args: &[String]) -> Vec<String> {
+    let value = |flag: &str| args.iter().position(|a| a == flag).and_then(|i| args.get(i + 1));
+    let mut untested = Vec::new();
+    if let Some(encoder) = value("--encoder") {
+        untested.push(format!("encoder {}", encoder));
+    }
+    if let Some(window) = value("--window") {
+        untested.push(format!("window capture {}", window));
+    }
+    untested
+}
+
+fn parse_report(output: CliOutput) -> PreflightReport {
+    let mut report = PreflightReport {
+        passed: false,
+        exit_code: output.exit_code,
+        sources_created: false,
+        capture_method: None,
+        copy_path: None,
+        warnings: Vec::new(),
+        errors: Vec::new(),
+        untested: Vec::new(),
+        output: Vec::new(),
+    };
+
+    for line in output.stdout.lines().chain(output.stderr.lines()) {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        if let Some(method) = trimmed.strip_prefix("Monitor capture method:") {
+            report.capture_method = Some(method.trim().to_string());
+        } else if let Some(path) = trimmed.strip_prefix("Monitor capture copy path:") {
+            report.copy_path = Some(path.trim().to_string());
+        } else if trimmed.starts_with("Monitor source created. Test mode") {
+            report.sources_created = true;
+        } else if trimmed.starts_with("Warning") {
+            report.warnings.push(trimmed.to_string());
+        } else if trimmed.starts_with("Failed") || trimmed.starts_with("Agent error") {
+            report.errors.push(trimmed.to_string());
+        }
+        report.output.push(trimmed.to_string());
+    }
+
+    report.passed = output.success && report.sources_created && report.errors.is_empty();
+    report
+}
@@ -0,0 +1,52 @@
+use qrcode::render::svg;
+use qrcode::{Color, EcLevel, QrCode};
+
+/// Modules of white border around the code, as the spec requires for reliable scanning.
+const QUIET_ZONE: usize = 4;
+/// Pixels per module in the PNG; big enough to scan off a cabinet screen from arm's length.
+const PNG_SCALE: usize = 8;
+
+fn encode(data: &str) -> anyhow::Result<QrCode> {
+    // Medium correction keeps URLs with long tokens at a version phones still read easily
+    QrCode::with_error_correction_level(data.as_bytes(), EcLevel::M)
+        .map_err(|e| anyhow::anyhow!("Failed to encode QR code: {}", e))
+}
+
+pub fn render_svg(data: &str) -> anyhow::Result<String> {
+    let code = encode(data)?;
+    Ok(code
+        .render::<svg::Color>()
+        .min_dimensions(256, 256)
+        .quiet_zone(true)
+        .build())
+}
+
+/// Render an 8-bit greyscale PNG with the quiet zone included.
+pub fn render_png(data: &str) -> anyhow::Result<Vec<u8>> {
+    let code = encode(data)?;
+    let modules = code.width();
+    let colors = code.to_colors();
+    let side = (modules + 2 * QUIET_ZONE) * PNG_SCALE;
+
+    let mut pixels = vec![0xFFu8; side * side];
+    for (i, color) in colors.iter().enumerate() {
+        if *color != Color::Dark {
+            continue;
+        }
+        let x0 = (i % modules + QUIET_ZONE) * PNG_SCALE;
+        let y0 = (i / modules + QUIET_ZONE) * PNG_SCALE;
+        for y in y0..y0 + PNG_SCALE {
+            pixels[y * side + x0..y * side + x0 + PNG_SCALE].fill(0);
+        }
+    }
+
+    let mut out = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut out, side as u32, side as u32);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&pixels)?;
+    }
+    Ok(out)
+}
@@ -2,6 +2,10 @@ use std::collections::HashMap;
 use tokio::sync::RwLock;
 use tokio::process::{Command, Child};
 use tokio::fs;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+use std::process::Stdio;
+use std::time::Duration;
 use uuid::Uuid;
 use anyhow::Result;
 use serde::{Serialize, Deserialize};
@@ -12,6 +16,9 @@ pub enum RecorderMode {
     Service { agent_client: AgentClient },
 }
 
+// How long to watch cli-capture's output for the "Capture started" line before assuming it came up.
+const STARTUP_WATCH: Duration = Duration::from_secs(20);
+
 pub struct RecorderManager {
     processes: RwLock<HashMap<Uuid, (Option<Child>, u32, String)>>, // Child (if direct), PID, Task type
     mode: RecorderMode,
@@ -70,18 +77,38 @@ impl RecorderManager {
 
         validate_cli_path(&cli_path).await?;
 
-        match &self.mode {
+        let output_rx = match &self.mode {
             RecorderMode::Direct => {
                 let mut cmd = Command::new(&cli_path);
                 cmd.args(args);
+                cmd.stdout(Stdio::piped());
+                cmd.stderr(Stdio::piped());
 
-                let child = cmd.spawn().map_err(|e| anyhow::anyhow!("Failed to spawn process '{}': {}", cli_path, e))?;
+                let mut child = cmd.spawn().map_err(|e| anyhow::anyhow!("Failed to spawn process '{}': {}", cli_path, e))?;
                 let pid = child.id().unwrap_or(0);
+                let (tx, rx) = mpsc::unbounded_channel();
+                if let Some(stdout) = child.stdout.take() {
+                    spawn_output_reader(stdout, pid, tx.clone());
+                }
+                if let Some(stderr) = child.stderr.take() {
+                    spawn_output_reader(stderr, pid, tx);
+                }
                 processes.insert(user_id, (Some(child), pid, task_type));
+                Some(rx)
             }
             RecorderMode::Service { agent_client } => {
                 let pid = agent_client.start_recording(cli_path, args).await?;
                 processes.insert(user_id, (None, pid, task_type));
+                None
+            }
+        };
+        drop(processes);
+
+        // Only direct mode can see the CLI output; the agent just hands back a PID.
+        if let Some(rx) = output_rx {
+            if let Err(e) = watch_startup(rx).await {
+                let _ = self.stop_recording(user_id).await;
+                return Err(e);
             }
         }
         
@@ -89,36 +116,24 @@ impl RecorderManager {
     }
 
     pub async fn stop_recording(&self, user_id: Uuid) -> Result<()> {
-        let mut processes = self.processes.write().await;
-        
-        if let Some((child_opt, pid, _)) = processes.remove(&user_id) {
-            match &self.mode {
-                RecorderMode::Direct => {
-                    if let Some(mut child) = child_opt {
-                        let _ = child.kill().await;
-                        let _ = child.wait().await;
-                    }
-                }
-                RecorderMode::Service { .. } => {
-                    // In service mode, kill process by PID
-                    #[cfg(windows)]
-                    {
-                        let _ = std::process::Command::new("taskkill")
-                            .args(["/PID", &pid.to_string(), "/F"])
-                            .output();
-                    }
-                    #[cfg(unix)]
-                    {
-                        let _ = std::process::Command::new("kill")
-                            .args(["-9", &pid.to_string()])
-                            .output();
-                    }
+        // Kill outside the lock so a slow kill doesn't hold up status checks
+        let entry = self.processes.write().await.remove(&user_id);
+        let Some((child_opt, pid, _)) = entry else {
+            return Err(anyhow::anyhow!("No active process found"));
+        };
+        match &self.mode {
+            RecorderMode::Direct => {
+                if let Some(mut child) = child_opt {
+                    let _ = child.kill().await;
+                    let _ = child.wait().await;
                 }
             }
-            Ok(())
-        } else {
-            Err(anyhow::anyhow!("No active process found"))
+            RecorderMode::Service { .. } => {
+                // In service mode, kill process by PID
+                kill_pid(pid).await;
+            }
         }
+        Ok(())
     }
 
     pub async fn get_task_type(&self, user_id: Uuid) -> Option<String> {
@@ -140,6 +155,136 @@ impl RecorderManager {
         let processes = self.processes.read().await;
         !processes.is_empty()
     }
+
+    /// Forget processes that exited without being stopped and return their users.
+    pub async fn reap_exited(&self) -> Vec<Uuid> {
+        // Agent-started processes are checked with a subprocess, so collect their PIDs and
+        // check them before taking the write lock.
+        let service_pids: Vec<(Uuid, u32)> = self
+            .processes
+            .read()
+            .await
+            .iter()
+            .filter(|(_, (child_opt, _, _))| child_opt.is_none())
+            .map(|(user_id, (_, pid, _))| (*user_id, *pid))
+            .collect();
+        let mut dead_pids = HashMap::new();
+        for (user_id, pid) in service_pids {
+            if !process_alive(pid).await {
+                dead_pids.insert(user_id, pid);
+            }
+        }
+
+        let mut processes = self.processes.write().await;
+        let mut exited = Vec::new();
+        for (user_id, (child_opt, pid, _)) in processes.iter_mut() {
+            let alive = match child_opt {
+                Some(child) => matches!(child.try_wait(), Ok(None)),
+                // Only trust the check if the entry wasn't replaced in the meantime
+                None => dead_pids.get(user_id) != Some(pid),
+            };
+            if !alive {
+                exited.push(*user_id);
+            }
+        }
+        for user_id in &exited {
+            processes.remove(user_id);
+        }
+        exited
+    }
+}
+
+async fn kill_pid(pid: u32) {
+    #[cfg(windows)]
+    {
+        let _ = Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/F"])
+            .output()
+            .await;
+    }
+    #[cfg(unix)]
+    {
+        let _ = Command::new("kill")
+            .args(["-9", &pid.to_string()])
+            .output()
+            .await;
+    }
+}
+
+/// Whether a process started by the agent is still running.
+async fn process_alive(pid: u32) -> bool {
+    #[cfg(windows)]
+    {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .await
+            .map(|o| String::from_utf8_lossy(&o.stdout).contains(&pid.to_string()))
+            .unwrap_or(true)
+    }
+    #[cfg(unix)]
+    {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .output()
+            .await
+            .map(|o| o.status.success())
+            .unwrap_or(true)
+    }
+}
+
+fn spawn_output_reader<R>(reader: R, pid: u32, tx: mpsc::UnboundedSender<String>)
+where
+    R: AsyncRead + Unpin + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(reader).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            tracing::debug!("cli-capture[{}]: {}", pid, line);
+            let _ = tx.send(line);
+        }
+    });
+}
+
+/// cli-capture could not open the chosen video encoder, so another encoder may still work.
+#[derive(Debug)]
+pub struct EncoderInitError(pub String);
+
+impl std::fmt::Display for EncoderInitError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Encoder failed to initialise: {}", self.0)
+    }
+}
+
+impl std::error::Error for EncoderInitError {}
+
+/// Wait for cli-capture to report that output started. A failure to open the encoder comes
+/// back as an `EncoderInitError` so callers can fall back to the next encoder.
+async fn watch_startup(mut rx: mpsc::UnboundedReceiver<String>) -> Result<()> {
+    let deadline = tokio::time::Instant::now() + STARTUP_WATCH;
+    let mut recent: Vec<String> = Vec::new();
+    loop {
+        let line = match tokio::time::timeout_at(deadline, rx.recv()).await {
+            Ok(Some(line)) => line,
+            Ok(None) => return Err(anyhow::anyhow!("CLI exited during startup: {}", recent.join("; "))),
+            // No verdict yet; the process is still alive so treat it as running.
+            Err(_) => return Ok(()),
+        };
+        if line.starts_with("Capture started") {
+            return Ok(());
+        }
+        if let Some(detail) = line.strip_prefix("Failed to initialise encoder:") {
+            return Err(EncoderInitError(detail.trim().to_string()).into());
+        }
+        // An encoder cli-capture doesn't know is replaced with obs_x264 and capture carries on
+        if line.starts_with("Failed to create video encoder") {
+            tracing::warn!("{}", line);
+        }
+        recent.push(line);
+        if recent.len() > 5 {
+            recent.remove(0);
+        }
+    }
 }
 
 async fn validate_cli_path(cli_path: &str) -> Result<()> {
@@ -168,3 +313,46 @@ fn is_service_mode() -> bool {
     std::env::args().any(|arg| arg == "--service")
         || std::env::var("RUN_AS_SERVICE").map(|v| v == "1").unwrap_or(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn watch(lines: &[&str], exits: bool) -> Result<()> {
+        let (tx, rx) = mpsc::unbounded_channel();
+        for line in lines {
+            tx.send(line.to_string()).unwrap();
+        }
+        // Without an exit the sender stays open, as it does while the process runs
+        let _running = (!exits).then_some(tx);
+        watch_startup(rx).await
+    }
+
+    #[tokio::test]
+    async fn capture_started_is_success() {
+        assert!(watch(&["Loading plugins", "Capture started. Output: a.mp4"], false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn cli_fallback_to_x264_is_not_fatal() {
+        let lines = [
+            "Failed to create video encoder: jim_nvenc, falling back to obs_x264",
+            "Capture started. Output: a.mp4",
+        ];
+        assert!(watch(&lines, false).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn encoder_open_failure_is_typed() {
+        let err = watch(&["Failed to initialise encoder: jim_nvenc: NVENC not available"], false).await.unwrap_err();
+        let init = err.downcast_ref::<EncoderInitError>().expect("encoder error");
+        assert_eq!(init.0, "jim_nvenc: NVENC not available");
+    }
+
+    #[tokio::test]
+    async fn output_failure_is_not_an_encoder_error() {
+        let err = watch(&["Failed to start output: Could not open file"], true).await.unwrap_err();
+        assert!(!err.is::<EncoderInitError>());
+        assert!(err.to_string().contains("Could not open file"));
+    }
+}
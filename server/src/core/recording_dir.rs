@@ -0,0 +1,55 @@
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Extensions cli-capture (or OBS) can produce; anything else in the directory is ignored.
+pub const RECORDING_EXTENSIONS: [&str; 6] = ["mp4", "mkv", "flv", "mov", "ts", "m4v"];
+
+#[derive(Debug, Clone)]
+pub struct DiskFile {
+    /// Path relative to the recording directory, `/`-separated.
+    pub relative: String,
+    pub modified: Option<SystemTime>,
+}
+
+pub fn is_recording_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| RECORDING_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Walk the recording directory recursively, skipping hidden entries.
+pub fn list_recording_files(base: &Path) -> std::io::Result<Vec<DiskFile>> {
+    let mut files = Vec::new();
+    let mut pending = vec![base.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            if name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let path = entry.path();
+            let meta = entry.metadata()?;
+            if meta.is_dir() {
+                pending.push(path);
+            } else if meta.is_file() && is_recording_file(&path) {
+                let relative = path
+                    .strip_prefix(base)
+                    .map(normalize_relative)
+                    .unwrap_or_else(|_| name.to_string_lossy().to_string());
+                files.push(DiskFile { relative, modified: meta.modified().ok() });
+            }
+        }
+    }
+    files.sort_by(|a, b| a.relative.cmp(&b.relative));
+    Ok(files)
+}
+
+/// Normalise a relative path so rows written on Windows and Unix compare equal.
+pub fn normalize_relative(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
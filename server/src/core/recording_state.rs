@@ -0,0 +1,263 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::path::Path;
+use std::time::Duration;
+
+/// `recordings.status`. Every change goes through `can_transition_to` and is logged in
+/// `recording_status_history`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordingStatus {
+    /// Row created, cli-capture not confirmed running yet.
+    Pending,
+    Recording,
+    /// The process was stopped or exited; waiting for the file to settle and parse.
+    Finalizing,
+    Saved,
+    /// The process never produced a usable file.
+    Failed,
+    /// The file exists but its container index is missing or truncated.
+    Incomplete,
+    /// Soft-deleted into the recycle bin.
+    Deleted,
+    /// Reconcile could not find the file on disk.
+    Missing,
+    /// A finished tus upload; settled like `saved`, but kept apart so uploads stay identifiable.
+    Uploaded,
+}
+
+/// SQL list of the statuses whose file is still being written, for `status IN ...` filters.
+pub const IN_PROGRESS_SQL: &str = "('pending', 'recording', 'finalizing')";
+
+impl RecordingStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Recording => "recording",
+            Self::Finalizing => "finalizing",
+            Self::Saved => "saved",
+            Self::Failed => "failed",
+            Self::Incomplete => "incomplete",
+            Self::Deleted => "deleted",
+            Self::Missing => "missing",
+            Self::Uploaded => "uploaded",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Some(match value {
+            "pending" => Self::Pending,
+            "recording" => Self::Recording,
+            "finalizing" => Self::Finalizing,
+            "saved" => Self::Saved,
+            "failed" => Self::Failed,
+            "incomplete" => Self::Incomplete,
+            "deleted" => Self::Deleted,
+            "missing" => Self::Missing,
+            "uploaded" => Self::Uploaded,
+            _ => return None,
+        })
+    }
+
+    /// The file is still being written, so it must not be moved, cut or served.
+    pub fn in_progress(self) -> bool {
+        matches!(self, Self::Pending | Self::Recording | Self::Finalizing)
+    }
+
+    pub fn can_transition_to(self, to: Self) -> bool {
+        use RecordingStatus::*;
+        match (self, to) {
+            (Pending, Recording | Failed) => true,
+            (Recording, Finalizing | Failed) => true,
+            (Finalizing, Saved | Incomplete | Failed | Uploaded) => true,
+            // Re-probing after a repair or remux can change the verdict either way
+            (Saved, Incomplete) | (Incomplete, Saved) => true,
+            (Saved | Incomplete | Failed | Uploaded, Missing | Deleted) => true,
+            (Missing, Saved | Incomplete | Failed | Uploaded | Deleted) => true,
+            (Deleted, Saved | Incomplete | Failed | Uploaded | Missing) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Whether a raw status column value belongs to a recording that is still being written.
+pub fn is_in_progress(status: &str) -> bool {
+    RecordingStatus::parse(status).is_some_and(|s| s.in_progress())
+}
+
+/// The status a finished file settles into: uploads stay `uploaded` whatever the probe says
+/// (`is_complete` still records it); otherwise parseable files are saved and files that parse
+/// but lack their index are incomplete.
+pub fn finalized_status(complete: bool, upload: bool) -> RecordingStatus {
+    if upload {
+        RecordingStatus::Uploaded
+    } else if complete {
+        RecordingStatus::Saved
+    } else {
+        RecordingStatus::Incomplete
+    }
+}
+
+/// How often the size of a finalizing file is sampled.
+pub const STABILITY_POLL: Duration = Duration::from_secs(1);
+/// Consecutive unchanged samples needed before the file counts as settled.
+pub const STABLE_SAMPLES: u32 = 3;
+/// Give up waiting after this long and judge the file as it is.
+pub const STABILITY_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// How often a reader waiting on a finalizing recording re-reads its status.
+pub const SETTLE_POLL: Duration = Duration::from_millis(500);
+
+/// Poll `status` until the recording is no longer finalizing or `timeout` passes, returning
+/// the last status seen (None if the recording is gone).
+pub async fn wait_while_finalizing<F, Fut>(mut status: F, poll: Duration, timeout: Duration) -> Option<RecordingStatus>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Option<RecordingStatus>>,
+{
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        let current = status().await;
+        if current != Some(RecordingStatus::Finalizing) || tokio::time::Instant::now() >= deadline {
+            return current;
+        }
+        tokio::time::sleep(poll).await;
+    }
+}
+
+/// Wait until the file's size and modification time stop changing, returning its final size.
+/// Returns Ok(None) if the file does not exist.
+pub async fn wait_until_stable(path: &Path) -> std::io::Result<Option<u64>> {
+    let deadline = tokio::time::Instant::now() + STABILITY_TIMEOUT;
+    let mut last = None;
+    let mut unchanged = 0;
+    loop {
+        let meta = match tokio::fs::metadata(path).await {
+            Ok(m) => m,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let sample = (meta.len(), meta.modified().ok());
+        if last == Some(sample) {
+            unchanged += 1;
+            if unchanged >= STABLE_SAMPLES {
+                return Ok(Some(sample.0));
+            }
+        } else {
+            unchanged = 0;
+            last = Some(sample);
+        }
+        if tokio::time::Instant::now() >= deadline {
+            return Ok(Some(sample.0));
+        }
+        tokio::time::sleep(STABILITY_POLL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    const POLL: Duration = Duration::from_millis(1);
+
+    const ALL: [RecordingStatus; 9] = [
+        RecordingStatus::Pending,
+        RecordingStatus::Recording,
+        RecordingStatus::Finalizing,
+        RecordingStatus::Saved,
+        RecordingStatus::Failed,
+        RecordingStatus::Incomplete,
+        RecordingStatus::Deleted,
+        RecordingStatus::Missing,
+        RecordingStatus::Uploaded,
+    ];
+
+    #[test]
+    fn transition_table() {
+        use RecordingStatus::*;
+        let cases = [
+            (Pending, Recording, true),
+            (Pending, Failed, true),
+            (Pending, Saved, false),
+            (Recording, Finalizing, true),
+            (Recording, Failed, true),
+            // A live recording can't be trashed, lost or saved without finalizing first
+            (Recording, Deleted, false),
+            (Recording, Missing, false),
+            (Recording, Saved, false),
+            (Finalizing, Saved, true),
+            (Finalizing, Incomplete, true),
+            (Finalizing, Uploaded, true),
+            (Finalizing, Deleted, false),
+            (Saved, Incomplete, true),
+            (Incomplete, Saved, true),
+            (Saved, Deleted, true),
+            (Uploaded, Missing, true),
+            (Uploaded, Saved, false),
+            // Restoring from the bin or finding the file again goes back to where it was
+            (Deleted, Saved, true),
+            (Deleted, Incomplete, true),
+            (Deleted, Failed, true),
+            (Deleted, Uploaded, true),
+            (Missing, Saved, true),
+            (Missing, Uploaded, true),
+            (Missing, Deleted, true),
+            (Deleted, Missing, true),
+            // Nothing finished goes back to being written
+            (Saved, Recording, false),
+            (Deleted, Recording, false),
+            (Missing, Finalizing, false),
+            (Failed, Pending, false),
+        ];
+        for (from, to, allowed) in cases {
+            assert_eq!(from.can_transition_to(to), allowed, "{:?} -> {:?}", from, to);
+        }
+        for status in ALL {
+            assert!(!status.can_transition_to(status), "{:?} -> itself", status);
+            assert_eq!(RecordingStatus::parse(status.as_str()), Some(status));
+        }
+    }
+
+    // A phone scanning the hand-off QR right after stop sees the recording finalizing for a
+    // few polls before it settles.
+    #[tokio::test]
+    async fn scan_right_after_stop_waits_for_the_recording_to_settle() {
+        let polls = AtomicUsize::new(0);
+        let status = wait_while_finalizing(
+            || {
+                let n = polls.fetch_add(1, Ordering::SeqCst);
+                async move { Some(if n < 3 { RecordingStatus::Finalizing } else { RecordingStatus::Saved }) }
+            },
+            POLL,
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(status, Some(RecordingStatus::Saved));
+        assert_eq!(polls.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn waiting_gives_up_after_the_timeout() {
+        let status = wait_while_finalizing(|| async { Some(RecordingStatus::Finalizing) }, POLL, Duration::from_millis(20)).await;
+        assert_eq!(status, Some(RecordingStatus::Finalizing));
+    }
+
+    #[tokio::test]
+    async fn other_statuses_return_at_once() {
+        for current in [Some(RecordingStatus::Recording), Some(RecordingStatus::Failed), None] {
+            let polls = AtomicUsize::new(0);
+            let status = wait_while_finalizing(
+                || {
+                    polls.fetch_add(1, Ordering::SeqCst);
+                    async move { current }
+                },
+                POLL,
+                Duration::from_secs(5),
+            )
+            .await;
+            assert_eq!(status, current);
+            assert_eq!(polls.load(Ordering::SeqCst), 1);
+        }
+    }
+}
@@ -0,0 +1,215 @@
+use serde::{Deserialize, Serialize};
+use crate::core::hardware::Device;
+
+/// How a requested output size is reconciled with the source monitor's shape.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ScaleMode {
+    /// Keep the source aspect ratio and fit inside the requested box, never upscaling.
+    #[default]
+    Fit,
+    /// Keep the source aspect ratio and cover the requested box.
+    Fill,
+    /// Use the requested size as-is, even if it stretches the source.
+    Exact,
+}
+
+impl ScaleMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "" | "fit" => Some(ScaleMode::Fit),
+            "fill" => Some(ScaleMode::Fill),
+            "exact" => Some(ScaleMode::Exact),
+            _ => None,
+        }
+    }
+}
+
+/// Parse `WIDTHxHEIGHT` or one of the common labels (480p, 720p, 1080p, 1440p, 4k).
+pub fn parse_dims(value: &str) -> Option<(i32, i32)> {
+    let v = value.trim().to_lowercase();
+    let label = match v.as_str() {
+        "480p" => Some((854, 480)),
+        "720p" => Some((1280, 720)),
+        "1080p" => Some((1920, 1080)),
+        "1440p" => Some((2560, 1440)),
+        "4k" | "2160p" => Some((3840, 2160)),
+        _ => None,
+    };
+    if label.is_some() {
+        return label;
+    }
+    let (w, h) = v.split_once('x')?;
+    let w = w.trim().parse::<i32>().ok()?;
+    let h = h.trim().parse::<i32>().ok()?;
+    if w > 0 && h > 0 { Some((w, h)) } else { None }
+}
+
+pub fn pixel_count((w, h): (i32, i32)) -> i64 {
+    w as i64 * h as i64
+}
+
+/// Pull the size out of a scanned screen name like `Display 1: 2560x1080 @ 0,0 (Primary Monitor)`.
+pub fn screen_dims(name: &str) -> Option<(i32, i32)> {
+    let after = name.split_once(':').map(|(_, rest)| rest).unwrap_or(name);
+    after.split_whitespace().find_map(parse_dims)
+}
+
+/// Size of the monitor a capture will use: the configured one, otherwise the primary (or first) screen.
+pub fn monitor_dims(screens: &[Device], monitor_id: &str) -> Option<(i32, i32)> {
+    let screen = if monitor_id.is_empty() {
+        screens.iter().find(|s| s.name.contains("Primary")).or_else(|| screens.first())
+    } else {
+        screens.iter().find(|s| s.id == monitor_id)
+    }?;
+    screen_dims(&screen.name)
+}
+
+/// Apply the scale mode to the request, before any pixel limit.
+pub fn shape(requested: Option<(i32, i32)>, source: Option<(i32, i32)>, mode: ScaleMode) -> Option<(i32, i32)> {
+    let (source, (rw, rh)) = match (source, requested) {
+        (Some(s), Some(r)) if mode != ScaleMode::Exact => (s, r),
+        (_, Some(r)) => return Some(r),
+        (s, None) => return s,
+    };
+    let (sw, sh) = (source.0 as f64, source.1 as f64);
+    let scale_w = rw as f64 / sw;
+    let scale_h = rh as f64 / sh;
+    let scale = match mode {
+        ScaleMode::Fill => scale_w.max(scale_h),
+        _ => scale_w.min(scale_h).min(1.0),
+    };
+    Some(((sw * scale).floor() as i32, (sh * scale).floor() as i32))
+}
+
+/// Scale down uniformly until the size fits in `max_pixels`, keeping the aspect ratio.
+/// Both sides are rounded down to even numbers since encoders reject odd dimensions.
+pub fn fit_pixel_budget((w, h): (i32, i32), max_pixels: i64) -> (i32, i32) {
+    let (mut w, mut h) = (w.max(2), h.max(2));
+    let pixels = pixel_count((w, h));
+    if max_pixels > 0 && pixels > max_pixels {
+        let factor = (max_pixels as f64 / pixels as f64).sqrt();
+        w = (w as f64 * factor).floor() as i32;
+        h = (h as f64 * factor).floor() as i32;
+    }
+    (even(w), even(h))
+}
+
+/// Resolve the output size: shape the request against the source, then enforce the limit's pixel count.
+pub fn resolve(
+    requested: Option<(i32, i32)>,
+    source: Option<(i32, i32)>,
+    limit: (i32, i32),
+    mode: ScaleMode,
+) -> (i32, i32) {
+    let shaped = shape(requested, source, mode).unwrap_or(limit);
+    fit_pixel_budget(shaped, pixel_count(limit))
+}
+
+fn even(v: i32) -> i32 {
+    (v / 2 * 2).max(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const P1080: (i32, i32) = (1920, 1080);
+    const P720: (i32, i32) = (1280, 720);
+
+    type Dims = (i32, i32);
+    // (requested, source, limit, mode, expected)
+    type ResolveCase = (Option<Dims>, Option<Dims>, Dims, ScaleMode, Dims);
+
+    #[test]
+    fn resolve_table() {
+        use ScaleMode::*;
+        let cases: &[ResolveCase] = &[
+            // Source within the budget is used as-is
+            (None, Some(P1080), P1080, Fit, P1080),
+            // Ultrawide keeps 64:27 instead of being squashed to 16:9
+            (None, Some((2560, 1080)), P1080, Fit, (2216, 934)),
+            // A 2560x1080 request fits under a 1440p pixel budget untouched
+            (Some((2560, 1080)), Some((2560, 1080)), (2560, 1440), Fit, (2560, 1080)),
+            // 4K source halves to 1080p
+            (None, Some((3840, 2160)), P1080, Fit, P1080),
+            // Portrait source under a landscape limit keeps its orientation
+            (None, Some((1080, 1920)), P1080, Fit, (1080, 1920)),
+            // Landscape source under a portrait limit uses the same pixel count
+            (None, Some((2560, 1440)), (1080, 1920), Fit, P1080),
+            // Fit shrinks into the requested box with the source's shape
+            (Some(P720), Some((2560, 1080)), P1080, Fit, (1280, 540)),
+            // Fit never upscales past the source
+            (Some(P1080), Some(P720), P1080, Fit, P720),
+            // Fill covers the requested box
+            (Some(P720), Some((2560, 1080)), P1080, Fill, (1706, 720)),
+            // Fill is still bound by the pixel budget
+            (Some(P1080), Some((2560, 1080)), P1080, Fill, (2216, 934)),
+            // Exact ignores the source shape
+            (Some(P720), Some((2560, 1080)), P1080, Exact, P720),
+            // Exact over budget scales down in the requested shape
+            (Some((2560, 1440)), Some((2560, 1080)), P1080, Exact, P1080),
+            // Odd sources round down to even sides
+            (None, Some((1366, 768)), P720, Fit, (1280, 718)),
+            (Some((1279, 719)), None, P1080, Exact, (1278, 718)),
+            // Without a request, no source falls back to the limit itself
+            (None, None, P720, Fit, P720),
+            (Some(P720), None, P1080, Fit, P720),
+        ];
+
+        for (i, &(requested, source, limit, mode, expected)) in cases.iter().enumerate() {
+            let got = resolve(requested, source, limit, mode);
+            assert_eq!(got, expected, "case {}: {:?} from {:?} limit {:?} {:?}", i, requested, source, limit, mode);
+            assert!(pixel_count(got) <= pixel_count(limit), "case {} exceeds budget", i);
+        }
+    }
+
+    #[test]
+    fn parse_dims_table() {
+        let cases = [
+            ("1920x1080", Some(P1080)),
+            (" 1280 x 720 ", Some(P720)),
+            ("1080p", Some(P1080)),
+            ("4K", Some((3840, 2160))),
+            ("1440p", Some((2560, 1440))),
+            ("0x1080", None),
+            ("widescreen", None),
+            ("", None),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_dims(input), expected, "input {:?}", input);
+        }
+    }
+
+    #[test]
+    fn screen_dims_from_scan_names() {
+        let cases = [
+            ("Display 1: 2560x1080 @ 0,0 (Primary Monitor)", Some((2560, 1080))),
+            ("Display 2: 1080x1920 @ 2560,0", Some((1080, 1920))),
+            ("Display 3", None),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(screen_dims(name), expected, "name {:?}", name);
+        }
+    }
+
+    #[test]
+    fn monitor_dims_prefers_configured_then_primary() {
+        let screens = vec![
+            Device { id: "a".into(), name: "Display 1: 1280x720 @ 0,0".into(), index: Some(0) },
+            Device { id: "b".into(), name: "Display 2: 2560x1080 @ 1280,0 (Primary Monitor)".into(), index: Some(1) },
+        ];
+        assert_eq!(monitor_dims(&screens, "a"), Some(P720));
+        assert_eq!(monitor_dims(&screens, ""), Some((2560, 1080)));
+        assert_eq!(monitor_dims(&screens, "missing"), None);
+        assert_eq!(monitor_dims(&[], ""), None);
+    }
+
+    #[test]
+    fn scale_mode_parse() {
+        assert_eq!(ScaleMode::parse(""), Some(ScaleMode::Fit));
+        assert_eq!(ScaleMode::parse("Fill"), Some(ScaleMode::Fill));
+        assert_eq!(ScaleMode::parse("exact"), Some(ScaleMode::Exact));
+        assert_eq!(ScaleMode::parse("stretch"), None);
+    }
+}
@@ -0,0 +1,214 @@
+use std::collections::{HashMap, HashSet};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+pub const DEFAULT_CLEANUP_INTERVAL_MINUTES: u64 = 60;
+pub const DEFAULT_TRASH_RETENTION_DAYS: u32 = 30;
+
+/// Admin retention rules. Every rule is optional; unset rules never delete anything.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct RetentionPolicy {
+    #[serde(default)]
+    pub max_age_days: Option<u32>,
+    #[serde(default)]
+    pub max_total_bytes: Option<i64>,
+    #[serde(default)]
+    pub keep_newest_per_user: Option<u32>,
+    /// How often the scheduled cleanup runs; 0 disables it.
+    #[serde(default = "default_interval")]
+    pub interval_minutes: u64,
+    /// Days a deleted recording stays in the recycle bin; 0 keeps it until emptied by hand.
+    #[serde(default = "default_trash_days")]
+    pub trash_retention_days: u32,
+}
+
+fn default_interval() -> u64 {
+    DEFAULT_CLEANUP_INTERVAL_MINUTES
+}
+
+fn default_trash_days() -> u32 {
+    DEFAULT_TRASH_RETENTION_DAYS
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age_days: None,
+            max_total_bytes: None,
+            keep_newest_per_user: None,
+            interval_minutes: DEFAULT_CLEANUP_INTERVAL_MINUTES,
+            trash_retention_days: DEFAULT_TRASH_RETENTION_DAYS,
+        }
+    }
+}
+
+/// A finished recording as seen by the cleanup.
+#[derive(Debug, Clone)]
+pub struct RetentionCandidate {
+    pub id: Uuid,
+    pub user_id: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: i64,
+    pub pinned: bool,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RetentionReason {
+    MaxAge,
+    KeepNewest,
+    MaxTotalSize,
+    /// Sat in the recycle bin longer than `trash_retention_days`.
+    TrashExpired,
+}
+
+/// Pick the recordings the policy removes. Pinned recordings are never picked and
+/// don't count against keep-N, but their size still counts toward the total.
+pub fn select_expired(
+    policy: &RetentionPolicy,
+    recordings: &[RetentionCandidate],
+    now: DateTime<Utc>,
+) -> Vec<(Uuid, RetentionReason)> {
+    let mut selected: Vec<(Uuid, RetentionReason)> = Vec::new();
+    let mut taken: HashSet<Uuid> = HashSet::new();
+
+    let mut oldest_first: Vec<&RetentionCandidate> = recordings.iter().collect();
+    oldest_first.sort_by_key(|r| r.created_at);
+
+    if let Some(days) = policy.max_age_days {
+        let cutoff = now - Duration::days(days as i64);
+        for r in oldest_first.iter().filter(|r| !r.pinned && r.created_at < cutoff) {
+            if taken.insert(r.id) {
+                selected.push((r.id, RetentionReason::MaxAge));
+            }
+        }
+    }
+
+    if let Some(keep) = policy.keep_newest_per_user {
+        let mut per_user: HashMap<Option<Uuid>, Vec<&RetentionCandidate>> = HashMap::new();
+        for r in oldest_first.iter().filter(|r| !r.pinned) {
+            per_user.entry(r.user_id).or_default().push(r);
+        }
+        for list in per_user.values() {
+            let excess = list.len().saturating_sub(keep as usize);
+            for r in list.iter().take(excess) {
+                if taken.insert(r.id) {
+                    selected.push((r.id, RetentionReason::KeepNewest));
+                }
+            }
+        }
+    }
+
+    if let Some(max_total) = policy.max_total_bytes {
+        let mut total: i64 = recordings
+            .iter()
+            .filter(|r| !taken.contains(&r.id))
+            .map(|r| r.size_bytes.max(0))
+            .sum();
+        for r in oldest_first.iter().filter(|r| !r.pinned) {
+            if total <= max_total {
+                break;
+            }
+            if taken.insert(r.id) {
+                total -= r.size_bytes.max(0);
+                selected.push((r.id, RetentionReason::MaxTotalSize));
+            }
+        }
+    }
+
+    selected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use RetentionReason::*;
+
+    const A: Option<u128> = Some(0xa);
+    const B: Option<u128> = Some(0xb);
+
+    // (id, user, age in days, size, pinned)
+    type Rec = (u128, Option<u128>, i64, i64, bool);
+    // (policy, recordings, expected removals)
+    type ExpireCase = (RetentionPolicy, Vec<Rec>, Vec<(u128, RetentionReason)>);
+
+    fn policy(max_age_days: Option<u32>, max_total_bytes: Option<i64>, keep_newest_per_user: Option<u32>) -> RetentionPolicy {
+        RetentionPolicy { max_age_days, max_total_bytes, keep_newest_per_user, ..Default::default() }
+    }
+
+    fn candidates(recs: &[Rec], now: DateTime<Utc>) -> Vec<RetentionCandidate> {
+        recs.iter()
+            .map(|&(id, user, age, size, pinned)| RetentionCandidate {
+                id: Uuid::from_u128(id),
+                user_id: user.map(Uuid::from_u128),
+                created_at: now - Duration::days(age),
+                size_bytes: size,
+                pinned,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn select_expired_table() {
+        let now = Utc::now();
+        let cases: Vec<ExpireCase> = vec![
+            // No rules, nothing goes
+            (policy(None, None, None), vec![(1, A, 400, 100, false)], vec![]),
+            // Max age skips pinned rows however old they are
+            (
+                policy(Some(30), None, None),
+                vec![(1, A, 40, 100, false), (2, A, 20, 100, false), (3, A, 50, 100, true)],
+                vec![(1, MaxAge)],
+            ),
+            // Keep-N is per user, unowned rows form their own group, and pinned rows don't count
+            (
+                policy(None, None, Some(1)),
+                vec![
+                    (1, A, 3, 100, false),
+                    (2, A, 2, 100, false),
+                    (3, A, 1, 100, false),
+                    (4, B, 5, 100, false),
+                    (5, None, 4, 100, false),
+                    (6, None, 2, 100, false),
+                    (7, A, 10, 100, true),
+                ],
+                vec![(1, KeepNewest), (2, KeepNewest), (5, KeepNewest)],
+            ),
+            // Total size deletes oldest first and stops once under the limit;
+            // the pinned row is kept but its size still counts
+            (
+                policy(None, Some(250), None),
+                vec![(1, A, 4, 100, false), (2, A, 3, 100, false), (3, A, 2, 100, false), (4, A, 5, 100, true)],
+                vec![(1, MaxTotalSize), (2, MaxTotalSize)],
+            ),
+            // Pinned rows alone over the limit are still never picked
+            (
+                policy(None, Some(300), None),
+                vec![(1, A, 10, 500, true), (2, A, 1, 100, false)],
+                vec![(2, MaxTotalSize)],
+            ),
+            // Rows already removed by age no longer count toward the total
+            (
+                policy(Some(30), Some(200), None),
+                vec![(1, A, 40, 100, false), (2, A, 5, 100, false), (3, A, 1, 100, false)],
+                vec![(1, MaxAge)],
+            ),
+            // A row is reported once, under the first rule that picked it
+            (
+                policy(Some(30), None, Some(1)),
+                vec![(1, A, 40, 100, false), (2, A, 20, 100, false), (3, A, 1, 100, false)],
+                vec![(1, MaxAge), (2, KeepNewest)],
+            ),
+        ];
+
+        for (i, (policy, recs, expected)) in cases.iter().enumerate() {
+            let mut got = select_expired(policy, &candidates(recs, now), now);
+            // Keep-N walks users in hash order
+            got.sort_by_key(|(id, _)| *id);
+            let expected: Vec<(Uuid, RetentionReason)> =
+                expected.iter().map(|&(id, reason)| (Uuid::from_u128(id), reason)).collect();
+            assert_eq!(got, expected, "case {}", i);
+        }
+    }
+}
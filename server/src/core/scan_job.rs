@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{oneshot, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+
+// Finished jobs are kept around so clients can still poll them for a while.
+const MAX_FINISHED_JOBS: usize = 20;
+
+#[derive(Clone, Debug, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanJobState {
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    TimedOut,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct ScanJob {
+    pub id: Uuid,
+    pub kind: String,
+    pub state: ScanJobState,
+    pub requested_by: Uuid,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    pub timeout_secs: u64,
+    pub error: Option<String>,
+    pub result: Option<serde_json::Value>,
+}
+
+/// Tracks cli-capture runs that load OBS (hardware scans, preflight tests).
+/// Only one such run may be active at a time since each one initialises the encoders.
+pub struct ScanJobManager {
+    jobs: RwLock<HashMap<Uuid, ScanJob>>,
+    active: Mutex<Option<(Uuid, oneshot::Sender<()>)>>,
+}
+
+impl ScanJobManager {
+    pub fn new() -> Self {
+        Self {
+            jobs: RwLock::new(HashMap::new()),
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Start a job unless another one is running. The returned handle resolves to the
+    /// final job snapshot once it completes, fails, times out or is cancelled.
+    pub async fn start<F>(
+        self: &std::sync::Arc<Self>,
+        kind: &str,
+        requested_by: Uuid,
+        timeout: Duration,
+        work: F,
+    ) -> anyhow::Result<(ScanJob, JoinHandle<ScanJob>)>
+    where
+        F: Future<Output = anyhow::Result<serde_json::Value>> + Send + 'static,
+    {
+        let mut active = self.active.lock().await;
+        if let Some((active_id, _)) = active.as_ref() {
+            return Err(anyhow::anyhow!("Scan already in progress ({})", active_id));
+        }
+
+        let job = ScanJob {
+            id: Uuid::new_v4(),
+            kind: kind.to_string(),
+            state: ScanJobState::Running,
+            requested_by,
+            started_at: Utc::now(),
+            finished_at: None,
+            timeout_secs: timeout.as_secs(),
+            error: None,
+            result: None,
+        };
+        let (cancel_tx, cancel_rx) = oneshot::channel();
+        *active = Some((job.id, cancel_tx));
+        drop(active);
+
+        {
+            let mut jobs = self.jobs.write().await;
+            prune_finished(&mut jobs);
+            jobs.insert(job.id, job.clone());
+        }
+
+        let manager = self.clone();
+        let job_id = job.id;
+        let handle = tokio::spawn(async move {
+            let (state, result, error) = tokio::select! {
+                res = tokio::time::timeout(timeout, work) => match res {
+                    Ok(Ok(value)) => (ScanJobState::Completed, Some(value), None),
+                    Ok(Err(e)) => (ScanJobState::Failed, None, Some(e.to_string())),
+                    Err(_) => (
+                        ScanJobState::TimedOut,
+                        None,
+                        Some(format!("Scan timed out after {}s", timeout.as_secs())),
+                    ),
+                },
+                _ = cancel_rx => (ScanJobState::Cancelled, None, Some("Scan cancelled".to_string())),
+            };
+            manager.finish(job_id, state, result, error).await
+        });
+
+        Ok((job, handle))
+    }
+
+    async fn finish(
+        &self,
+        id: Uuid,
+        state: ScanJobState,
+        result: Option<serde_json::Value>,
+        error: Option<String>,
+    ) -> ScanJob {
+        {
+            let mut active = self.active.lock().await;
+            if active.as_ref().map(|(active_id, _)| *active_id == id).unwrap_or(false) {
+                *active = None;
+            }
+        }
+        let mut jobs = self.jobs.write().await;
+        let job = jobs.get_mut(&id).expect("scan job registered at start");
+        job.state = state;
+        job.result = result;
+        job.error = error;
+        job.finished_at = Some(Utc::now());
+        job.clone()
+    }
+
+    pub async fn get(&self, id: Uuid) -> Option<ScanJob> {
+        self.jobs.read().await.get(&id).cloned()
+    }
+
+    pub async fn list(&self) -> Vec<ScanJob> {
+        let jobs = self.jobs.read().await;
+        let mut list: Vec<ScanJob> = jobs.values().cloned().collect();
+        list.sort_by_key(|j| std::cmp::Reverse(j.started_at));
+        list
+    }
+
+    pub async fn is_running(&self) -> bool {
+        self.active.lock().await.is_some()
+    }
+
+    /// Signal the active job to stop. Returns false if the job is not the running one.
+    pub async fn cancel(&self, id: Uuid) -> bool {
+        let mut active = self.active.lock().await;
+        match active.take() {
+            Some((active_id, cancel_tx)) if active_id == id => {
+                let _ = cancel_tx.send(());
+                true
+            }
+            other => {
+                *active = other;
+                false
+            }
+        }
+    }
+}
+
+fn prune_finished(jobs: &mut HashMap<Uuid, ScanJob>) {
+    let mut finished: Vec<(DateTime<Utc>, Uuid)> = jobs
+        .values()
+        .filter(|j| j.state != ScanJobState::Running)
+        .map(|j| (j.started_at, j.id))
+        .collect();
+    if finished.len() < MAX_FINISHED_JOBS {
+        return;
+    }
+    finished.sort();
+    let excess = finished.len() + 1 - MAX_FINISHED_JOBS;
+    for (_, id) in finished.into_iter().take(excess) {
+        jobs.remove(&id);
+    }
+}
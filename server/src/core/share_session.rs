@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::core::download::ByteRange;
+
+/// Range requests from one client against one share count as a single download until the
+/// client has been quiet for this long.
+pub const SESSION_IDLE: Duration = Duration::from_secs(5 * 60);
+/// A ranged request spanning at least this fraction of the file fetches the whole thing.
+pub const FULL_FETCH_RATIO: f64 = 0.9;
+
+struct Session {
+    last_seen: Instant,
+    full_fetches: u32,
+}
+
+/// Tracks who is currently reading each share link so `max_downloads` counts downloads,
+/// not HTTP requests.
+#[derive(Default)]
+pub struct ShareSessions {
+    sessions: HashMap<(String, String), Session>,
+}
+
+impl ShareSessions {
+    /// Record a request for `share` from `client` and decide whether it counts as a download.
+    ///
+    /// Requests without a range always count. A ranged request counts when it opens a new
+    /// session; within a session, players seeking stay free, but every near-complete range
+    /// after the first one counts, so a client can't fetch the file repeatedly in pieces.
+    pub fn should_count(&mut self, share: &str, client: &str, range: &ByteRange, len: Option<u64>, now: Instant) -> bool {
+        self.sessions.retain(|_, s| now.duration_since(s.last_seen) < SESSION_IDLE);
+
+        let large = match range {
+            ByteRange::Full => true,
+            ByteRange::Partial { start, end } => {
+                len.is_some_and(|len| (end - start + 1) as f64 >= len as f64 * FULL_FETCH_RATIO)
+            }
+            // Nothing is served for an unsatisfiable range
+            ByteRange::Unsatisfiable => return false,
+        };
+
+        let key = (share.to_string(), client.to_string());
+        let Some(session) = self.sessions.get_mut(&key) else {
+            self.sessions.insert(key, Session { last_seen: now, full_fetches: u32::from(large) });
+            return true;
+        };
+        session.last_seen = now;
+        if matches!(range, ByteRange::Full) {
+            session.full_fetches += 1;
+            return true;
+        }
+        if !large {
+            return false;
+        }
+        session.full_fetches += 1;
+        session.full_fetches > 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEN: Option<u64> = Some(1000);
+    const SEEK: ByteRange = ByteRange::Partial { start: 500, end: 599 };
+    const WHOLE: ByteRange = ByteRange::Partial { start: 0, end: 999 };
+
+    #[test]
+    fn should_count_table() {
+        let t0 = Instant::now();
+        let later = |secs: u64| t0 + Duration::from_secs(secs);
+        // (share, client, range, len, seconds after start, counts)
+        let cases = [
+            // A player opening the file counts once, then seeks freely
+            ("s1", "phone", ByteRange::Partial { start: 0, end: 1 }, LEN, 0, true),
+            ("s1", "phone", SEEK, LEN, 10, false),
+            ("s1", "phone", SEEK, LEN, 20, false),
+            // Its first near-complete fetch is the same download; the next one is another
+            ("s1", "phone", WHOLE, LEN, 30, false),
+            ("s1", "phone", WHOLE, LEN, 40, true),
+            // Another client, or the same client on another share, is a new session
+            ("s1", "laptop", SEEK, LEN, 50, true),
+            ("s2", "phone", SEEK, LEN, 60, true),
+            // Plain requests always count
+            ("s1", "phone", ByteRange::Full, LEN, 70, true),
+            ("s1", "phone", ByteRange::Full, LEN, 80, true),
+            // Nothing is served for an unsatisfiable range, and no session is opened
+            ("s3", "phone", ByteRange::Unsatisfiable, LEN, 90, false),
+            ("s3", "phone", SEEK, LEN, 100, true),
+            // A session opened by a whole-file range already used up its first fetch
+            ("s4", "phone", WHOLE, LEN, 110, true),
+            ("s4", "phone", WHOLE, LEN, 120, true),
+            // Without a known length no range is large
+            ("s5", "phone", WHOLE, None, 130, true),
+            ("s5", "phone", WHOLE, None, 140, false),
+            // Going quiet for the idle window ends the session
+            ("s5", "phone", SEEK, LEN, 140 + SESSION_IDLE.as_secs(), true),
+        ];
+
+        let mut sessions = ShareSessions::default();
+        for (i, (share, client, range, len, at, expected)) in cases.iter().enumerate() {
+            let counted = sessions.should_count(share, client, range, *len, later(*at));
+            assert_eq!(counted, *expected, "case {}: {} {} {:?}", i, share, client, range);
+        }
+    }
+}
@@ -0,0 +1,236 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncSeekExt};
+
+use crate::core::path_template::validate_relative;
+use crate::core::s3::{S3Config, S3Storage};
+
+/// Body of an object being read back, already limited to the requested range.
+pub type ObjectReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Where finished recordings are archived. Keys are `/`-separated and relative.
+#[async_trait]
+pub trait StorageBackend: Send + Sync {
+    /// Stored in `recordings.archive_backend` so reads go to the backend that wrote the object.
+    fn kind(&self) -> StorageKind;
+    async fn put_file(&self, key: &str, path: &Path) -> anyhow::Result<()>;
+    /// Object size, or None when it doesn't exist.
+    async fn size(&self, key: &str) -> anyhow::Result<Option<u64>>;
+    /// Read the whole object, or the inclusive byte range `start..=end`.
+    async fn open(&self, key: &str, range: Option<(u64, u64)>) -> anyhow::Result<ObjectReader>;
+    async fn delete(&self, key: &str) -> anyhow::Result<()>;
+    /// A time-limited URL the client can fetch directly, for backends that have one.
+    fn presign_get(&self, _key: &str, _expires_secs: u64, _disposition: &str) -> Option<String> {
+        None
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum StorageKind {
+    /// Archival disabled; recordings stay on the capture PC only.
+    #[default]
+    None,
+    /// Another directory, typically a NAS mount.
+    Local,
+    S3,
+}
+
+impl StorageKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Local => "local",
+            Self::S3 => "s3",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum DownloadMode {
+    /// Stream archived objects through the server.
+    #[default]
+    Proxy,
+    /// Redirect to a presigned URL; falls back to proxying on backends without one.
+    Presign,
+}
+
+/// Archival policy, stored under the `storage` config key.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct StorageConfig {
+    #[serde(default)]
+    pub backend: StorageKind,
+    /// Archive directory for the `local` backend.
+    #[serde(default)]
+    pub local_root: String,
+    #[serde(default)]
+    pub s3: S3Config,
+    /// Prepended to the recording's relative path to form the object key.
+    #[serde(default)]
+    pub prefix: String,
+    /// Archive each recording as soon as it stops.
+    #[serde(default)]
+    pub upload_after_stop: bool,
+    /// Remove the capture PC's copy once the upload is confirmed.
+    #[serde(default)]
+    pub delete_local_after_upload: bool,
+    #[serde(default)]
+    pub download_mode: DownloadMode,
+    #[serde(default = "default_presign_minutes")]
+    pub presign_expires_minutes: u64,
+}
+
+fn default_presign_minutes() -> u64 {
+    15
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageKind::None,
+            local_root: String::new(),
+            s3: S3Config::default(),
+            prefix: String::new(),
+            upload_after_stop: false,
+            delete_local_after_upload: false,
+            download_mode: DownloadMode::Proxy,
+            presign_expires_minutes: default_presign_minutes(),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Instantiate a backend of `kind` from this config. `None` when archival is off.
+    pub fn backend_for(&self, kind: StorageKind) -> anyhow::Result<Option<Box<dyn StorageBackend>>> {
+        Ok(match kind {
+            StorageKind::None => None,
+            StorageKind::Local => {
+                if self.local_root.trim().is_empty() {
+                    anyhow::bail!("Local archive directory not configured");
+                }
+                Some(Box::new(LocalStorage::new(PathBuf::from(self.local_root.trim()))))
+            }
+            StorageKind::S3 => Some(Box::new(S3Storage::new(&self.s3)?)),
+        })
+    }
+
+    pub fn backend(&self) -> anyhow::Result<Option<Box<dyn StorageBackend>>> {
+        self.backend_for(self.backend)
+    }
+
+    /// Object key for a recording's path relative to the recording root.
+    pub fn object_key(&self, relative: &str) -> String {
+        let relative = relative.replace('\\', "/");
+        let prefix = self.prefix.trim().trim_matches('/');
+        if prefix.is_empty() {
+            relative
+        } else {
+            format!("{}/{}", prefix, relative)
+        }
+    }
+}
+
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: PathBuf) -> Self {
+        Self { root }
+    }
+
+    fn path_for(&self, key: &str) -> anyhow::Result<PathBuf> {
+        validate_relative(key).map_err(|e| anyhow::anyhow!("Invalid object key {}: {}", key, e))?;
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl StorageBackend for LocalStorage {
+    fn kind(&self) -> StorageKind {
+        StorageKind::Local
+    }
+
+    async fn put_file(&self, key: &str, path: &Path) -> anyhow::Result<()> {
+        let dest = self.path_for(key)?;
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Copy next to the target first so a half-written file never carries the real name
+        let mut partial = dest.clone().into_os_string();
+        partial.push(".part");
+        tokio::fs::copy(path, &partial).await?;
+        tokio::fs::rename(&partial, &dest).await?;
+        Ok(())
+    }
+
+    async fn size(&self, key: &str) -> anyhow::Result<Option<u64>> {
+        match tokio::fs::metadata(self.path_for(key)?).await {
+            Ok(m) => Ok(Some(m.len())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn open(&self, key: &str, range: Option<(u64, u64)>) -> anyhow::Result<ObjectReader> {
+        let mut file = tokio::fs::File::open(self.path_for(key)?).await?;
+        let total = file.metadata().await?.len();
+        let (start, len) = match range {
+            Some((start, end)) => (start, end - start + 1),
+            None => (0, total),
+        };
+        if start > 0 {
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+        }
+        Ok(Box::pin(file.take(len)))
+    }
+
+    async fn delete(&self, key: &str) -> anyhow::Result<()> {
+        match tokio::fs::remove_file(self.path_for(key)?).await {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn read_all(mut reader: ObjectReader) -> Vec<u8> {
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).await.unwrap();
+        out
+    }
+
+    #[tokio::test]
+    async fn local_storage_round_trip() {
+        let dir = std::env::temp_dir().join(format!("storage-test-{}", uuid::Uuid::new_v4()));
+        let source = dir.join("source.bin");
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        let data: Vec<u8> = (0..=255u8).cycle().take(4096).collect();
+        tokio::fs::write(&source, &data).await.unwrap();
+
+        let storage = LocalStorage::new(dir.join("objects"));
+        let key = "alice/2024/clip.mp4";
+        assert_eq!(storage.size(key).await.unwrap(), None);
+        storage.put_file(key, &source).await.unwrap();
+        assert_eq!(storage.size(key).await.unwrap(), Some(4096));
+
+        assert_eq!(read_all(storage.open(key, None).await.unwrap()).await, data);
+        assert_eq!(read_all(storage.open(key, Some((100, 199))).await.unwrap()).await, &data[100..200]);
+
+        storage.delete(key).await.unwrap();
+        assert_eq!(storage.size(key).await.unwrap(), None);
+        // Deleting something already gone is not an error
+        storage.delete(key).await.unwrap();
+
+        assert!(storage.put_file("../escape.mp4", &source).await.is_err());
+        assert!(storage.open("/etc/passwd", None).await.is_err());
+
+        tokio::fs::remove_dir_all(&dir).await.unwrap();
+    }
+}
@@ -0,0 +1,182 @@
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::core::path_template::validate_relative;
+use crate::core::recording_dir::RECORDING_EXTENSIONS;
+
+/// The only tus protocol version we speak.
+pub const TUS_VERSION: &str = "1.0.0";
+/// Partial uploads live here under the recording root; hidden so reconcile skips them.
+pub const UPLOAD_STAGING_DIR: &str = ".uploads";
+
+/// Rules for uploading outside videos into the library, stored under `upload_policy`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct UploadPolicy {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    /// Lowercase, without the dot; a subset of the extensions reconcile recognises.
+    #[serde(default = "default_extensions")]
+    pub allowed_extensions: Vec<String>,
+    /// Largest single upload; None leaves only the user's storage quota.
+    #[serde(default)]
+    pub max_size_bytes: Option<i64>,
+    /// Folder under the recording root finished uploads are moved to; empty for the root.
+    #[serde(default = "default_folder")]
+    pub folder: String,
+    /// Unfinished uploads are discarded after this long without progress.
+    #[serde(default = "default_expire_hours")]
+    pub expire_hours: i64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_extensions() -> Vec<String> {
+    RECORDING_EXTENSIONS.iter().map(|e| e.to_string()).collect()
+}
+
+fn default_folder() -> String {
+    "uploads".to_string()
+}
+
+fn default_expire_hours() -> i64 {
+    24
+}
+
+impl Default for UploadPolicy {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            allowed_extensions: default_extensions(),
+            max_size_bytes: None,
+            folder: default_folder(),
+            expire_hours: default_expire_hours(),
+        }
+    }
+}
+
+impl UploadPolicy {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.allowed_extensions.is_empty() {
+            return Err("At least one extension must be allowed".to_string());
+        }
+        for ext in &self.allowed_extensions {
+            if !RECORDING_EXTENSIONS.contains(&ext.as_str()) {
+                return Err(format!("{} is not a recording format; allowed: {}", ext, RECORDING_EXTENSIONS.join(", ")));
+            }
+        }
+        if self.max_size_bytes.is_some_and(|m| m <= 0) {
+            return Err("max_size_bytes must be positive".to_string());
+        }
+        if !self.folder.is_empty() {
+            validate_relative(&self.folder).map_err(|e| format!("folder: {}", e))?;
+        }
+        if !(1..=24 * 30).contains(&self.expire_hours) {
+            return Err("expire_hours must be between 1 and 720".to_string());
+        }
+        Ok(())
+    }
+
+    pub fn allows(&self, filename: &str) -> bool {
+        filename
+            .rsplit_once('.')
+            .map(|(_, ext)| self.allowed_extensions.iter().any(|a| a.eq_ignore_ascii_case(ext)))
+            .unwrap_or(false)
+    }
+
+    /// Where a finished upload named `name` goes, relative to the recording root.
+    pub fn destination(&self, name: &str) -> String {
+        let folder = self.folder.trim_matches('/');
+        if folder.is_empty() {
+            name.to_string()
+        } else {
+            format!("{}/{}", folder, name)
+        }
+    }
+}
+
+/// Parse a tus `Upload-Metadata` header: comma-separated `key base64(value)` pairs,
+/// where the value may be omitted.
+pub fn parse_metadata(header: &str) -> Result<HashMap<String, String>, &'static str> {
+    let mut out = HashMap::new();
+    for pair in header.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = match pair.split_once(' ') {
+            Some((k, v)) => (k, v.trim()),
+            None => (pair, ""),
+        };
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(value)
+            .map_err(|_| "Upload-Metadata values must be base64")?;
+        let decoded = String::from_utf8(decoded).map_err(|_| "Upload-Metadata values must be UTF-8")?;
+        out.insert(key.to_string(), decoded);
+    }
+    Ok(out)
+}
+
+/// The last component of a client-supplied file name, which may carry a Windows or Unix path.
+pub fn client_file_name(name: &str) -> &str {
+    name.trim().rsplit(['/', '\\']).next().unwrap_or("").trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // (header, decoded pairs or None when rejected)
+    type MetadataCase<'a> = (&'a str, Option<&'a [(&'a str, &'a str)]>);
+
+    #[test]
+    fn parse_metadata_table() {
+        let cases: &[MetadataCase] = &[
+            ("", Some(&[])),
+            ("filename d2hvbGUubWt2", Some(&[("filename", "whole.mkv")])),
+            (
+                " filename d2hvbGUubWt2 , filetype dmlkZW8vbXA0 ",
+                Some(&[("filename", "whole.mkv"), ("filetype", "video/mp4")]),
+            ),
+            ("filename w6l0w6kubXA0", Some(&[("filename", "\u{e9}t\u{e9}.mp4")])),
+            // Keys may come without a value
+            ("filename d2hvbGUubWt2,is_confidential", Some(&[("filename", "whole.mkv"), ("is_confidential", "")])),
+            ("a,,b ", Some(&[("a", ""), ("b", "")])),
+            ("filename whole.mkv", None),
+            ("filename //79", None),
+        ];
+        for (header, expected) in cases {
+            let got = parse_metadata(header).ok();
+            let expected: Option<HashMap<String, String>> =
+                expected.map(|pairs| pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect());
+            assert_eq!(got, expected, "header {:?}", header);
+        }
+    }
+
+    #[test]
+    fn client_file_name_table() {
+        let cases = [
+            ("rec.mp4", "rec.mp4"),
+            (r"C:\Users\me\Videos\rec.mp4", "rec.mp4"),
+            ("/home/me/rec.mp4", "rec.mp4"),
+            (r"  mixed/dir\ rec.mp4 ", "rec.mp4"),
+            ("../../etc/passwd", "passwd"),
+            ("dir/", ""),
+            ("", ""),
+        ];
+        for (name, expected) in cases {
+            assert_eq!(client_file_name(name), expected, "name {:?}", name);
+        }
+    }
+
+    #[test]
+    fn policy_checks_extensions_and_folder() {
+        let policy = UploadPolicy::default();
+        assert!(policy.allows("rec.MP4"));
+        assert!(!policy.allows("notes.txt"));
+        assert!(!policy.allows("mp4"));
+        assert_eq!(policy.destination("rec.mp4"), "uploads/rec.mp4");
+        assert_eq!(UploadPolicy { folder: String::new(), ..policy.clone() }.destination("rec.mp4"), "rec.mp4");
+        assert!(UploadPolicy { folder: "../up".into(), ..policy.clone() }.validate().is_err());
+        assert!(UploadPolicy { allowed_extensions: vec!["exe".into()], ..policy.clone() }.validate().is_err());
+        assert!(policy.validate().is_ok());
+    }
+}
@@ -0,0 +1,208 @@
+use std::collections::HashSet;
+
+// Recordings are already compressed, so entries are stored as-is. Every entry uses a
+// data descriptor (the CRC is only known after streaming the file) and ZIP64 fields,
+// so archives past 4 GiB work without knowing sizes up front.
+
+const LOCAL_HEADER_SIG: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR_SIG: u32 = 0x0807_4b50;
+const CENTRAL_HEADER_SIG: u32 = 0x0201_4b50;
+const ZIP64_EOCD_SIG: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIG: u32 = 0x0706_4b50;
+const EOCD_SIG: u32 = 0x0605_4b50;
+
+const VERSION_ZIP64: u16 = 45;
+/// Bit 3: sizes and CRC follow the data; bit 11: names are UTF-8.
+const FLAGS: u16 = 0x0808;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+struct Entry {
+    name: String,
+    offset: u64,
+    crc: u32,
+    size: u64,
+    dos_time: u16,
+    dos_date: u16,
+}
+
+/// Produces the byte framing around each file; the caller streams file contents in between.
+#[derive(Default)]
+pub struct ZipWriter {
+    entries: Vec<Entry>,
+    offset: u64,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Header to write before an entry's data.
+    pub fn start_entry(&mut self, name: &str, modified: chrono::DateTime<chrono::Utc>) -> Vec<u8> {
+        let (dos_time, dos_date) = dos_datetime(modified);
+        let mut buf = Vec::with_capacity(30 + name.len() + 20);
+        put_u32(&mut buf, LOCAL_HEADER_SIG);
+        put_u16(&mut buf, VERSION_ZIP64);
+        put_u16(&mut buf, FLAGS);
+        put_u16(&mut buf, 0); // stored
+        put_u16(&mut buf, dos_time);
+        put_u16(&mut buf, dos_date);
+        put_u32(&mut buf, 0); // crc, in the descriptor
+        put_u32(&mut buf, u32::MAX);
+        put_u32(&mut buf, u32::MAX);
+        put_u16(&mut buf, name.len() as u16);
+        put_u16(&mut buf, 20);
+        buf.extend_from_slice(name.as_bytes());
+        put_u16(&mut buf, ZIP64_EXTRA_ID);
+        put_u16(&mut buf, 16);
+        put_u64(&mut buf, 0);
+        put_u64(&mut buf, 0);
+
+        self.entries.push(Entry {
+            name: name.to_string(),
+            offset: self.offset,
+            crc: 0,
+            size: 0,
+            dos_time,
+            dos_date,
+        });
+        self.offset += buf.len() as u64;
+        buf
+    }
+
+    /// Descriptor to write after the entry's `size` bytes of data.
+    pub fn finish_entry(&mut self, crc: u32, size: u64) -> Vec<u8> {
+        if let Some(entry) = self.entries.last_mut() {
+            entry.crc = crc;
+            entry.size = size;
+        }
+        let mut buf = Vec::with_capacity(24);
+        put_u32(&mut buf, DATA_DESCRIPTOR_SIG);
+        put_u32(&mut buf, crc);
+        put_u64(&mut buf, size);
+        put_u64(&mut buf, size);
+        self.offset += size + buf.len() as u64;
+        buf
+    }
+
+    /// Central directory and end records.
+    pub fn finish(self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let cd_start = self.offset;
+        for e in &self.entries {
+            let mut extra = Vec::new();
+            let big_size = e.size >= u32::MAX as u64;
+            let big_offset = e.offset >= u32::MAX as u64;
+            if big_size {
+                put_u64(&mut extra, e.size);
+                put_u64(&mut extra, e.size);
+            }
+            if big_offset {
+                put_u64(&mut extra, e.offset);
+            }
+
+            put_u32(&mut buf, CENTRAL_HEADER_SIG);
+            put_u16(&mut buf, VERSION_ZIP64); // made by
+            put_u16(&mut buf, VERSION_ZIP64);
+            put_u16(&mut buf, FLAGS);
+            put_u16(&mut buf, 0);
+            put_u16(&mut buf, e.dos_time);
+            put_u16(&mut buf, e.dos_date);
+            put_u32(&mut buf, e.crc);
+            let size32 = if big_size { u32::MAX } else { e.size as u32 };
+            put_u32(&mut buf, size32);
+            put_u32(&mut buf, size32);
+            put_u16(&mut buf, e.name.len() as u16);
+            put_u16(&mut buf, if extra.is_empty() { 0 } else { extra.len() as u16 + 4 });
+            put_u16(&mut buf, 0); // comment
+            put_u16(&mut buf, 0); // disk
+            put_u16(&mut buf, 0); // internal attrs
+            put_u32(&mut buf, 0); // external attrs
+            put_u32(&mut buf, if big_offset { u32::MAX } else { e.offset as u32 });
+            buf.extend_from_slice(e.name.as_bytes());
+            if !extra.is_empty() {
+                put_u16(&mut buf, ZIP64_EXTRA_ID);
+                put_u16(&mut buf, extra.len() as u16);
+                buf.extend_from_slice(&extra);
+            }
+        }
+        let cd_size = buf.len() as u64;
+        let zip64_eocd_offset = cd_start + cd_size;
+        let count = self.entries.len() as u64;
+
+        put_u32(&mut buf, ZIP64_EOCD_SIG);
+        put_u64(&mut buf, 44);
+        put_u16(&mut buf, VERSION_ZIP64);
+        put_u16(&mut buf, VERSION_ZIP64);
+        put_u32(&mut buf, 0);
+        put_u32(&mut buf, 0);
+        put_u64(&mut buf, count);
+        put_u64(&mut buf, count);
+        put_u64(&mut buf, cd_size);
+        put_u64(&mut buf, cd_start);
+
+        put_u32(&mut buf, ZIP64_LOCATOR_SIG);
+        put_u32(&mut buf, 0);
+        put_u64(&mut buf, zip64_eocd_offset);
+        put_u32(&mut buf, 1);
+
+        put_u32(&mut buf, EOCD_SIG);
+        put_u16(&mut buf, 0);
+        put_u16(&mut buf, 0);
+        put_u16(&mut buf, count.min(u16::MAX as u64) as u16);
+        put_u16(&mut buf, count.min(u16::MAX as u64) as u16);
+        put_u32(&mut buf, cd_size.min(u32::MAX as u64) as u32);
+        put_u32(&mut buf, cd_start.min(u32::MAX as u64) as u32);
+        put_u16(&mut buf, 0);
+        buf
+    }
+}
+
+/// Make archive names unique by adding ` (n)` before the extension.
+pub fn unique_entry_names(names: &[String]) -> Vec<String> {
+    let mut used: HashSet<String> = HashSet::new();
+    names
+        .iter()
+        .map(|name| {
+            let name = name.replace('\\', "/");
+            if used.insert(name.to_lowercase()) {
+                return name;
+            }
+            let (stem, ext) = match name.rsplit_once('.') {
+                Some((s, e)) if !s.is_empty() && !e.contains('/') => (s.to_string(), format!(".{}", e)),
+                _ => (name.clone(), String::new()),
+            };
+            let mut n = 2;
+            loop {
+                let candidate = format!("{} ({}){}", stem, n, ext);
+                if used.insert(candidate.to_lowercase()) {
+                    return candidate;
+                }
+                n += 1;
+            }
+        })
+        .collect()
+}
+
+fn dos_datetime(t: chrono::DateTime<chrono::Utc>) -> (u16, u16) {
+    use chrono::{Datelike, Timelike};
+    // DOS dates start in 1980
+    if t.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = (t.hour() << 11) | (t.minute() << 5) | (t.second() / 2);
+    let date = (((t.year() - 1980) as u32) << 9) | (t.month() << 5) | t.day();
+    (time as u16, date as u16)
+}
+
+fn put_u16(buf: &mut Vec<u8>, v: u16) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_u32(buf: &mut Vec<u8>, v: u32) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
+
+fn put_u64(buf: &mut Vec<u8>, v: u64) {
+    buf.extend_from_slice(&v.to_le_bytes());
+}
@@ -12,24 +12,27 @@ use tower_http::trace::TraceLayer;
 use tower_http::cors::CorsLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use crate::core::recorder::{RecorderManager, StopRequest};
-use std::collections::HashMap;
+use crate::core::scan_job::ScanJobManager;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 use std::sync::Once;
 
 #[cfg(windows)]
 use windows_service::define_windows_service;
 
-pub struct DownloadToken {
-    pub user_id: Uuid,
-    pub file_id: Uuid,
-    pub expires_at: i64,
-}
-
 pub struct AppState {
     pub db: RwLock<Option<PgPool>>,
     pub recorder_manager: Arc<RecorderManager>,
+    pub scan_jobs: Arc<ScanJobManager>,
     pub stop_requests: RwLock<HashMap<Uuid, StopRequest>>,
-    pub download_tokens: RwLock<HashMap<String, DownloadToken>>,
+    pub reconcile_lock: tokio::sync::Mutex<()>,
+    /// Held while checking for and claiming the encoder, so a recording and a scan or
+    /// preflight can't both see it free and start together.
+    pub encoder_lock: tokio::sync::Mutex<()>,
+    /// Uploads currently receiving a PATCH.
+    pub active_uploads: std::sync::Mutex<HashSet<Uuid>>,
+    /// Clients currently reading share links, for download counting.
+    pub share_sessions: std::sync::Mutex<core::share_session::ShareSessions>,
 }
 
 #[cfg(windows)]
@@ -144,8 +147,12 @@ async fn build_state() -> Arc<AppState> {
     Arc::new(AppState {
         db: RwLock::new(db_pool),
         recorder_manager: Arc::new(RecorderManager::new()),
+        scan_jobs: Arc::new(ScanJobManager::new()),
         stop_requests: RwLock::new(HashMap::new()),
-        download_tokens: RwLock::new(HashMap::new()),
+        reconcile_lock: tokio::sync::Mutex::new(()),
+        encoder_lock: tokio::sync::Mutex::new(()),
+        active_uploads: std::sync::Mutex::new(HashSet::new()),
+        share_sessions: std::sync::Mutex::new(Default::default()),
     })
 }
 
@@ -157,6 +164,9 @@ fn build_app(state: Arc<AppState>) -> Router {
         .nest("/api/hardware", api::hardware::router())
         .nest("/api/recorder", api::recorder::router())
         .nest("/api/files", api::files::router())
+        .nest("/api/share", api::share::router())
+        .nest("/api/jobs", api::jobs::router())
+        .nest("/api/uploads", api::uploads::router())
         .nest("/api/announcements", api::announcements::router())
         .nest("/api/settings", api::settings::router())
         .nest("/api/user", api::user_config::router())
@@ -225,11 +235,19 @@ where
             });
         }
     }
+    tokio::spawn(api::retention::reconcile_loop(state.clone(), shutdown_rx.clone()));
+    tokio::spawn(api::retention::cleanup_loop(state.clone(), shutdown_rx.clone()));
+    tokio::spawn(api::files::token_sweep_loop(state.clone(), shutdown_rx.clone()));
+    tokio::spawn(api::uploads::upload_sweep_loop(state.clone(), shutdown_rx.clone()));
+    tokio::spawn(api::recorder::recording_watch_loop(state.clone(), shutdown_rx.clone()));
+    for index in 0..core::jobs::MAX_WORKERS {
+        tokio::spawn(api::jobs::job_worker_loop(state.clone(), shutdown_rx.clone(), index));
+    }
     let app = build_app(state);
     let addr = SocketAddr::from(([0, 0, 0, 0], 3000));
     tracing::info!("listening on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app)
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
         .with_graceful_shutdown(async move {
             let mut shutdown_rx = shutdown_rx.clone();
             let _ = shutdown_rx.changed().await;
@@ -36,6 +36,23 @@ interface UserInfo {
   created_at: string;
 }
 
+interface VerifyJob {
+  id: string;
+  status: string;
+  progress: number;
+  error?: string | null;
+  result?: {
+    checked: number;
+    ok: number;
+    baselined: number;
+    mismatched: string[];
+    missing: string[];
+    errors: number;
+  } | null;
+  created_at: string;
+  finished_at?: string | null;
+}
+
 const deviceForbiddenChars = /[|;$`><()[\]'"\n\r]/;
 const resolutionLabelSet = new Set(['4k', '2160p', '1080p', '720p', '480p']);
 
@@ -111,6 +128,49 @@ function AdminActions({ token, baseUrl, setError, setSuccess }: AdminSectionProp
   const [announcements, setAnnouncements] = useState<Announcement[]>([]);
   const [installingService, setInstallingService] = useState(false);
   const [uninstallingService, setUninstallingService] = useState(false);
+  const [verifyJob, setVerifyJob] = useState<VerifyJob | null>(null);
+
+  const fetchVerifyJob = useCallback(async () => {
+    try {
+      const res = await axios.get(`${baseUrl}/api/jobs`, {
+        params: { kind: 'verify', limit: 1 },
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setVerifyJob(res.data[0] || null);
+    } catch (error) {
+      console.error(error);
+    }
+  }, [baseUrl, token]);
+
+  useEffect(() => {
+    fetchVerifyJob();
+  }, [fetchVerifyJob]);
+
+  const verifyActive = verifyJob?.status === 'queued' || verifyJob?.status === 'running';
+  useEffect(() => {
+    if (!verifyActive) return;
+    const timer = setInterval(fetchVerifyJob, 3000);
+    return () => clearInterval(timer);
+  }, [verifyActive, fetchVerifyJob]);
+
+  const handleVerifyAll = async () => {
+    if (!confirm('将重新计算所有录像的 SHA-256 并与记录值比对，文件较多时耗时较长。继续吗？')) return;
+    setError('');
+    try {
+      await axios.post(`${baseUrl}/api/jobs/verify`, {}, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setSuccess('校验任务已加入队列');
+      fetchVerifyJob();
+    } catch (error) {
+      console.error(error);
+      if (axios.isAxiosError(error)) {
+        setError(typeof error.response?.data === 'string' ? error.response?.data : '启动校验失败');
+      } else {
+        setError('启动校验失败');
+      }
+    }
+  };
 
   const fetchAnnouncements = useCallback(async () => {
     try {
@@ -366,6 +426,37 @@ function AdminActions({ token, baseUrl, setError, setSuccess }: AdminSectionProp
         </div>
       </div>
 
+      <div className="p-4 border rounded dark:border-gray-700">
+        <h3 className="font-medium mb-2">文件完整性校验</h3>
+        <p className="text-sm text-gray-600 dark:text-gray-400 mb-3">
+          重新计算所有录像（含仅存于归档的文件）的 SHA-256，与完成录制时记录的值比对，标记被修改或损坏的文件。
+        </p>
+        <button
+          onClick={handleVerifyAll}
+          disabled={verifyActive}
+          className="bg-blue-600 hover:bg-blue-700 disabled:bg-gray-400 text-white font-bold py-2 px-4 rounded w-full"
+        >
+          {verifyActive ? `校验中 ${Math.round((verifyJob?.progress || 0) * 100)}%` : '校验全部文件'}
+        </button>
+        {verifyJob && !verifyActive && (
+          <div className="mt-3 text-sm space-y-1">
+            <p className="text-xs text-gray-500">
+              上次校验：{new Date(verifyJob.finished_at || verifyJob.created_at).toLocaleString()}（{verifyJob.status}）
+            </p>
+            {verifyJob.error && <p className="text-xs text-red-600">{verifyJob.error}</p>}
+            {verifyJob.result && (
+              <p className="text-xs">
+                共 {verifyJob.result.checked} 个：{verifyJob.result.ok} 个一致，{verifyJob.result.baselined} 个首次记录，
+                <span className={verifyJob.result.mismatched.length ? 'text-red-600 font-medium' : ''}>
+                  {verifyJob.result.mismatched.length} 个不一致
+                </span>
+                ，{verifyJob.result.missing.length} 个丢失，{verifyJob.result.errors} 个读取失败
+              </p>
+            )}
+          </div>
+        )}
+      </div>
+
       <div className="p-4 border rounded dark:border-gray-700">
         <h3 className="font-medium mb-2">发布公告</h3>
         <textarea
@@ -496,9 +587,57 @@ function UserManagement({ token, baseUrl, setError, setSuccess }: AdminSectionPr
     );
 }
 
+interface StorageSettings {
+    backend: 'none' | 'local' | 's3';
+    local_root: string;
+    prefix: string;
+    upload_after_stop: boolean;
+    delete_local_after_upload: boolean;
+    download_mode: 'proxy' | 'presign';
+    presign_expires_minutes: number;
+    s3: {
+        endpoint: string;
+        region: string;
+        bucket: string;
+        access_key_id: string;
+        secret_access_key: string;
+        path_style: boolean;
+        has_secret?: boolean;
+    };
+}
+
+const defaultStorageSettings: StorageSettings = {
+    backend: 'none',
+    local_root: '',
+    prefix: '',
+    upload_after_stop: false,
+    delete_local_after_upload: false,
+    download_mode: 'proxy',
+    presign_expires_minutes: 15,
+    s3: { endpoint: '', region: 'us-east-1', bucket: '', access_key_id: '', secret_access_key: '', path_style: true },
+};
+
+interface UploadSettings {
+    enabled: boolean;
+    allowed_extensions: string[];
+    max_size_bytes: number | null;
+    folder: string;
+    expire_hours: number;
+}
+
+const defaultUploadSettings: UploadSettings = {
+    enabled: true,
+    allowed_extensions: ['mp4', 'mkv', 'flv', 'mov', 'ts', 'm4v'],
+    max_size_bytes: null,
+    folder: 'uploads',
+    expire_hours: 24,
+};
+
 function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionProps) {
     const [cliPath, setCliPath] = useState('');
     const [globalPath, setGlobalPath] = useState('');
+    const [pathTemplate, setPathTemplate] = useState('');
+    const [pathTemplateExample, setPathTemplateExample] = useState('');
     const [downloadTokenTtlMinutes, setDownloadTokenTtlMinutes] = useState(60);
     const [serverName, setServerName] = useState('');
     const [recordConfig, setRecordConfig] = useState({
@@ -507,6 +646,11 @@ function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionPr
         max_res: '1080p',
         video_encoder: 'x264'
     });
+    const [storage, setStorage] = useState<StorageSettings>(defaultStorageSettings);
+    const [uploads, setUploads] = useState<UploadSettings>(defaultUploadSettings);
+    const [pipelineSteps, setPipelineSteps] = useState('[]');
+    const [pipelineMaxAttempts, setPipelineMaxAttempts] = useState(3);
+    const [pipelineWorkers, setPipelineWorkers] = useState(1);
     const [hardwareInfo, setHardwareInfo] = useState<HardwareInfo | null>(null);
     const [loading, setLoading] = useState(false);
     const [saving, setSaving] = useState(false);
@@ -532,12 +676,16 @@ function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionPr
     const fetchSettings = useCallback(async () => {
         setLoading(true);
         try {
-            const [pathRes, configRes, globalPathRes, ttlRes, nameRes] = await Promise.all([
+            const [pathRes, configRes, globalPathRes, ttlRes, nameRes, templateRes, storageRes, pipelineRes, uploadsRes] = await Promise.all([
                 axios.get(`${baseUrl}/api/settings/cli-path`, { headers: { Authorization: `Bearer ${token}` } }),
                 axios.get(`${baseUrl}/api/settings/record-config`, { headers: { Authorization: `Bearer ${token}` } }),
                 axios.get(`${baseUrl}/api/settings/global-path`, { headers: { Authorization: `Bearer ${token}` } }),
                 axios.get(`${baseUrl}/api/settings/download-token-ttl`, { headers: { Authorization: `Bearer ${token}` } }),
-                axios.get(`${baseUrl}/api/settings/server-name`, { headers: { Authorization: `Bearer ${token}` } })
+                axios.get(`${baseUrl}/api/settings/server-name`, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.get(`${baseUrl}/api/settings/path-template`, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.get(`${baseUrl}/api/settings/storage`, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.get(`${baseUrl}/api/settings/pipeline`, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.get(`${baseUrl}/api/settings/uploads`, { headers: { Authorization: `Bearer ${token}` } })
             ]);
             setCliPath(pathRes.data.path);
             setRecordConfig({
@@ -547,6 +695,13 @@ function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionPr
             setGlobalPath(globalPathRes.data.path);
             setDownloadTokenTtlMinutes(ttlRes.data.minutes ?? 60);
             setServerName(nameRes.data.name ?? '');
+            setPathTemplate(templateRes.data.template ?? '');
+            setPathTemplateExample(templateRes.data.example ?? '');
+            setStorage({ ...defaultStorageSettings, ...storageRes.data, s3: { ...defaultStorageSettings.s3, ...storageRes.data.s3 } });
+            setPipelineSteps(JSON.stringify(pipelineRes.data.steps ?? [], null, 2));
+            setPipelineMaxAttempts(pipelineRes.data.max_attempts ?? 3);
+            setPipelineWorkers(pipelineRes.data.workers ?? 1);
+            setUploads({ ...defaultUploadSettings, ...uploadsRes.data });
         } catch (error) {
             console.error(error);
         } finally {
@@ -584,6 +739,13 @@ function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionPr
         if (bitrateError) errors.push(bitrateError);
         const encoderError = validateEncoderIdValue(recordConfig.video_encoder);
         if (encoderError) errors.push(encoderError);
+        let steps: unknown = [];
+        try {
+            steps = JSON.parse(pipelineSteps || '[]');
+            if (!Array.isArray(steps)) errors.push('处理流程步骤必须是 JSON 数组');
+        } catch {
+            errors.push('处理流程步骤不是有效的 JSON');
+        }
         if (errors.length > 0) {
             setError(errors[0]);
             setSaving(false);
@@ -595,7 +757,11 @@ function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionPr
                 axios.post(`${baseUrl}/api/settings/record-config`, recordConfig, { headers: { Authorization: `Bearer ${token}` } }),
                 axios.post(`${baseUrl}/api/settings/global-path`, { path: globalPath }, { headers: { Authorization: `Bearer ${token}` } }),
                 axios.post(`${baseUrl}/api/settings/download-token-ttl`, { minutes: Math.max(1, Math.floor(downloadTokenTtlMinutes)) }, { headers: { Authorization: `Bearer ${token}` } }),
-                axios.post(`${baseUrl}/api/settings/server-name`, { name: serverName }, { headers: { Authorization: `Bearer ${token}` } })
+                axios.post(`${baseUrl}/api/settings/server-name`, { name: serverName }, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.post(`${baseUrl}/api/settings/path-template`, { template: pathTemplate }, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.post(`${baseUrl}/api/settings/storage`, storage, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.post(`${baseUrl}/api/settings/pipeline`, { steps, max_attempts: pipelineMaxAttempts, workers: pipelineWorkers }, { headers: { Authorization: `Bearer ${token}` } }),
+                axios.post(`${baseUrl}/api/settings/uploads`, uploads, { headers: { Authorization: `Bearer ${token}` } })
             ]);
             setSuccess('所有设置已保存');
         } catch (error) {
@@ -638,6 +804,20 @@ function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionPr
                     />
                     <p className="text-xs text-gray-500 mt-1">留空则使用默认路径</p>
                 </div>
+                <div className="mb-4">
+                    <label className="block text-sm font-medium mb-1">录像文件路径模板</label>
+                    <input 
+                        type="text"
+                        value={pathTemplate}
+                        onChange={e => setPathTemplate(e.target.value)}
+                        className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                        placeholder="{username}/{yyyy}/{mm}/{date}_{seq}.{ext}"
+                    />
+                    <p className="text-xs text-gray-500 mt-1">
+                        可用占位符：{'{username} {user_id} {yyyy} {mm} {dd} {HH} {MM} {SS} {date} {time} {timestamp} {seq} {ext}'}，相对于存储根目录，重名时自动追加序号
+                        {pathTemplateExample && <>；示例：{pathTemplateExample}</>}
+                    </p>
+                </div>
                 <div className="mb-4">
                     <label className="block text-sm font-medium mb-1">后端名称</label>
                     <input 
@@ -660,6 +840,247 @@ function SystemSettings({ token, baseUrl, setError, setSuccess }: AdminSectionPr
                 </div>
             </div>
 
+            <div className="p-4 border rounded dark:border-gray-700">
+                <h3 className="font-medium mb-4 text-lg">归档存储</h3>
+                <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+                    <div>
+                        <label className="block text-sm font-medium mb-1">存储后端</label>
+                        <select
+                            value={storage.backend}
+                            onChange={e => setStorage({ ...storage, backend: e.target.value as StorageSettings['backend'] })}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                        >
+                            <option value="none">不归档（仅本机）</option>
+                            <option value="local">本地/NAS 目录</option>
+                            <option value="s3">S3 兼容存储</option>
+                        </select>
+                    </div>
+                    <div>
+                        <label className="block text-sm font-medium mb-1">对象键前缀</label>
+                        <input
+                            type="text"
+                            value={storage.prefix}
+                            onChange={e => setStorage({ ...storage, prefix: e.target.value })}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                            placeholder="例如：capture-01"
+                        />
+                    </div>
+                    {storage.backend === 'local' && (
+                        <div className="md:col-span-2">
+                            <label className="block text-sm font-medium mb-1">归档目录</label>
+                            <input
+                                type="text"
+                                value={storage.local_root}
+                                onChange={e => setStorage({ ...storage, local_root: e.target.value })}
+                                className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                placeholder="例如：\\nas\录像归档"
+                            />
+                        </div>
+                    )}
+                    {storage.backend === 's3' && (
+                        <>
+                            <div>
+                                <label className="block text-sm font-medium mb-1">Endpoint</label>
+                                <input
+                                    type="text"
+                                    value={storage.s3.endpoint}
+                                    onChange={e => setStorage({ ...storage, s3: { ...storage.s3, endpoint: e.target.value } })}
+                                    className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                    placeholder="例如：http://nas:9000"
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium mb-1">Region</label>
+                                <input
+                                    type="text"
+                                    value={storage.s3.region}
+                                    onChange={e => setStorage({ ...storage, s3: { ...storage.s3, region: e.target.value } })}
+                                    className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium mb-1">Bucket</label>
+                                <input
+                                    type="text"
+                                    value={storage.s3.bucket}
+                                    onChange={e => setStorage({ ...storage, s3: { ...storage.s3, bucket: e.target.value } })}
+                                    className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium mb-1">Access Key ID</label>
+                                <input
+                                    type="text"
+                                    value={storage.s3.access_key_id}
+                                    onChange={e => setStorage({ ...storage, s3: { ...storage.s3, access_key_id: e.target.value } })}
+                                    className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                />
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium mb-1">Secret Access Key</label>
+                                <input
+                                    type="password"
+                                    value={storage.s3.secret_access_key}
+                                    onChange={e => setStorage({ ...storage, s3: { ...storage.s3, secret_access_key: e.target.value } })}
+                                    className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                    placeholder={storage.s3.has_secret ? '已设置，留空保持不变' : ''}
+                                />
+                            </div>
+                            <label className="flex items-center space-x-2 text-sm">
+                                <input
+                                    type="checkbox"
+                                    checked={storage.s3.path_style}
+                                    onChange={e => setStorage({ ...storage, s3: { ...storage.s3, path_style: e.target.checked } })}
+                                />
+                                <span>路径风格寻址（MinIO 等自建存储）</span>
+                            </label>
+                        </>
+                    )}
+                    {storage.backend !== 'none' && (
+                        <>
+                            <label className="flex items-center space-x-2 text-sm">
+                                <input
+                                    type="checkbox"
+                                    checked={storage.upload_after_stop}
+                                    onChange={e => setStorage({ ...storage, upload_after_stop: e.target.checked })}
+                                />
+                                <span>录制停止后自动归档</span>
+                            </label>
+                            <label className="flex items-center space-x-2 text-sm">
+                                <input
+                                    type="checkbox"
+                                    checked={storage.delete_local_after_upload}
+                                    onChange={e => setStorage({ ...storage, delete_local_after_upload: e.target.checked })}
+                                />
+                                <span>归档成功后删除本机文件</span>
+                            </label>
+                            <div>
+                                <label className="block text-sm font-medium mb-1">归档文件下载方式</label>
+                                <select
+                                    value={storage.download_mode}
+                                    onChange={e => setStorage({ ...storage, download_mode: e.target.value as StorageSettings['download_mode'] })}
+                                    className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                >
+                                    <option value="proxy">经服务器中转</option>
+                                    <option value="presign">预签名链接直连（仅 S3）</option>
+                                </select>
+                            </div>
+                            <div>
+                                <label className="block text-sm font-medium mb-1">预签名链接有效期 (分钟)</label>
+                                <input
+                                    type="number"
+                                    min={1}
+                                    value={storage.presign_expires_minutes}
+                                    onChange={e => setStorage({ ...storage, presign_expires_minutes: parseInt(e.target.value) || 1 })}
+                                    className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                                />
+                            </div>
+                        </>
+                    )}
+                </div>
+            </div>
+
+            <div className="p-4 border rounded dark:border-gray-700">
+                <h3 className="font-medium mb-4 text-lg">视频上传</h3>
+                <label className="flex items-center space-x-2 text-sm mb-4">
+                    <input
+                        type="checkbox"
+                        checked={uploads.enabled}
+                        onChange={e => setUploads({ ...uploads, enabled: e.target.checked })}
+                    />
+                    <span>允许用户上传外部录制的视频</span>
+                </label>
+                <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+                    <div>
+                        <label className="block text-sm font-medium mb-1">允许的扩展名</label>
+                        <input
+                            type="text"
+                            value={uploads.allowed_extensions.join(', ')}
+                            onChange={e => setUploads({ ...uploads, allowed_extensions: e.target.value.split(',').map(x => x.trim()).filter(Boolean) })}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                            placeholder="mp4, mkv"
+                        />
+                    </div>
+                    <div>
+                        <label className="block text-sm font-medium mb-1">单个文件大小上限 (MB，留空不限)</label>
+                        <input
+                            type="number"
+                            min={1}
+                            value={uploads.max_size_bytes ? Math.round(uploads.max_size_bytes / 1024 / 1024) : ''}
+                            onChange={e => {
+                                const mb = parseInt(e.target.value);
+                                setUploads({ ...uploads, max_size_bytes: mb > 0 ? mb * 1024 * 1024 : null });
+                            }}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                        />
+                    </div>
+                    <div>
+                        <label className="block text-sm font-medium mb-1">保存到 (相对录制目录)</label>
+                        <input
+                            type="text"
+                            value={uploads.folder}
+                            onChange={e => setUploads({ ...uploads, folder: e.target.value })}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                            placeholder="留空则保存在录制目录根下"
+                        />
+                    </div>
+                    <div>
+                        <label className="block text-sm font-medium mb-1">未完成上传保留时间 (小时)</label>
+                        <input
+                            type="number"
+                            min={1}
+                            max={720}
+                            value={uploads.expire_hours}
+                            onChange={e => setUploads({ ...uploads, expire_hours: parseInt(e.target.value) || 1 })}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                        />
+                    </div>
+                </div>
+                <p className="text-xs text-gray-500 mt-2">上传同样计入用户存储配额；中断的上传在保留时间内可续传。</p>
+            </div>
+
+            <div className="p-4 border rounded dark:border-gray-700">
+                <h3 className="font-medium mb-4 text-lg">录制后处理流程</h3>
+                <div className="mb-4">
+                    <label className="block text-sm font-medium mb-1">步骤 (JSON)</label>
+                    <textarea
+                        value={pipelineSteps}
+                        onChange={e => setPipelineSteps(e.target.value)}
+                        rows={8}
+                        className="w-full p-2 border rounded font-mono text-xs dark:bg-gray-700 dark:border-gray-600"
+                    />
+                    <p className="text-xs text-gray-500 mt-1">
+                        每步包含 kind (remux / transcode / thumbnail / checksum / upload)；执行外部程序的步骤还需 command 与 args，
+                        参数中可使用 {'{input}'}、{'{output}'}、{'{dir}'}、{'{stem}'}、{'{duration}'}。
+                        例如：{'{"kind": "remux", "command": "ffmpeg", "args": ["-y", "-i", "{input}", "-c", "copy", "-progress", "pipe:1", "{output}"], "output_ext": "mp4"}'}
+                    </p>
+                </div>
+                <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
+                    <div>
+                        <label className="block text-sm font-medium mb-1">最大尝试次数</label>
+                        <input
+                            type="number"
+                            min={1}
+                            max={10}
+                            value={pipelineMaxAttempts}
+                            onChange={e => setPipelineMaxAttempts(parseInt(e.target.value) || 1)}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                        />
+                    </div>
+                    <div>
+                        <label className="block text-sm font-medium mb-1">并发任务数</label>
+                        <input
+                            type="number"
+                            min={1}
+                            max={4}
+                            value={pipelineWorkers}
+                            onChange={e => setPipelineWorkers(parseInt(e.target.value) || 1)}
+                            className="w-full p-2 border rounded dark:bg-gray-700 dark:border-gray-600"
+                        />
+                    </div>
+                </div>
+            </div>
+
             <div className="p-4 border rounded dark:border-gray-700">
                 <h3 className="font-medium mb-4 text-lg">全局录制限制 (默认值)</h3>
                 <div className="grid grid-cols-1 md:grid-cols-2 gap-4">
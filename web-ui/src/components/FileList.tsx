@@ -1,45 +1,309 @@
-import { useState, useEffect, useCallback } from 'react';
+import { useState, useEffect, useCallback, useRef } from 'react';
 import axios from 'axios';
-import { Trash2, Edit2, FileVideo, Check, X, Download } from 'lucide-react';
+import { Trash2, Edit2, FileVideo, Check, X, Download, Play, Pin, Archive, ListChecks, Scissors, Upload, Bookmark } from 'lucide-react';
 
 interface RecordingFile {
   id: string;
   filename: string;
   status: string;
   created_at: string;
+  size_bytes?: number | null;
+  duration_secs?: number | null;
+  video_codec?: string | null;
+  width?: number | null;
+  height?: number | null;
+  frame_rate?: number | null;
+  audio_tracks?: number | null;
+  is_complete?: boolean | null;
+  pinned?: boolean;
+  capture_mode?: string | null;
+  archive_state?: string | null;
+  archive_error?: string | null;
+  local_copy?: boolean;
+  has_thumbnail?: boolean;
+  source_recording_id?: string | null;
+  clip_start_secs?: number | null;
+  clip_end_secs?: number | null;
+  sha256?: string | null;
+  integrity_state?: string | null;
+  integrity_checked_at?: string | null;
+  status_changed_at?: string | null;
+  marker_count?: number;
 }
 
+interface Marker {
+  id: string;
+  offset_secs: number;
+  label: string | null;
+}
+
+interface StatusChange {
+  from_status: string | null;
+  to_status: string;
+  reason: string | null;
+  created_at: string;
+}
+
+interface Job {
+  id: string;
+  step_index: number;
+  kind: string;
+  status: string;
+  progress: number;
+  attempts: number;
+  max_attempts: number;
+  error?: string | null;
+  created_at: string;
+}
+
+interface FileListResponse {
+  items: RecordingFile[];
+  next_cursor: string | null;
+  total_count: number;
+  total_size_bytes: number;
+}
+
+interface TrashEntry {
+  id: string;
+  filename: string;
+  size_bytes?: number | null;
+  deleted_at: string;
+  purge_at: string | null;
+}
+
+const PAGE_SIZE = 50;
+
+const JOB_KIND_LABELS: Record<string, string> = {
+  remux: '封装转换',
+  transcode: '转码',
+  thumbnail: '缩略图',
+  checksum: '校验',
+  upload: '归档上传',
+};
+
+const RECORDING_STATUS_LABELS: Record<string, string> = {
+  pending: '启动中',
+  recording: '录制中',
+  finalizing: '收尾中',
+  saved: '已保存',
+  incomplete: '不完整',
+  uploaded: '已上传',
+  failed: '失败',
+  missing: '文件丢失',
+  deleted: '已删除',
+};
+
+const RECORDING_STATUS_STYLES: Record<string, string> = {
+  pending: 'bg-gray-100 text-gray-800',
+  recording: 'bg-red-100 text-red-800',
+  finalizing: 'bg-blue-100 text-blue-800',
+  saved: 'bg-green-100 text-green-800',
+  incomplete: 'bg-yellow-100 text-yellow-800',
+  uploaded: 'bg-green-100 text-green-800',
+  failed: 'bg-red-100 text-red-800',
+  missing: 'bg-red-100 text-red-800',
+};
+
+// The file is still being written, so it can't be played, cut or processed yet
+const isInProgress = (file: RecordingFile) =>
+  file.status === 'pending' || file.status === 'recording' || file.status === 'finalizing';
+
+const JOB_STATUS_LABELS: Record<string, string> = {
+  queued: '排队中',
+  running: '处理中',
+  succeeded: '已完成',
+  failed: '失败',
+  cancelled: '已取消',
+};
+
+// The thumbnail endpoint needs the bearer token, so fetch it as a blob instead of a plain <img src>
+function Thumbnail({ id, baseUrl, token }: { id: string; baseUrl: string; token: string | null }) {
+  const [src, setSrc] = useState<string | null>(null);
+
+  useEffect(() => {
+    let url: string | null = null;
+    axios.get(`${baseUrl}/api/files/${id}/thumbnail`, {
+      headers: { Authorization: `Bearer ${token}` },
+      responseType: 'blob',
+    }).then(res => {
+      url = URL.createObjectURL(res.data);
+      setSrc(url);
+    }).catch(() => setSrc(null));
+    return () => {
+      if (url) URL.revokeObjectURL(url);
+    };
+  }, [id, baseUrl, token]);
+
+  if (!src) return <FileVideo className="text-gray-500" />;
+  return <img src={src} alt="" className="w-16 h-9 object-cover rounded bg-black" />;
+}
+
+const formatSize = (bytes: number) => {
+  const units = ['B', 'KB', 'MB', 'GB', 'TB'];
+  let value = bytes;
+  let unit = 0;
+  while (value >= 1024 && unit < units.length - 1) {
+    value /= 1024;
+    unit++;
+  }
+  return `${value.toFixed(unit === 0 ? 0 : 1)} ${units[unit]}`;
+};
+
+const formatDuration = (secs: number) => {
+  const total = Math.round(secs);
+  const h = Math.floor(total / 3600);
+  const m = Math.floor((total % 3600) / 60);
+  const s = total % 60;
+  const mmss = `${String(m).padStart(2, '0')}:${String(s).padStart(2, '0')}`;
+  return h > 0 ? `${h}:${mmss}` : mmss;
+};
+
+// Accepts plain seconds, mm:ss or h:mm:ss
+const parseTimestamp = (value: string): number | null => {
+  const parts = value.trim().split(':');
+  if (parts.length === 0 || parts.length > 3 || parts.some(p => p === '' || isNaN(Number(p)))) return null;
+  return parts.reduce((acc, p) => acc * 60 + Number(p), 0);
+};
+
+const isClippable = (file: RecordingFile) =>
+  !isInProgress(file) && file.local_copy !== false && /\.(mp4|m4v|mov)$/i.test(file.filename);
+
+const describeMedia = (file: RecordingFile) => {
+  const parts: string[] = [];
+  if (file.duration_secs) parts.push(formatDuration(file.duration_secs));
+  if (file.width && file.height) parts.push(`${file.width}x${file.height}`);
+  if (file.frame_rate) parts.push(`${file.frame_rate}fps`);
+  if (file.video_codec) parts.push(file.video_codec);
+  if (file.size_bytes != null) parts.push(formatSize(file.size_bytes));
+  return parts.join(' · ');
+};
+
+// tus PATCH size; small enough that an interrupted upload loses little
+const UPLOAD_CHUNK_SIZE = 8 * 1024 * 1024;
+
+const tusMetadata = (fields: Record<string, string>) =>
+  Object.entries(fields)
+    .map(([key, value]) => `${key} ${btoa(String.fromCharCode(...new TextEncoder().encode(value)))}`)
+    .join(',');
+
 export default function FileList() {
   const [files, setFiles] = useState<RecordingFile[]>([]);
   const [loading, setLoading] = useState(false);
   const [editingId, setEditingId] = useState<string | null>(null);
   const [newName, setNewName] = useState('');
+  const [playing, setPlaying] = useState<{ id: string; url: string; resumeAt?: number } | null>(null);
+  const [jobsFor, setJobsFor] = useState<{ id: string; jobs: Job[] } | null>(null);
+  const [historyFor, setHistoryFor] = useState<{ id: string; history: StatusChange[] } | null>(null);
+  const [markersFor, setMarkersFor] = useState<{ id: string; markers: Marker[] } | null>(null);
+  const [clipping, setClipping] = useState<{ id: string; start: string; end: string } | null>(null);
+  const [selected, setSelected] = useState<Set<string>>(new Set());
+  const [showTrash, setShowTrash] = useState(false);
+  const [trash, setTrash] = useState<TrashEntry[]>([]);
+  const [uploading, setUploading] = useState<{ name: string; sent: number; total: number } | null>(null);
+  const uploadInput = useRef<HTMLInputElement>(null);
 
   const token = localStorage.getItem('token');
   const baseUrl = localStorage.getItem('backend_url') || 'http://localhost:3000';
 
+  const [search, setSearch] = useState('');
+  const [status, setStatus] = useState('');
+  const [sort, setSort] = useState('created_at');
+  const [nextCursor, setNextCursor] = useState<string | null>(null);
+  const [totals, setTotals] = useState<{ count: number; size: number } | null>(null);
+  // Auto refresh only reloads the first page, so it pauses once more pages are shown
+  const pagesLoaded = useRef(1);
+
+  const fetchPage = useCallback(async (cursor: string | null) => {
+    const params: Record<string, string | number> = { limit: PAGE_SIZE, sort };
+    if (sort === 'filename') params.order = 'asc';
+    if (search.trim()) params.q = search.trim();
+    if (status) params.status = status;
+    if (cursor) params.cursor = cursor;
+    const res = await axios.get<FileListResponse>(`${baseUrl}/api/files`, {
+      headers: { Authorization: `Bearer ${token}` },
+      params
+    });
+    return res.data;
+  }, [baseUrl, token, search, status, sort]);
+
   const fetchFiles = useCallback(async () => {
     setLoading(true);
     try {
-      const res = await axios.get(`${baseUrl}/api/files`, {
-        headers: { Authorization: `Bearer ${token}` }
-      });
-      setFiles(res.data);
+      const data = await fetchPage(null);
+      pagesLoaded.current = 1;
+      setFiles(data.items);
+      setNextCursor(data.next_cursor);
+      setTotals({ count: data.total_count, size: data.total_size_bytes });
     } catch (error) {
       console.error(error);
     } finally {
       setLoading(false);
     }
-  }, [baseUrl, token]);
+  }, [fetchPage]);
+
+  const loadMore = async () => {
+    if (!nextCursor) return;
+    try {
+      const data = await fetchPage(nextCursor);
+      pagesLoaded.current += 1;
+      setFiles(prev => [...prev, ...data.items]);
+      setNextCursor(data.next_cursor);
+    } catch (error) {
+      console.error(error);
+    }
+  };
 
   useEffect(() => {
     fetchFiles();
-    const interval = setInterval(fetchFiles, 10000); // Auto refresh
+    const interval = setInterval(() => {
+      if (pagesLoaded.current === 1) fetchFiles();
+    }, 10000); // Auto refresh
     return () => clearInterval(interval);
   }, [fetchFiles]);
 
+  const fetchTrash = useCallback(async () => {
+    try {
+      const res = await axios.get<TrashEntry[]>(`${baseUrl}/api/files/trash`, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setTrash(res.data);
+    } catch (error) {
+      console.error(error);
+    }
+  }, [baseUrl, token]);
+
+  useEffect(() => {
+    if (showTrash) fetchTrash();
+  }, [showTrash, fetchTrash]);
+
+  const handleRestore = async (id: string) => {
+    try {
+      await axios.post(`${baseUrl}/api/files/trash/${id}/restore`, {}, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      fetchTrash();
+      fetchFiles();
+    } catch (err) {
+      console.error(err);
+      alert(axios.isAxiosError(err) && typeof err.response?.data === 'string' ? err.response.data : '恢复失败');
+    }
+  };
+
+  const handlePurge = async (id: string) => {
+    if (!confirm('永久删除后无法恢复，确定吗？')) return;
+    try {
+      await axios.delete(`${baseUrl}/api/files/trash/${id}`, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      fetchTrash();
+    } catch (err) {
+      console.error(err);
+      alert('删除失败');
+    }
+  };
+
   const handleDelete = async (id: string) => {
-    if (!confirm('确定要删除此文件吗？')) return;
+    if (!confirm('确定要将此文件移到回收站吗？')) return;
     try {
       await axios.delete(`${baseUrl}/api/files/${id}`, {
         headers: { Authorization: `Bearer ${token}` }
@@ -51,6 +315,7 @@ export default function FileList() {
     }
   };
 
+  // Download tokens are single-use and still need the bearer, so the file is fetched here.
   const handleDownload = async (file: RecordingFile) => {
     try {
       const tokenRes = await axios.post(`${baseUrl}/api/files/${file.id}/download-token`, {}, {
@@ -79,6 +344,349 @@ export default function FileList() {
     }
   };
 
+  // <video> can't send headers, so playback uses a short-lived token scoped to the recording.
+  const getPlaybackUrl = async (file: RecordingFile) => {
+    const tokenRes = await axios.post(`${baseUrl}/api/files/${file.id}/playback-token`, {}, {
+      headers: { Authorization: `Bearer ${token}` }
+    });
+    const playbackToken = tokenRes.data?.token as string | undefined;
+    if (!playbackToken) return null;
+    return `${baseUrl}/api/files/play?token=${encodeURIComponent(playbackToken)}`;
+  };
+
+  const handlePlay = async (file: RecordingFile) => {
+    if (playing?.id === file.id) {
+      setPlaying(null);
+      return;
+    }
+    try {
+      const url = await getPlaybackUrl(file);
+      if (!url) {
+        alert('播放失败');
+        return;
+      }
+      setPlaying({ id: file.id, url });
+    } catch (err) {
+      console.error(err);
+      alert('播放失败');
+    }
+  };
+
+  // The playback token expires while a long recording is still playing; fetch a fresh one
+  // and pick up where the player stopped.
+  const handlePlaybackError = async (file: RecordingFile, video: HTMLVideoElement) => {
+    const resumeAt = video.currentTime;
+    // No progress since the last refresh: the file itself can't be played
+    if (playing?.resumeAt !== undefined && resumeAt <= playing.resumeAt) {
+      setPlaying(null);
+      alert('播放失败');
+      return;
+    }
+    try {
+      const url = await getPlaybackUrl(file);
+      if (!url) return;
+      setPlaying({ id: file.id, url, resumeAt });
+    } catch (err) {
+      console.error(err);
+    }
+  };
+
+  const handlePin = async (file: RecordingFile) => {
+    try {
+      await axios.post(`${baseUrl}/api/files/${file.id}/pin`, { pinned: !file.pinned }, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      fetchFiles();
+    } catch (err) {
+      console.error(err);
+      alert('操作失败');
+    }
+  };
+
+  const handleArchive = async (file: RecordingFile) => {
+    try {
+      await axios.post(`${baseUrl}/api/files/${file.id}/archive`, {}, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      fetchFiles();
+    } catch (err: any) {
+      console.error(err);
+      alert(err.response?.data || '归档失败');
+    }
+  };
+
+  const loadJobs = async (id: string) => {
+    try {
+      const res = await axios.get(`${baseUrl}/api/jobs`, {
+        params: { recording_id: id },
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setJobsFor({ id, jobs: res.data });
+    } catch (err) {
+      console.error(err);
+      alert('加载处理任务失败');
+    }
+  };
+
+  const handleToggleHistory = async (file: RecordingFile) => {
+    if (historyFor?.id === file.id) {
+      setHistoryFor(null);
+      return;
+    }
+    try {
+      const res = await axios.get(`${baseUrl}/api/files/${file.id}/history`, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setHistoryFor({ id: file.id, history: res.data });
+    } catch (err) {
+      console.error(err);
+      alert('加载状态记录失败');
+    }
+  };
+
+  const loadMarkers = async (id: string) => {
+    try {
+      const res = await axios.get(`${baseUrl}/api/files/${id}/markers`, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setMarkersFor({ id, markers: res.data });
+    } catch (err) {
+      console.error(err);
+      alert('加载标记失败');
+    }
+  };
+
+  const handleToggleMarkers = (file: RecordingFile) => {
+    if (markersFor?.id === file.id) {
+      setMarkersFor(null);
+      return;
+    }
+    loadMarkers(file.id);
+  };
+
+  const handleDeleteMarker = async (fileId: string, marker: Marker) => {
+    if (!confirm('确定删除这个标记吗？')) return;
+    try {
+      await axios.delete(`${baseUrl}/api/files/${fileId}/markers/${marker.id}`, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      loadMarkers(fileId);
+      fetchFiles();
+    } catch (err) {
+      console.error(err);
+      alert('删除标记失败');
+    }
+  };
+
+  // Cuts 30s either side of the marker, the server's default
+  const handleClipMarker = async (fileId: string, marker: Marker) => {
+    try {
+      await axios.post(`${baseUrl}/api/files/${fileId}/clip`, { marker_id: marker.id }, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      fetchFiles();
+    } catch (err: any) {
+      console.error(err);
+      alert(err.response?.data || '剪辑失败');
+    }
+  };
+
+  const handleExportChapters = async (file: RecordingFile, format: 'vtt' | 'ffmetadata') => {
+    try {
+      const res = await axios.get(`${baseUrl}/api/files/${file.id}/chapters`, {
+        params: { format },
+        headers: { Authorization: `Bearer ${token}` },
+        responseType: 'blob'
+      });
+      const stem = file.filename.split(/[\\/]/).pop()?.replace(/\.[^.]+$/, '') || 'chapters';
+      const url = URL.createObjectURL(res.data);
+      const link = document.createElement('a');
+      link.href = url;
+      link.download = `${stem}.${format}`;
+      document.body.appendChild(link);
+      link.click();
+      link.remove();
+      URL.revokeObjectURL(url);
+    } catch (err) {
+      console.error(err);
+      alert('导出章节失败');
+    }
+  };
+
+  const handleToggleJobs = (file: RecordingFile) => {
+    if (jobsFor?.id === file.id) {
+      setJobsFor(null);
+      return;
+    }
+    loadJobs(file.id);
+  };
+
+  const handleJobAction = async (fileId: string, job: Job, action: 'retry' | 'cancel') => {
+    try {
+      await axios.post(`${baseUrl}/api/jobs/${job.id}/${action}`, {}, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      loadJobs(fileId);
+    } catch (err: any) {
+      console.error(err);
+      alert(err.response?.data || '操作失败');
+    }
+  };
+
+  const handleRunPipeline = async (file: RecordingFile) => {
+    try {
+      await axios.post(`${baseUrl}/api/jobs`, { recording_id: file.id }, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      loadJobs(file.id);
+    } catch (err: any) {
+      console.error(err);
+      alert(err.response?.data || '提交处理失败');
+    }
+  };
+
+  const handleClip = async () => {
+    if (!clipping) return;
+    const start = parseTimestamp(clipping.start);
+    const end = parseTimestamp(clipping.end);
+    if (start === null || end === null || end <= start) {
+      alert('请输入有效的开始和结束时间');
+      return;
+    }
+    try {
+      await axios.post(`${baseUrl}/api/files/${clipping.id}/clip`, { start_secs: start, end_secs: end }, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setClipping(null);
+      fetchFiles();
+    } catch (err: any) {
+      console.error(err);
+      alert(err.response?.data || '剪辑失败');
+    }
+  };
+
+  const toggleSelected = (id: string) => {
+    setSelected(prev => {
+      const next = new Set(prev);
+      if (next.has(id)) next.delete(id); else next.add(id);
+      return next;
+    });
+  };
+
+  const handleBulkDelete = async () => {
+    if (!confirm(`确定要删除选中的 ${selected.size} 个文件吗？`)) return;
+    try {
+      const res = await axios.post(`${baseUrl}/api/files/bulk/delete`, { ids: Array.from(selected) }, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      if (res.data?.failed?.length) {
+        alert(`${res.data.failed.length} 个文件删除失败`);
+      }
+      setSelected(new Set());
+      fetchFiles();
+    } catch (err) {
+      console.error(err);
+      alert('删除失败');
+    }
+  };
+
+  const handleBulkZip = async () => {
+    try {
+      const res = await axios.post(`${baseUrl}/api/files/bulk/zip`, { ids: Array.from(selected) }, {
+        headers: { Authorization: `Bearer ${token}` },
+        responseType: 'blob'
+      });
+      const url = URL.createObjectURL(res.data);
+      const link = document.createElement('a');
+      link.href = url;
+      link.download = 'recordings.zip';
+      document.body.appendChild(link);
+      link.click();
+      link.remove();
+      URL.revokeObjectURL(url);
+    } catch (err) {
+      console.error(err);
+      alert('打包下载失败');
+    }
+  };
+
+  const handleMerge = async () => {
+    // Join in recording order, oldest first
+    const parts = files
+      .filter(f => selected.has(f.id))
+      .sort((a, b) => new Date(a.created_at).getTime() - new Date(b.created_at).getTime());
+    if (parts.length < 2) return;
+    if (!parts.every(isClippable)) {
+      alert('只能合并已完成的 MP4 录像');
+      return;
+    }
+    if (!confirm(`将按录制时间顺序合并 ${parts.length} 个文件：\n${parts.map(p => p.filename).join('\n')}`)) return;
+    const deleteParts = confirm('合并完成后将原始片段移到回收站吗？');
+    try {
+      await axios.post(`${baseUrl}/api/files/concat`, { ids: parts.map(p => p.id), delete_parts: deleteParts }, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setSelected(new Set());
+      fetchFiles();
+    } catch (err: any) {
+      console.error(err);
+      alert(err.response?.data || '合并失败');
+    }
+  };
+
+  // Resumable upload: the upload URL is remembered per file, so picking the same file again
+  // after a dropped connection continues where the server left off.
+  const handleUpload = async (file: File) => {
+    const key = `upload:${file.name}:${file.size}:${file.lastModified}`;
+    const tusHeaders = { Authorization: `Bearer ${token}`, 'Tus-Resumable': '1.0.0' };
+    let location = localStorage.getItem(key);
+    let offset = 0;
+    setUploading({ name: file.name, sent: 0, total: file.size });
+    try {
+      if (location) {
+        try {
+          const res = await axios.head(`${baseUrl}${location}`, { headers: tusHeaders });
+          offset = Number(res.headers['upload-offset'] || 0);
+        } catch {
+          localStorage.removeItem(key);
+          location = null;
+        }
+      }
+      if (!location) {
+        const res = await axios.post(`${baseUrl}/api/uploads`, null, {
+          headers: {
+            ...tusHeaders,
+            'Upload-Length': String(file.size),
+            'Upload-Metadata': tusMetadata({ filename: file.name, filetype: file.type }),
+          }
+        });
+        location = res.headers['location'] as string;
+        localStorage.setItem(key, location);
+      }
+      while (offset < file.size) {
+        setUploading({ name: file.name, sent: offset, total: file.size });
+        const res = await axios.patch(`${baseUrl}${location}`, file.slice(offset, offset + UPLOAD_CHUNK_SIZE), {
+          headers: {
+            ...tusHeaders,
+            'Content-Type': 'application/offset+octet-stream',
+            'Upload-Offset': String(offset),
+          }
+        });
+        offset = Number(res.headers['upload-offset']);
+      }
+      localStorage.removeItem(key);
+      fetchFiles();
+    } catch (err: any) {
+      console.error(err);
+      alert(typeof err.response?.data === 'string' && err.response.data
+        ? err.response.data
+        : '上传中断，重新选择同一文件即可继续上传');
+    } finally {
+      setUploading(null);
+      if (uploadInput.current) uploadInput.current.value = '';
+    }
+  };
+
   const handleRename = async (id: string) => {
     if (!newName.trim()) return;
     try {
@@ -98,7 +706,87 @@ export default function FileList() {
     <div className="bg-white dark:bg-gray-800 p-6 rounded-lg shadow-md mt-6">
       <div className="flex justify-between items-center mb-4">
         <h2 className="text-xl font-semibold">录像文件</h2>
-        <button onClick={fetchFiles} className="text-sm text-blue-500 hover:underline">刷新</button>
+        <div className="flex items-center space-x-3">
+          {selected.size > 0 && (
+            <>
+              <button onClick={handleBulkZip} className="text-sm text-blue-500 hover:underline">打包下载 ({selected.size})</button>
+              {selected.size > 1 && (
+                <button onClick={handleMerge} className="text-sm text-blue-500 hover:underline">无损合并 ({selected.size})</button>
+              )}
+              <button onClick={handleBulkDelete} className="text-sm text-red-500 hover:underline">删除所选</button>
+            </>
+          )}
+          <input
+            ref={uploadInput}
+            type="file"
+            accept=".mp4,.mkv,.flv,.mov,.ts,.m4v"
+            className="hidden"
+            onChange={e => e.target.files?.[0] && handleUpload(e.target.files[0])}
+          />
+          {uploading ? (
+            <span className="text-sm text-gray-500">
+              上传 {uploading.name} {Math.floor((uploading.sent / uploading.total) * 100)}%
+            </span>
+          ) : (
+            <button onClick={() => uploadInput.current?.click()} className="text-sm text-blue-500 hover:underline flex items-center">
+              <Upload size={14} className="mr-1" />上传视频
+            </button>
+          )}
+          <button onClick={() => setShowTrash(!showTrash)} className="text-sm text-gray-500 hover:underline">
+            {showTrash ? '返回文件列表' : '回收站'}
+          </button>
+          <button onClick={fetchFiles} className="text-sm text-blue-500 hover:underline">刷新</button>
+        </div>
+      </div>
+
+      {showTrash ? (
+        trash.length === 0 ? (
+          <p className="text-gray-500">回收站为空</p>
+        ) : (
+          <div className="space-y-2">
+            {trash.map(entry => (
+              <div key={entry.id} className="flex items-center justify-between p-3 border rounded dark:border-gray-700">
+                <div>
+                  <p className="font-medium">{entry.filename}</p>
+                  <p className="text-xs text-gray-500">
+                    删除于 {new Date(entry.deleted_at).toLocaleString()}
+                    {entry.purge_at && ` · 将于 ${new Date(entry.purge_at).toLocaleDateString()} 自动清除`}
+                    {entry.size_bytes != null && ` · ${formatSize(entry.size_bytes)}`}
+                  </p>
+                </div>
+                <div className="flex space-x-3 text-sm">
+                  <button onClick={() => handleRestore(entry.id)} className="text-blue-500 hover:underline">恢复</button>
+                  <button onClick={() => handlePurge(entry.id)} className="text-red-500 hover:underline">永久删除</button>
+                </div>
+              </div>
+            ))}
+          </div>
+        )
+      ) : (
+      <>
+      <div className="flex flex-wrap items-center gap-2 mb-4 text-sm">
+        <input
+          type="text"
+          value={search}
+          onChange={e => setSearch(e.target.value)}
+          placeholder="搜索文件名"
+          className="border rounded p-1 dark:bg-gray-700"
+        />
+        <select value={status} onChange={e => setStatus(e.target.value)} className="border rounded p-1 dark:bg-gray-700">
+          <option value="">全部状态</option>
+          {['pending', 'recording', 'finalizing', 'saved', 'incomplete', 'uploaded', 'failed', 'missing'].map(s => (
+            <option key={s} value={s}>{RECORDING_STATUS_LABELS[s]}</option>
+          ))}
+        </select>
+        <select value={sort} onChange={e => setSort(e.target.value)} className="border rounded p-1 dark:bg-gray-700">
+          <option value="created_at">按时间</option>
+          <option value="filename">按文件名</option>
+          <option value="size">按大小</option>
+          <option value="duration">按时长</option>
+        </select>
+        {totals && (
+          <span className="text-gray-500">共 {totals.count} 个文件，{formatSize(totals.size)}</span>
+        )}
       </div>
 
       {loading && files.length === 0 ? (
@@ -108,9 +796,20 @@ export default function FileList() {
       ) : (
         <div className="space-y-2">
           {files.map(file => (
-            <div key={file.id} className="flex items-center justify-between p-3 border rounded dark:border-gray-700 hover:bg-gray-50 dark:hover:bg-gray-700">
+            <div key={file.id}>
+            <div className="flex items-center justify-between p-3 border rounded dark:border-gray-700 hover:bg-gray-50 dark:hover:bg-gray-700">
               <div className="flex items-center space-x-3">
-                <FileVideo className="text-gray-500" />
+                <input
+                  type="checkbox"
+                  checked={selected.has(file.id)}
+                  onChange={() => toggleSelected(file.id)}
+                  disabled={isInProgress(file)}
+                />
+                {file.has_thumbnail ? (
+                  <Thumbnail id={file.id} baseUrl={baseUrl} token={token} />
+                ) : (
+                  <FileVideo className="text-gray-500" />
+                )}
                 <div>
                   {editingId === file.id ? (
                     <div className="flex items-center space-x-2">
@@ -128,8 +827,47 @@ export default function FileList() {
                     <p className="font-medium">{file.filename}</p>
                   )}
                   <p className="text-xs text-gray-500">
-                    {new Date(file.created_at).toLocaleString()} - <span className={`uppercase text-[10px] px-1 rounded ${file.status === 'recording' ? 'bg-red-100 text-red-800' : 'bg-green-100 text-green-800'}`}>{file.status}</span>
+                    {new Date(file.created_at).toLocaleString()} -{' '}
+                    <button
+                      onClick={() => handleToggleHistory(file)}
+                      title={file.status_changed_at ? `状态更新于 ${new Date(file.status_changed_at).toLocaleString()}，点击查看记录` : '点击查看状态记录'}
+                      className={`text-[10px] px-1 rounded ${RECORDING_STATUS_STYLES[file.status] || 'bg-gray-100 text-gray-800'}`}
+                    >
+                      {RECORDING_STATUS_LABELS[file.status] || file.status}
+                    </button>
+                    {file.source_recording_id && (
+                      <span className="ml-1 text-[10px] px-1 rounded bg-purple-100 text-purple-800">片段</span>
+                    )}
+                    {file.archive_state === 'uploading' && (
+                      <span className="ml-1 text-[10px] px-1 rounded bg-blue-100 text-blue-800">归档中</span>
+                    )}
+                    {file.archive_state === 'archived' && (
+                      <span className="ml-1 text-[10px] px-1 rounded bg-blue-100 text-blue-800">
+                        {file.local_copy === false ? '仅存于归档' : '已归档'}
+                      </span>
+                    )}
+                    {file.archive_state === 'failed' && (
+                      <span className="ml-1 text-[10px] px-1 rounded bg-red-100 text-red-800" title={file.archive_error || ''}>归档失败</span>
+                    )}
+                    {file.integrity_state === 'mismatch' && (
+                      <span className="ml-1 text-[10px] px-1 rounded bg-red-100 text-red-800" title="文件内容与完成录制时记录的 SHA-256 不一致">校验失败</span>
+                    )}
+                    {file.integrity_state === 'missing' && (
+                      <span className="ml-1 text-[10px] px-1 rounded bg-red-100 text-red-800">文件丢失</span>
+                    )}
                   </p>
+                  {describeMedia(file) && (
+                    <p className="text-xs text-gray-400">{describeMedia(file)}</p>
+                  )}
+                  {file.sha256 && (
+                    <p
+                      className="text-[10px] text-gray-400 font-mono cursor-pointer"
+                      title={`SHA-256: ${file.sha256}${file.integrity_checked_at ? `\n上次校验：${new Date(file.integrity_checked_at).toLocaleString()}` : ''}\n点击复制`}
+                      onClick={() => navigator.clipboard?.writeText(file.sha256 || '')}
+                    >
+                      SHA-256 {file.sha256.slice(0, 16)}…
+                    </p>
+                  )}
                 </div>
               </div>
               
@@ -139,6 +877,46 @@ export default function FileList() {
                         <Edit2 size={18} />
                     </button>
                 )}
+                {!isInProgress(file) && (
+                  <button onClick={() => handlePlay(file)} className="text-gray-500 hover:text-blue-500">
+                    <Play size={18} />
+                  </button>
+                )}
+                <button
+                  onClick={() => handlePin(file)}
+                  title={file.pinned ? '取消固定' : '固定（不会被自动清理）'}
+                  className={file.pinned ? 'text-blue-500' : 'text-gray-500 hover:text-blue-500'}
+                >
+                  <Pin size={18} />
+                </button>
+                {!isInProgress(file) && file.local_copy !== false && file.archive_state !== 'uploading' && (
+                  <button onClick={() => handleArchive(file)} title="上传到归档存储" className="text-gray-500 hover:text-blue-500">
+                    <Archive size={18} />
+                  </button>
+                )}
+                {isClippable(file) && (
+                  <button
+                    onClick={() => setClipping(clipping?.id === file.id ? null : { id: file.id, start: '0:00', end: file.duration_secs ? formatDuration(file.duration_secs) : '' })}
+                    title="剪辑片段"
+                    className={clipping?.id === file.id ? 'text-blue-500' : 'text-gray-500 hover:text-blue-500'}
+                  >
+                    <Scissors size={18} />
+                  </button>
+                )}
+                {(file.marker_count ?? 0) > 0 && (
+                  <button
+                    onClick={() => handleToggleMarkers(file)}
+                    title={`标记（${file.marker_count}）`}
+                    className={markersFor?.id === file.id ? 'text-blue-500' : 'text-gray-500 hover:text-blue-500'}
+                  >
+                    <Bookmark size={18} />
+                  </button>
+                )}
+                {!isInProgress(file) && (
+                  <button onClick={() => handleToggleJobs(file)} title="处理任务" className={jobsFor?.id === file.id ? 'text-blue-500' : 'text-gray-500 hover:text-blue-500'}>
+                    <ListChecks size={18} />
+                  </button>
+                )}
                 <button onClick={() => handleDownload(file)} className="text-gray-500 hover:text-blue-500">
                   <Download size={18} />
                 </button>
@@ -147,9 +925,126 @@ export default function FileList() {
                 </button>
               </div>
             </div>
+            {playing?.id === file.id && (
+              <video
+                src={playing.url}
+                controls
+                autoPlay
+                className="w-full mt-2 rounded bg-black"
+                onLoadedMetadata={e => {
+                  if (playing.resumeAt) e.currentTarget.currentTime = playing.resumeAt;
+                }}
+                onError={e => handlePlaybackError(file, e.currentTarget)}
+              />
+            )}
+            {clipping?.id === file.id && (
+              <div className="mt-2 p-3 border rounded text-sm dark:border-gray-700 flex flex-wrap items-center gap-2">
+                <span>从</span>
+                <input
+                  type="text"
+                  value={clipping.start}
+                  onChange={e => setClipping({ ...clipping, start: e.target.value })}
+                  className="border rounded p-1 w-24 dark:bg-gray-600"
+                />
+                <span>到</span>
+                <input
+                  type="text"
+                  value={clipping.end}
+                  onChange={e => setClipping({ ...clipping, end: e.target.value })}
+                  className="border rounded p-1 w-24 dark:bg-gray-600"
+                />
+                <button onClick={handleClip} className="text-blue-500 hover:underline">导出片段</button>
+                <span className="text-xs text-gray-500">不重新编码，起点会对齐到之前最近的关键帧</span>
+              </div>
+            )}
+            {markersFor?.id === file.id && (
+              <div className="mt-2 p-3 border rounded text-sm dark:border-gray-700 space-y-1">
+                {markersFor.markers.length === 0 ? (
+                  <p className="text-gray-500">暂无标记</p>
+                ) : (
+                  markersFor.markers.map(marker => (
+                    <div key={marker.id} className="flex items-center justify-between">
+                      <span>
+                        <span className="font-mono">{formatDuration(marker.offset_secs)}</span>
+                        <span className="ml-2">{marker.label || '未命名标记'}</span>
+                      </span>
+                      <div className="flex space-x-3">
+                        {isClippable(file) && (
+                          <button onClick={() => handleClipMarker(file.id, marker)} title="截取标记前后各 30 秒" className="text-blue-500 hover:underline">剪辑</button>
+                        )}
+                        <button onClick={() => handleDeleteMarker(file.id, marker)} className="text-red-500 hover:underline">删除</button>
+                      </div>
+                    </div>
+                  ))
+                )}
+                {markersFor.markers.length > 0 && (
+                  <div className="flex space-x-3 pt-1">
+                    <button onClick={() => handleExportChapters(file, 'vtt')} className="text-blue-500 hover:underline">导出 WebVTT 章节</button>
+                    <button onClick={() => handleExportChapters(file, 'ffmetadata')} className="text-blue-500 hover:underline">导出 ffmetadata 章节</button>
+                  </div>
+                )}
+              </div>
+            )}
+            {historyFor?.id === file.id && (
+              <div className="mt-2 p-3 border rounded text-sm dark:border-gray-700 space-y-1">
+                {historyFor.history.length === 0 ? (
+                  <p className="text-gray-500">暂无状态记录</p>
+                ) : (
+                  historyFor.history.map((change, i) => (
+                    <div key={i} className="flex items-center justify-between">
+                      <span>
+                        {change.from_status ? `${RECORDING_STATUS_LABELS[change.from_status] || change.from_status} → ` : ''}
+                        <span className="font-medium">{RECORDING_STATUS_LABELS[change.to_status] || change.to_status}</span>
+                        {change.reason && <span className="ml-2 text-xs text-gray-500">{change.reason}</span>}
+                      </span>
+                      <span className="text-xs text-gray-500">{new Date(change.created_at).toLocaleString()}</span>
+                    </div>
+                  ))
+                )}
+              </div>
+            )}
+            {jobsFor?.id === file.id && (
+              <div className="mt-2 p-3 border rounded text-sm dark:border-gray-700 space-y-1">
+                {jobsFor.jobs.length === 0 ? (
+                  <p className="text-gray-500">暂无处理任务</p>
+                ) : (
+                  jobsFor.jobs.map(job => (
+                    <div key={job.id} className="flex items-center justify-between">
+                      <div>
+                        <span className="font-medium">{JOB_KIND_LABELS[job.kind] || job.kind}</span>
+                        <span className="ml-2 text-xs text-gray-500">
+                          {JOB_STATUS_LABELS[job.status] || job.status}
+                          {job.status === 'running' && ` ${Math.round(job.progress * 100)}%`}
+                          {job.attempts > 1 && ` · 第 ${job.attempts}/${job.max_attempts} 次尝试`}
+                        </span>
+                        {job.error && <p className="text-xs text-red-500">{job.error}</p>}
+                      </div>
+                      <div className="flex space-x-3">
+                        {(job.status === 'failed' || job.status === 'cancelled') && (
+                          <button onClick={() => handleJobAction(file.id, job, 'retry')} className="text-blue-500 hover:underline">重试</button>
+                        )}
+                        {(job.status === 'queued' || job.status === 'running') && (
+                          <button onClick={() => handleJobAction(file.id, job, 'cancel')} className="text-red-500 hover:underline">取消</button>
+                        )}
+                      </div>
+                    </div>
+                  ))
+                )}
+                <div className="flex space-x-3 pt-1">
+                  <button onClick={() => loadJobs(file.id)} className="text-blue-500 hover:underline">刷新</button>
+                  <button onClick={() => handleRunPipeline(file)} className="text-blue-500 hover:underline">重新运行处理流程</button>
+                </div>
+              </div>
+            )}
+            </div>
           ))}
+          {nextCursor && (
+            <button onClick={loadMore} className="w-full text-sm text-blue-500 hover:underline py-2">加载更多</button>
+          )}
         </div>
       )}
+      </>
+      )}
     </div>
   );
 }
@@ -20,12 +20,25 @@ interface StopRequest {
 //   status: 'Pending' | 'Accepted' | 'Denied';
 // }
 
+interface Handoff {
+  recording_id: string;
+  url: string;
+  expires_at: string | null;
+  qr_png: string;
+}
+
 interface Announcement {
   id: string;
   content: string;
   created_at: string;
 }
 
+const formatOffset = (secs: number) => {
+  const total = Math.floor(secs);
+  const mmss = `${String(Math.floor((total % 3600) / 60)).padStart(2, '0')}:${String(total % 60).padStart(2, '0')}`;
+  return total >= 3600 ? `${Math.floor(total / 3600)}:${mmss}` : mmss;
+};
+
 export default function UserDashboard() {
   const [isRecording, setIsRecording] = useState(false);
   const [taskType, setTaskType] = useState<'idle' | 'record' | 'stream'>('idle');
@@ -37,6 +50,7 @@ export default function UserDashboard() {
   const [activeUsers, setActiveUsers] = useState<ActiveUser[]>([]);
   const [notification, setNotification] = useState<StopRequest | null>(null);
   const [announcements, setAnnouncements] = useState<Announcement[]>([]);
+  const [handoff, setHandoff] = useState<Handoff[]>([]);
   const [sentRequest, setSentRequest] = useState<{ targetId: string, status: string } | null>(null);
 
   const token = localStorage.getItem('token');
@@ -171,10 +185,12 @@ export default function UserDashboard() {
     setLoading(true);
     setError('');
     setStatusMsg('');
+    setHandoff([]);
     try {
-      await axios.post(`${baseUrl}/api/recorder/stop`, {}, {
+      const res = await axios.post(`${baseUrl}/api/recorder/stop`, {}, {
         headers: { Authorization: `Bearer ${token}` }
       });
+      setHandoff(res.data?.handoff || []);
       setIsRecording(false);
       setTaskType('idle');
       setStatusMsg('已停止');
@@ -190,6 +206,25 @@ export default function UserDashboard() {
     }
   };
 
+  const handleMarker = async () => {
+    setError('');
+    const label = prompt('标记名称（可留空）');
+    if (label === null) return;
+    try {
+      const res = await axios.post(`${baseUrl}/api/recorder/marker`, { label }, {
+        headers: { Authorization: `Bearer ${token}` }
+      });
+      setStatusMsg(`已在 ${formatOffset(res.data.offset_secs)} 处添加标记`);
+    } catch (error) {
+      console.error(error);
+      if (axios.isAxiosError(error)) {
+        setError(typeof error.response?.data === 'string' ? error.response?.data : '添加标记失败');
+      } else {
+        setError('添加标记失败');
+      }
+    }
+  };
+
   const handleRespondStop = async (accept: boolean) => {
     if (!notification) return;
     try {
@@ -258,16 +293,46 @@ export default function UserDashboard() {
               </button>
             </>
           ) : (
-            <button 
-              onClick={handleStop} 
-              disabled={loading}
-              className="bg-gray-600 hover:bg-gray-700 text-white font-bold py-2 px-6 rounded"
-            >
-              {loading ? '停止中...' : (taskType === 'stream' ? '停止推流' : '停止录制')}
-            </button>
+            <>
+              <button 
+                onClick={handleStop} 
+                disabled={loading}
+                className="bg-gray-600 hover:bg-gray-700 text-white font-bold py-2 px-6 rounded"
+              >
+                {loading ? '停止中...' : (taskType === 'stream' ? '停止推流' : '停止录制')}
+              </button>
+              {taskType === 'record' && (
+                <button
+                  onClick={handleMarker}
+                  className="bg-yellow-500 hover:bg-yellow-600 text-white font-bold py-2 px-6 rounded"
+                >
+                  添加标记
+                </button>
+              )}
+            </>
           )}
         </div>
 
+        {/* QR hand-off: scan to get the recording on a phone */}
+        {handoff.length > 0 && (
+          <div className="mt-6 border rounded p-4 dark:border-gray-700">
+            <div className="flex justify-between items-center mb-3">
+              <p className="font-bold">扫码获取录像</p>
+              <button onClick={() => setHandoff([])} className="text-sm text-gray-500 hover:text-gray-700">关闭</button>
+            </div>
+            <div className="flex flex-wrap gap-6">
+              {handoff.map(h => (
+                <div key={h.recording_id} className="flex flex-col items-center">
+                  <img src={h.qr_png} alt="QR" className="w-56 h-56 bg-white" />
+                  {h.expires_at && (
+                    <p className="text-xs text-gray-500 mt-2">有效期至 {new Date(h.expires_at).toLocaleTimeString()}</p>
+                  )}
+                </div>
+              ))}
+            </div>
+          </div>
+        )}
+
         {/* Notification Modal/Alert */}
         {notification && (
           <div className="mt-6 border-l-4 border-yellow-500 bg-yellow-100 p-4 dark:bg-yellow-900 dark:text-yellow-100">